@@ -0,0 +1,64 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use expense_tracker::models::{self, Expense};
+use expense_tracker::storage::{ExpenseStore, SqliteStore};
+use rusqlite::params;
+
+const EXISTING_ROWS: usize = 100_000;
+
+fn sample_expense(i: usize) -> Expense {
+    Expense {
+        id: 0,
+        date: format!("2023-{:02}-{:02}", i % 12 + 1, i % 28 + 1),
+        amount: (i % 500) as f32 + 0.99,
+        category: "Food".to_string(),
+        description: format!("Benchmark expense {}", i),
+        payment_method: "Card".to_string(),
+        tags: String::new(),
+        payee_id: None,
+        time: None,
+    }
+}
+
+// Fills the table in a single transaction so setup takes seconds rather than minutes
+fn populate(path: &str) {
+    let mut conn = models::open_database(path).unwrap();
+    let tx = conn.transaction().unwrap();
+    {
+        let mut stmt = tx
+            .prepare("INSERT INTO expenses (date, amount, category, description, payment_method) VALUES (?1, ?2, ?3, ?4, ?5)")
+            .unwrap();
+        for i in 0..EXISTING_ROWS {
+            let expense = sample_expense(i);
+            stmt.execute(params![
+                expense.date,
+                expense.amount,
+                expense.category,
+                expense.description,
+                expense.payment_method
+            ])
+            .unwrap();
+        }
+    }
+    tx.commit().unwrap();
+}
+
+fn add_expense_benchmark(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bench.db");
+    let path = path.to_str().unwrap();
+    let mut store = SqliteStore::open(path).unwrap();
+    populate(path);
+
+    // What every add used to cost on top of the insert, before the list was updated in place.
+    // Runs first, while the table still holds exactly 100k rows.
+    c.bench_function("reload all expenses with 100k rows", |b| {
+        b.iter(|| store.get_expenses().unwrap())
+    });
+    let expense = sample_expense(EXISTING_ROWS);
+    c.bench_function("add_expense with 100k rows", |b| {
+        b.iter(|| store.add_expense(&expense).unwrap())
+    });
+}
+
+criterion_group!(benches, add_expense_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,135 @@
+use crate::models::{Expense, Payee, ReviewStatus};
+use crate::payees;
+use std::collections::HashMap;
+
+// Payee history is more specific, so it needs fewer charges before it is trusted
+const MIN_PAYEE_HISTORY: usize = 3;
+const MIN_CATEGORY_HISTORY: usize = 5;
+// Only the most recent charges count, so prices that drift over years aren't flagged
+const HISTORY_WINDOW: usize = 24;
+// Modified z-score above which a charge is flagged (Iglewicz and Hoaglin's usual cut-off)
+const MODIFIED_Z_THRESHOLD: f32 = 3.5;
+// Scales the median absolute deviation to a standard deviation for normal data
+const MAD_SCALE: f32 = 1.4826;
+// When every earlier charge was the same amount there is no spread to measure against,
+// so anything this many times the usual amount is flagged instead
+const FLAT_HISTORY_RATIO: f32 = 1.5;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnomalyBasis {
+    Payee(String),
+    Category(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Anomaly {
+    pub expense_id: i32,
+    pub amount: f32,
+    pub basis: AnomalyBasis,
+    pub typical: f32, // median of the earlier charges
+}
+
+impl Anomaly {
+    pub fn reason(&self) -> String {
+        let basis = match &self.basis {
+            AnomalyBasis::Payee(name) => format!("at {}", name),
+            AnomalyBasis::Category(name) => format!("in {}", name),
+        };
+        format!(
+            "{:.1}x the usual {:.2} {}",
+            self.amount / self.typical,
+            self.typical,
+            basis
+        )
+    }
+}
+
+// Flags charges well above what the same payee (or, without enough payee history, the same
+// category) usually costs. Each charge is only compared with the ones before it. Charges the
+// user confirmed as unusual are left out of later baselines.
+pub fn detect_anomalies(
+    expenses: &[Expense],
+    payees: &[Payee],
+    reviews: &HashMap<i32, ReviewStatus>,
+) -> Vec<Anomaly> {
+    let mut ordered: Vec<&Expense> = expenses.iter().collect();
+    ordered.sort_by(|a, b| a.date.cmp(&b.date).then(a.id.cmp(&b.id)));
+
+    let mut payee_history: HashMap<i32, Vec<f32>> = HashMap::new();
+    let mut category_history: HashMap<&str, Vec<f32>> = HashMap::new();
+    let mut anomalies = Vec::new();
+    for expense in ordered {
+        let payee = expense
+            .payee_id
+            .and_then(|id| Some((id, payees::payee_name(payees, Some(id))?)));
+        let by_payee = payee.and_then(|(id, name)| {
+            let history = payee_history.get(&id)?;
+            (history.len() >= MIN_PAYEE_HISTORY)
+                .then(|| (AnomalyBasis::Payee(name.to_string()), history))
+        });
+        let basis = by_payee.or_else(|| {
+            let history = category_history.get(expense.category.as_str())?;
+            (history.len() >= MIN_CATEGORY_HISTORY)
+                .then(|| (AnomalyBasis::Category(expense.category.clone()), history))
+        });
+        if let Some((basis, history)) = basis {
+            if let Some(typical) = unusual_against(expense.amount, history) {
+                anomalies.push(Anomaly {
+                    expense_id: expense.id,
+                    amount: expense.amount,
+                    basis,
+                    typical,
+                });
+            }
+        }
+
+        if reviews.get(&expense.id) == Some(&ReviewStatus::Confirmed) {
+            continue;
+        }
+        if let Some((id, _)) = payee {
+            push_recent(payee_history.entry(id).or_default(), expense.amount);
+        }
+        push_recent(
+            category_history.entry(&expense.category).or_default(),
+            expense.amount,
+        );
+    }
+    anomalies
+}
+
+// The median of the history when the amount is unusually high against it
+fn unusual_against(amount: f32, history: &[f32]) -> Option<f32> {
+    let typical = median(history);
+    if typical <= 0.0 || amount <= typical {
+        return None;
+    }
+    let deviations: Vec<f32> = history
+        .iter()
+        .map(|value| (value - typical).abs())
+        .collect();
+    let spread = median(&deviations) * MAD_SCALE;
+    let unusual = if spread == 0.0 {
+        amount >= typical * FLAT_HISTORY_RATIO
+    } else {
+        (amount - typical) / spread >= MODIFIED_Z_THRESHOLD
+    };
+    unusual.then_some(typical)
+}
+
+fn push_recent(history: &mut Vec<f32>, amount: f32) {
+    if history.len() == HISTORY_WINDOW {
+        history.remove(0);
+    }
+    history.push(amount);
+}
+
+fn median(values: &[f32]) -> f32 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    }
+}
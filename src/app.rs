@@ -1,12 +1,35 @@
+use crate::anomalies::{self, Anomaly};
+use crate::calendar::{self, CalendarMonth};
+use crate::classifier::{self, CategoryClassifier};
+use crate::comparison::{self, ComparisonOptions, ComparisonRow};
+use crate::debts::{self, DebtState, PayoffPlan, Strategy};
+use crate::forecast::{self, MonthForecast, MonthProjection};
+use crate::goals::{self, GoalProgress};
 use crate::models;
-use crate::models::add_user;
-use crate::models::{add_expense, Expense, User};
+use crate::models::{
+    Asset, Attachment, CategoryRule, Debt, Expense, Goal, NetWorthSnapshot, Payee,
+    RecurringSchedule, ReviewStatus, User,
+};
+use crate::networth;
+use crate::patterns::PatternView;
+use crate::payees;
+use crate::periods::Period;
+use crate::receipts::ReceiptSuggestion;
+use crate::rules::{self, RuleChange};
+use crate::statement::Statement;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::storage::ExpenseStore;
+#[cfg(target_arch = "wasm32")]
+use crate::storage::HttpStore;
+use crate::subscriptions::{self, Subscription};
 use crate::ui;
-use crate::ui::create_monthly_spending_chart;
-use crate::ui::load_texture_from_memory;
+use crate::worker::{Job, JobResult, Worker};
+use chrono::{NaiveDate, NaiveTime};
 use eframe::egui;
 use image::{io::Reader as ImageReader, GenericImageView};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 pub fn load_image_to_memory(file_path: &str) -> Result<(Vec<u8>, [u32; 2]), image::ImageError> {
     let img = ImageReader::open(file_path)?.decode()?;
@@ -14,6 +37,101 @@ pub fn load_image_to_memory(file_path: &str) -> Result<(Vec<u8>, [u32; 2]), imag
     Ok((img.to_rgba8().into_raw(), [dimensions.0, dimensions.1]))
 }
 
+// Shared by the signup form and the command-line `user create`
+pub fn is_password_valid(password: &str) -> bool {
+    let has_number = password.chars().any(|c| c.is_ascii_digit());
+    let has_symbol = password.chars().any(|c| !c.is_alphanumeric());
+    let has_min_length = password.len() >= 5;
+
+    has_number && has_symbol && has_min_length
+}
+
+#[derive(Default)]
+pub struct RuleForm {
+    pub description_pattern: String,
+    pub is_regex: bool,
+    pub min_amount: String,
+    pub max_amount: String,
+    pub payment_method: String,
+    pub category: String,
+    pub tags: String,
+    pub rename_to: String,
+}
+
+#[derive(Default)]
+pub struct PayeeForm {
+    pub new_name: String,
+    pub new_alias: String,
+    pub alias_payee_id: Option<i32>,
+    pub merge_source_id: Option<i32>,
+    pub merge_target_id: Option<i32>,
+}
+
+pub struct GoalForm {
+    pub name: String,
+    pub target_amount: String,
+    pub target_date: String,
+    pub category: String,
+    pub tag: String,
+}
+
+impl Default for GoalForm {
+    fn default() -> Self {
+        GoalForm {
+            name: String::new(),
+            target_amount: String::new(),
+            target_date: String::new(),
+            category: "Savings and Investments".to_string(),
+            tag: String::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DebtForm {
+    pub name: String,
+    pub balance: String,
+    pub apr: String,
+    pub minimum_payment: String,
+    pub tag: String,
+    pub monthly_budget: String, // for the payoff plans; empty pays only the minimums
+}
+
+#[derive(Default)]
+pub struct AssetForm {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Default)]
+pub struct PeriodForm {
+    pub from: String,
+    pub to: String,
+    pub rolling_days: String,
+}
+
+// One level of chart drill-down; the list and charts show only expenses matching every level
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrillFilter {
+    Category(String),
+    Month(String), // YYYY-MM
+}
+
+impl DrillFilter {
+    pub fn matches(&self, expense: &Expense) -> bool {
+        match self {
+            DrillFilter::Category(category) => expense.category == *category,
+            DrillFilter::Month(month) => expense.date.starts_with(month.as_str()),
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        match self {
+            DrillFilter::Category(label) | DrillFilter::Month(label) => label,
+        }
+    }
+}
+
 pub struct MyApp {
     pub expense_name: String,
     pub expense_amount: String,
@@ -21,6 +139,7 @@ pub struct MyApp {
     pub category: String,
     pub expenses: Vec<Expense>,
     pub expense_date: String,
+    pub expense_time: String, // optional HH:MM
     pub warning_message: Option<String>,
     pub current_user_id: Option<i32>,
     pub username: String,
@@ -30,14 +149,71 @@ pub struct MyApp {
     pub new_username: String,
     pub new_password: String,
     pub showing_signup: bool,
-    pub image_texture: Option<egui::TextureHandle>,
     pub show_monthly_trends: bool,
     pub show_yearly_comparison: bool,
     pub show_monthly_spending: bool,
+    pub show_period_comparison: bool,
+    pub show_spending_patterns: bool,
+    pub receipts_expense_id: Option<i32>,
+    pub receipts: Vec<(Attachment, Option<egui::TextureHandle>)>,
+    pub receipt_preview: Option<egui::TextureHandle>,
+    pub receipt_path: String,
+    pub scan_receipt_path: String,
+    pub receipt_suggestion: Option<ReceiptSuggestion>,
+    pub rules: Vec<CategoryRule>,
+    pub rule_form: RuleForm,
+    pub rule_changes: Option<Vec<RuleChange>>,
+    pub classifier: CategoryClassifier,
+    pub category_suggested: bool,
+    pub payees: Vec<Payee>,
+    pub payee_form: PayeeForm,
+    pub import_path: String,
+    pub statement_path: String,
+    pub worker: Worker,
+    pub drill_down: Vec<DrillFilter>,
+    pub period: Period,
+    pub period_form: PeriodForm,
+    pub comparison: ComparisonOptions,
+    pub pattern_view: PatternView,
+    pub anomalies: Vec<Anomaly>,
+    pub anomaly_reviews: HashMap<i32, ReviewStatus>,
+    pub schedules: Vec<RecurringSchedule>,
+    pub goals: Vec<Goal>,
+    pub goal_form: GoalForm,
+    pub debts: Vec<Debt>,
+    pub debt_form: DebtForm,
+    pub debt_strategy: Strategy,
+    pub assets: Vec<Asset>,
+    pub asset_form: AssetForm,
+    pub net_worth_snapshots: Vec<NetWorthSnapshot>,
+    pub calendar_month: Option<NaiveDate>, // any day of the month shown; None is the current month
+    pub calendar_day: Option<NaiveDate>,
+    pub server_mode: bool, // expenses are on a server, so the local-only features are off
+    pub attachments_dir: Option<PathBuf>, // where receipt copies live, for the local database only
 }
 
+// Shown instead of the features that only work against the local database
+pub const LOCAL_ONLY_MESSAGE: &str = "Receipts, rules, payees, reviews, subscriptions, goals, debts and net worth are kept in the local database, so they aren't available while connected to a server.";
+
 impl MyApp {
-    pub fn new(egui_ctx: &egui::Context) -> Self {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_store(egui_ctx: &egui::Context, store: Box<dyn ExpenseStore>) -> Self {
+        let server_mode = store.is_remote();
+        let attachments_dir = store.attachments_dir();
+        Self::with_worker(
+            Worker::spawn(store, egui_ctx.clone()),
+            server_mode,
+            attachments_dir,
+        )
+    }
+
+    // The browser build always keeps its expenses on a server
+    #[cfg(target_arch = "wasm32")]
+    pub fn with_server(egui_ctx: &egui::Context, store: HttpStore) -> Self {
+        Self::with_worker(Worker::in_browser(store, egui_ctx.clone()), true, None)
+    }
+
+    fn with_worker(worker: Worker, server_mode: bool, attachments_dir: Option<PathBuf>) -> Self {
         let mut app = MyApp {
             expense_name: String::new(),
             expense_amount: String::new(),
@@ -45,6 +221,7 @@ impl MyApp {
             category: String::new(),
             expenses: Vec::new(),
             expense_date: String::new(),
+            expense_time: String::new(),
             warning_message: None,
             current_user_id: None,
             username: String::new(),
@@ -54,73 +231,535 @@ impl MyApp {
             new_username: String::new(),
             new_password: String::new(),
             showing_signup: false,
-            image_texture: None,
             show_monthly_trends: false,
             show_yearly_comparison: false,
             show_monthly_spending: false,
+            show_period_comparison: false,
+            show_spending_patterns: false,
+            receipts_expense_id: None,
+            receipts: Vec::new(),
+            receipt_preview: None,
+            receipt_path: String::new(),
+            scan_receipt_path: String::new(),
+            receipt_suggestion: None,
+            rules: Vec::new(),
+            rule_form: RuleForm::default(),
+            rule_changes: None,
+            classifier: CategoryClassifier::default(),
+            category_suggested: false,
+            payees: Vec::new(),
+            payee_form: PayeeForm::default(),
+            import_path: String::new(),
+            statement_path: String::new(),
+            worker,
+            drill_down: Vec::new(),
+            period: Period::AllTime,
+            period_form: PeriodForm::default(),
+            comparison: ComparisonOptions::default(),
+            pattern_view: PatternView::HourOfDay,
+            anomalies: Vec::new(),
+            anomaly_reviews: HashMap::new(),
+            schedules: Vec::new(),
+            goals: Vec::new(),
+            goal_form: GoalForm::default(),
+            debts: Vec::new(),
+            debt_form: DebtForm::default(),
+            debt_strategy: Strategy::Avalanche,
+            assets: Vec::new(),
+            asset_form: AssetForm::default(),
+            net_worth_snapshots: Vec::new(),
+            calendar_month: None,
+            calendar_day: None,
+            server_mode,
+            attachments_dir,
         };
+        // Payees come first so the expenses can be checked for anomalies once they arrive
+        app.worker.submit(Job::LoadUserData(None));
         app.load_expenses();
-        app.update_monthly_spending_chart(egui_ctx);
         app
     }
 
     pub fn calculate_category_totals(&self) -> HashMap<String, f32> {
-        let mut category_totals = HashMap::new();
-        for expense in &self.expenses {
-            let amount = category_totals
-                .entry(expense.category.clone())
-                .or_insert(0.0);
-            *amount += expense.amount;
-        }
-        category_totals
-    }
-
-    pub fn update_chart(&mut self, egui_ctx: &egui::Context) {
-        self.update_monthly_spending_chart(egui_ctx); // This already recalculates and updates the chart
-        egui_ctx.request_repaint(); // Request the UI to repaint after updating the chart
-    }
-
-    // In MyApp struct in app.rs
-    pub fn update_monthly_spending_chart(&mut self, egui_ctx: &egui::Context) {
-        let monthly_spending = self.calculate_category_totals();
-        match create_monthly_spending_chart(&monthly_spending) {
-            Ok(_) => {
-                if let Ok((image_data, image_size)) = load_image_to_memory("chart.png") {
-                    let image_size_usize = [image_size[0] as usize, image_size[1] as usize];
-
-                    // Ensure we use a unique identifier for the texture to avoid caching issues
-                    let texture_id = format!(
-                        "chart_texture_{}",
-                        std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap()
-                            .as_millis()
-                    );
-
-                    // Dispose of the old texture and create a new one
-                    let texture_id_clone = texture_id.clone();
-                    self.image_texture = Some(load_texture_from_memory(
-                        egui_ctx,
-                        &image_data,
-                        image_size_usize,
-                        texture_id_clone,
-                    ));
+        ui::calculate_category_totals(&self.expenses)
+    }
+
+    pub fn calculate_payee_totals(&self) -> HashMap<String, f32> {
+        payees::calculate_payee_totals(&self.expenses, &self.payees)
+    }
+
+    // Clicking a slice or bar narrows the current view; drilling into a category or month
+    // again replaces that level (and everything below it) instead of stacking a second one
+    pub fn drill_into(&mut self, filter: DrillFilter) {
+        let same_kind =
+            |level: &DrillFilter| std::mem::discriminant(level) == std::mem::discriminant(&filter);
+        if let Some(depth) = self.drill_down.iter().position(same_kind) {
+            self.drill_down.truncate(depth);
+        }
+        self.drill_down.push(filter);
+    }
+
+    // Breadcrumb navigation; depth 0 goes back to all expenses
+    pub fn drill_up_to(&mut self, depth: usize) {
+        self.drill_down.truncate(depth);
+    }
+
+    // What the analytics section aggregates: the selected period, narrowed by every drill-down level
+    pub fn drilled_expenses(&self) -> Cow<'_, [Expense]> {
+        if self.drill_down.is_empty() && self.period == Period::AllTime {
+            return Cow::Borrowed(&self.expenses);
+        }
+        Cow::Owned(
+            self.expenses
+                .iter()
+                .filter(|expense| self.is_drilled(expense))
+                .cloned()
+                .collect(),
+        )
+    }
+
+    pub fn is_drilled(&self, expense: &Expense) -> bool {
+        self.period.contains(&expense.date)
+            && self.drill_down.iter().all(|level| level.matches(expense))
+    }
+
+    // The selected period against its baseline, within the drill-down; None for all time
+    pub fn comparison_rows(&self) -> Option<(Period, Vec<ComparisonRow>)> {
+        if self.period == Period::AllTime {
+            return None;
+        }
+        let baseline = self.comparison.baseline.period(&self.period);
+        let expenses: Vec<Expense> = self
+            .expenses
+            .iter()
+            .filter(|expense| self.drill_down.iter().all(|level| level.matches(expense)))
+            .cloned()
+            .collect();
+        let rows = comparison::compare_periods(
+            &expenses,
+            &self.period,
+            &baseline,
+            self.comparison.group_by,
+        );
+        Some((baseline, rows))
+    }
+
+    // Month-end projection for the month containing today, within any category drill-down.
+    // None when the selected period, or a month drilled into, leaves today out.
+    pub fn month_forecast(&self, today: NaiveDate) -> Option<MonthForecast> {
+        let expenses = self.forecast_expenses(today)?;
+        Some(forecast::forecast_month(&expenses, today))
+    }
+
+    // The month-end projection followed by the next few months, for the trend chart; empty
+    // whenever month_forecast is None
+    pub fn projected_months(&self, today: NaiveDate) -> Vec<MonthProjection> {
+        self.forecast_expenses(today)
+            .map(|expenses| forecast::project_months(&expenses, today, forecast::FOLLOWING_MONTHS))
+            .unwrap_or_default()
+    }
+
+    fn forecast_expenses(&self, today: NaiveDate) -> Option<Vec<Expense>> {
+        let month = today.format("%Y-%m").to_string();
+        let other_month = self
+            .drill_down
+            .iter()
+            .any(|level| matches!(level, DrillFilter::Month(drilled) if *drilled != month));
+        if other_month || !self.period.contains(&today.format("%Y-%m-%d").to_string()) {
+            return None;
+        }
+        // Earlier months are the history the projection is built from, so only categories narrow it
+        let expenses: Vec<Expense> = self
+            .expenses
+            .iter()
+            .filter(|expense| {
+                self.drill_down
+                    .iter()
+                    .all(|level| matches!(level, DrillFilter::Month(_)) || level.matches(expense))
+            })
+            .cloned()
+            .collect();
+        Some(expenses)
+    }
+
+    pub fn apply_custom_period(&mut self) {
+        let parse = |text: &str| NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d");
+        match (parse(&self.period_form.from), parse(&self.period_form.to)) {
+            (Ok(from), Ok(to)) if from <= to => {
+                self.period = Period::Custom { from, to };
+                self.warning_message = None;
+            }
+            (Ok(_), Ok(_)) => {
+                self.warning_message = Some("The range must not end before it starts".to_string());
+            }
+            _ => {
+                self.warning_message = Some("Enter both dates as YYYY-MM-DD".to_string());
+            }
+        }
+    }
+
+    pub fn apply_rolling_period(&mut self, today: NaiveDate) {
+        match self.period_form.rolling_days.trim().parse::<u32>() {
+            Ok(days) if days > 0 => {
+                self.period = Period::rolling(days, today);
+                self.warning_message = None;
+            }
+            _ => {
+                self.warning_message = Some("Enter a number of days above zero".to_string());
+            }
+        }
+    }
+
+    // Applies finished background jobs without blocking; called at the start of every frame
+    pub fn poll_worker(&mut self) {
+        for result in self.worker.poll() {
+            self.apply_job_result(result);
+        }
+    }
+
+    // Blocks until every queued job, including ones queued by the results, has finished.
+    // For the terminal UI and tests; the GUI uses poll_worker.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn wait_for_worker(&mut self) {
+        while let Some(result) = self.worker.wait() {
+            self.apply_job_result(result);
+        }
+    }
+
+    fn apply_job_result(&mut self, result: JobResult) {
+        match result {
+            JobResult::Authenticated(Ok(Some(user))) => {
+                self.is_logged_in = true;
+                self.current_user_id = Some(user.id);
+                // The user's debts and assets arrive first, for the net worth check on the expenses
+                self.worker.submit(Job::LoadUserData(Some(user.id)));
+                self.current_user = Some(user);
+                self.load_expenses(); // Load expenses specific to this user
+            }
+            JobResult::Authenticated(Ok(None)) => {
+                self.warning_message = Some("Invalid username or password".to_string());
+            }
+            JobResult::Authenticated(Err(e)) => {
+                self.warning_message = Some(format!("Failed to log in: {}", e));
+            }
+            JobResult::Registered(Ok(())) => {
+                self.warning_message = Some("User successfully registered!".to_string());
+            }
+            JobResult::Registered(Err(models::MyError::InvalidInput(message))) => {
+                self.warning_message = Some(message);
+            }
+            JobResult::Registered(Err(e)) => {
+                self.warning_message = Some(format!("Failed to register: {:?}", e));
+            }
+            JobResult::ExpensesLoaded(Ok(expenses)) => {
+                self.expenses = expenses;
+                self.expenses_changed();
+                // Debt balances depend on the logged payments, so wait for them
+                self.record_net_worth_if_due(chrono::Local::now().date_naive());
+            }
+            JobResult::ExpenseAdded(Ok(expense)) => {
+                // Update the loaded list in place rather than re-reading the whole table
+                self.classifier.add_example(
+                    &expense.description,
+                    &expense.payment_method,
+                    &expense.category,
+                );
+                self.expenses.push(expense);
+                self.refresh_anomalies();
+            }
+            JobResult::ExpenseDeleted(Ok(expense_id)) => {
+                self.expenses.retain(|expense| expense.id != expense_id);
+                if self.receipts_expense_id == Some(expense_id) {
+                    self.close_receipts();
+                }
+                self.expenses_changed();
+            }
+            JobResult::ExpensesUpdated(Ok(updated)) => {
+                for expense in updated {
+                    if let Some(loaded) = self.expenses.iter_mut().find(|e| e.id == expense.id) {
+                        *loaded = expense;
+                    }
+                }
+                self.expenses_changed();
+            }
+            JobResult::Imported(Ok(imported)) => {
+                self.warning_message = Some(format!("Imported {} expenses", imported.len()));
+                self.import_path.clear();
+                self.expenses.extend(imported);
+                self.expenses_changed();
+            }
+            JobResult::Imported(Err(e)) => {
+                self.warning_message = Some(format!("Failed to import: {}", e));
+                // A store that saves one expense at a time may have kept some of them
+                self.load_expenses();
+            }
+            JobResult::UserDataLoaded(Ok(data)) => {
+                self.payees = data.payees;
+                self.anomaly_reviews = data.anomaly_reviews;
+                self.schedules = data.schedules;
+                self.rules = data.rules;
+                self.rule_changes = None;
+                self.goals = data.goals;
+                self.debts = data.debts;
+                self.assets = data.assets;
+                self.net_worth_snapshots = data.net_worth_snapshots;
+                self.refresh_anomalies();
+            }
+            JobResult::ReceiptScanned(Ok(suggestion)) if suggestion.is_empty() => {
+                self.warning_message = Some(
+                    "No details could be read from the receipt. Scanned PDFs without a text layer can't be read"
+                        .to_string(),
+                );
+            }
+            JobResult::ReceiptScanned(Ok(suggestion)) => {
+                self.warning_message = None;
+                self.receipt_suggestion = Some(suggestion);
+            }
+            JobResult::ReceiptScanned(Err(e)) => {
+                self.warning_message = Some(format!("Failed to read receipt: {}", e));
+            }
+            JobResult::StatementSaved {
+                label,
+                path,
+                result,
+            } => {
+                self.warning_message = Some(match result {
+                    Ok(()) => format!("Saved the {} statement to {}", label, path.display()),
+                    Err(e) => format!("Failed to save statement: {}", e),
+                });
+            }
+            // The window may have been closed or moved to another expense in the meantime
+            JobResult::ReceiptsLoaded {
+                expense_id,
+                receipts,
+            } => {
+                if self.receipts_expense_id == Some(expense_id) {
+                    match receipts {
+                        Ok(receipts) => self.receipts = receipts,
+                        Err(e) => eprintln!("Failed to load receipts: {}", e),
+                    }
+                }
+            }
+            JobResult::ReceiptAttached(Ok(expense_id)) => {
+                self.receipt_path.clear();
+                self.warning_message = None;
+                self.reload_receipts(expense_id);
+            }
+            JobResult::ReceiptAttached(Err(e)) => {
+                self.warning_message = Some(format!("Failed to attach receipt: {}", e));
+            }
+            JobResult::ReceiptRemoved(Ok(expense_id)) => {
+                self.receipt_preview = None;
+                self.reload_receipts(expense_id);
+            }
+            JobResult::ReceiptRemoved(Err(e)) => eprintln!("Failed to remove receipt: {}", e),
+            JobResult::ReceiptPreviewed {
+                attachment_id,
+                texture,
+            } => {
+                let still_listed = self
+                    .receipts
+                    .iter()
+                    .any(|(attachment, _)| attachment.id == attachment_id);
+                match texture {
+                    Ok(texture) if still_listed => self.receipt_preview = Some(texture),
+                    Ok(_) => {}
+                    Err(e) => eprintln!("Failed to preview receipt: {}", e),
                 }
             }
-            Err(e) => eprintln!("Error creating chart: {:?}", e),
+            JobResult::AnomalyReviewed {
+                expense_id,
+                status,
+                result: Ok(()),
+            } => {
+                self.anomaly_reviews.insert(expense_id, status);
+                self.refresh_anomalies();
+            }
+            JobResult::AnomalyReviewed { result: Err(e), .. } => {
+                self.warning_message = Some(format!("Failed to save review: {}", e));
+            }
+            JobResult::ScheduleAdded(Ok(schedules)) => {
+                self.schedules = schedules;
+                self.warning_message = None;
+            }
+            JobResult::ScheduleAdded(Err(e)) => {
+                self.warning_message = Some(format!("Failed to add schedule: {}", e));
+            }
+            JobResult::ScheduleDeleted(Ok(schedules)) => self.schedules = schedules,
+            JobResult::DueChargesRecorded(Ok((recorded, schedules))) => {
+                self.warning_message =
+                    Some(format!("Recorded {} scheduled charges", recorded.len()));
+                self.expenses.extend(recorded);
+                self.expenses_changed();
+                self.schedules = schedules;
+            }
+            JobResult::DueChargesRecorded(Err(e)) => {
+                self.warning_message = Some(format!("Failed to record scheduled charges: {}", e));
+            }
+            JobResult::PayeeAdded(Ok((payee_id, payees))) => {
+                self.payees = payees;
+                self.payee_form.new_name.clear();
+                self.payee_form.alias_payee_id = Some(payee_id);
+                self.warning_message = None;
+            }
+            JobResult::PayeeAdded(Err(e)) => {
+                self.warning_message = Some(format!("Failed to add payee: {}", e));
+            }
+            JobResult::PayeeAliasAdded(Ok(payees)) => {
+                self.payees = payees;
+                self.payee_form.new_alias.clear();
+                self.warning_message = None;
+            }
+            JobResult::PayeeAliasAdded(Err(e)) => {
+                self.warning_message = Some(format!("Failed to add alias: {}", e));
+            }
+            JobResult::PayeeAliasDeleted(Ok(payees)) => self.payees = payees,
+            JobResult::PayeesMerged(Ok(payees)) => {
+                self.payees = payees;
+                self.payee_form.merge_source_id = None;
+                self.warning_message = None;
+                self.load_expenses();
+            }
+            JobResult::PayeesMerged(Err(e)) => {
+                self.warning_message = Some(format!("Failed to merge payees: {}", e));
+            }
+            JobResult::RuleAdded(Ok(rules)) => {
+                self.rules = rules;
+                self.rule_changes = None;
+                self.rule_form = RuleForm::default();
+                self.warning_message = None;
+            }
+            JobResult::RuleAdded(Err(e)) => {
+                self.warning_message = Some(format!("Failed to save rule: {}", e));
+            }
+            JobResult::RuleDeleted(Ok(rules)) | JobResult::RulesSwapped(Ok(rules)) => {
+                self.rules = rules;
+                self.rule_changes = None;
+            }
+            JobResult::GoalAdded(Ok(goals)) => {
+                self.goals = goals;
+                self.goal_form = GoalForm::default();
+                self.warning_message = None;
+            }
+            JobResult::GoalAdded(Err(e)) => {
+                self.warning_message = Some(format!("Failed to add goal: {}", e));
+            }
+            JobResult::GoalDeleted(Ok(goals)) => self.goals = goals,
+            JobResult::DebtAdded(Ok(debts)) => {
+                self.debts = debts;
+                self.debt_form = DebtForm {
+                    monthly_budget: std::mem::take(&mut self.debt_form.monthly_budget),
+                    ..DebtForm::default()
+                };
+                self.warning_message = None;
+                self.record_net_worth(chrono::Local::now().date_naive());
+            }
+            JobResult::DebtAdded(Err(e)) => {
+                self.warning_message = Some(format!("Failed to add debt: {}", e));
+            }
+            JobResult::DebtDeleted(Ok(debts)) => self.debts = debts,
+            JobResult::AssetSaved(Ok(assets)) => {
+                self.assets = assets;
+                self.asset_form = AssetForm::default();
+                self.warning_message = None;
+                self.record_net_worth(chrono::Local::now().date_naive());
+            }
+            JobResult::AssetSaved(Err(e)) => {
+                self.warning_message = Some(format!("Failed to save asset: {}", e));
+            }
+            JobResult::AssetDeleted(Ok(assets)) => {
+                self.assets = assets;
+                self.record_net_worth(chrono::Local::now().date_naive());
+            }
+            JobResult::NetWorthRecorded(Ok(snapshots)) => self.net_worth_snapshots = snapshots,
+            JobResult::NetWorthRecorded(Err(e)) => {
+                self.warning_message = Some(format!("Failed to save net worth: {}", e));
+            }
+            JobResult::ScheduleDeleted(Err(e)) => eprintln!("Failed to delete schedule: {}", e),
+            JobResult::PayeeAliasDeleted(Err(e)) => eprintln!("Failed to delete alias: {}", e),
+            JobResult::RuleDeleted(Err(e)) => eprintln!("Failed to delete rule: {}", e),
+            JobResult::RulesSwapped(Err(e)) => eprintln!("Failed to reorder rules: {}", e),
+            JobResult::GoalDeleted(Err(e)) => eprintln!("Failed to delete goal: {}", e),
+            JobResult::DebtDeleted(Err(e)) => eprintln!("Failed to delete debt: {}", e),
+            JobResult::AssetDeleted(Err(e)) => eprintln!("Failed to delete asset: {}", e),
+            JobResult::ExpensesLoaded(Err(e)) => eprintln!("Failed to load expenses: {}", e),
+            JobResult::UserDataLoaded(Err(e)) => eprintln!("Failed to load user data: {}", e),
+            JobResult::ExpenseAdded(Err(e)) => eprintln!("Failed to add expense: {}", e),
+            JobResult::ExpenseDeleted(Err(e)) => eprintln!("Failed to delete expense: {}", e),
+            JobResult::ExpensesUpdated(Err(e)) => eprintln!("Failed to update expense: {}", e),
+            JobResult::LoggedOut => {}
+        }
+    }
+
+    // The classifier can't forget an example, so retrain it from the loaded list
+    fn expenses_changed(&mut self) {
+        self.classifier = CategoryClassifier::train(&self.expenses);
+        self.refresh_anomalies();
+    }
+
+    fn refresh_anomalies(&mut self) {
+        self.anomalies =
+            anomalies::detect_anomalies(&self.expenses, &self.payees, &self.anomaly_reviews);
+    }
+
+    // Flagged charges the user hasn't looked at yet, newest first
+    pub fn needs_review(&self) -> Vec<(&Anomaly, &Expense)> {
+        let mut flagged: Vec<(&Anomaly, &Expense)> = self
+            .anomalies
+            .iter()
+            .filter(|anomaly| !self.anomaly_reviews.contains_key(&anomaly.expense_id))
+            .filter_map(|anomaly| {
+                let expense = self.expenses.iter().find(|e| e.id == anomaly.expense_id)?;
+                Some((anomaly, expense))
+            })
+            .collect();
+        flagged.sort_by(|a, b| b.1.date.cmp(&a.1.date));
+        flagged
+    }
+
+    // Dismissed charges stay part of the usual spending; confirmed ones are left out of it, so
+    // either way the list of flagged charges can change
+    pub fn review_anomaly(&mut self, expense_id: i32, status: ReviewStatus) {
+        self.worker
+            .submit(Job::SetAnomalyReview { expense_id, status });
+    }
+
+    pub fn subscriptions(&self, today: NaiveDate) -> Vec<Subscription> {
+        subscriptions::detect_subscriptions(&self.expenses, &self.payees, today)
+    }
+
+    pub fn schedule_subscription(&mut self, subscription: &Subscription) {
+        self.worker
+            .submit(Job::AddSchedule(subscription.to_schedule()));
+    }
+
+    pub fn delete_schedule(&mut self, schedule_id: i32) {
+        self.worker.submit(Job::DeleteSchedule(schedule_id));
+    }
+
+    pub fn due_schedule_count(&self, today: NaiveDate) -> usize {
+        self.schedules
+            .iter()
+            .filter_map(|schedule| subscriptions::due_dates(schedule, today))
+            .map(|(due, _)| due.len())
+            .sum()
+    }
+
+    // Adds an expense for every scheduled charge up to today and moves the schedules on, both
+    // in one go on the worker
+    pub fn record_due_schedules(&mut self, today: NaiveDate) {
+        let charges = subscriptions::due_charges(&self.schedules, today);
+        if !charges.is_empty() {
+            self.worker.submit(Job::RecordDueCharges(charges));
         }
-        egui_ctx.request_repaint();
     }
 
     pub fn process_login(&mut self, username: &str, password: &str) {
         self.warning_message = None;
-        if let Ok(Some(user)) = models::authenticate_user(username, password) {
-            self.is_logged_in = true;
-            self.current_user = Some(user);
-            self.load_expenses(); // Load expenses specific to this user
-        } else {
-            self.warning_message = Some("Invalid username or password".to_string());
-        }
+        // bcrypt is deliberately slow, so checking the password happens off the UI thread
+        self.worker.submit(Job::Authenticate {
+            username: username.to_string(),
+            password: password.to_string(),
+        });
     }
 
     pub fn logout(&mut self) {
@@ -129,66 +768,39 @@ impl MyApp {
         self.username.clear();
         self.password.clear();
         self.current_user_id = None;
+        self.worker.submit(Job::Logout);
         // Clear any other user-specific data if necessary
+        self.rules.clear();
+        self.rule_form = RuleForm::default();
+        self.rule_changes = None;
+        self.goals.clear();
+        self.goal_form = GoalForm::default();
+        self.debts.clear();
+        self.debt_form = DebtForm::default();
+        self.assets.clear();
+        self.asset_form = AssetForm::default();
+        self.net_worth_snapshots.clear();
+        self.drill_down.clear();
         self.showing_signup = true;
     }
 
-    fn is_password_valid(&self, password: &str) -> bool {
-        let has_number = password.chars().any(|c| c.is_ascii_digit());
-        let has_symbol = password.chars().any(|c| !c.is_alphanumeric());
-        let has_min_length = password.len() >= 5;
-
-        has_number && has_symbol && has_min_length
-    }
-
     pub fn process_signup(&mut self) {
         self.warning_message = None;
 
         // Check if the username and password fields are not empty
         if self.new_username.is_empty() || self.new_password.is_empty() {
             self.warning_message = Some("Username and password cannot be empty".to_string());
-        } else if self.validate_and_register_user().is_err() {
-            // Error handling is done within `validate_and_register_user`
-        }
-    }
-
-    fn validate_and_register_user(&mut self) -> Result<(), ()> {
-        // Check if the username already exists
-        if models::is_username_unique(&self.new_username).unwrap_or(false) {
-            // Validate password complexity
-            if self.is_password_valid(&self.new_password) {
-                self.register_user()
-            } else {
-                self.warning_message = Some(
-                    "Password must be at least 5 characters long, include a number and a symbol"
-                        .to_string(),
-                );
-                Err(())
-            }
+        } else if !is_password_valid(&self.new_password) {
+            self.warning_message = Some(
+                "Password must be at least 5 characters long, include a number and a symbol"
+                    .to_string(),
+            );
         } else {
-            self.warning_message = Some("Username already exists".to_string());
-            Err(())
-        }
-    }
-
-    fn register_user(&mut self) -> Result<(), ()> {
-        // Create a new user instance
-        let user = User {
-            id: 0, // Or generate an ID as needed
-            username: self.new_username.clone(),
-            password_hash: String::new(), // This will be set in add_user
-        };
-
-        // Attempt to add the user to the database
-        match add_user(&user, &self.new_password) {
-            Ok(_) => {
-                self.warning_message = Some("User successfully registered!".to_string());
-                Ok(())
-            }
-            Err(e) => {
-                self.warning_message = Some(format!("Failed to register: {:?}", e));
-                Err(())
-            }
+            // The worker checks that the username is free before registering it
+            self.worker.submit(Job::Register {
+                username: self.new_username.clone(),
+                password: self.new_password.clone(),
+            });
         }
     }
 
@@ -199,46 +811,636 @@ impl MyApp {
     fn show_login(&mut self) {
         self.showing_signup = false;
     }
-    fn load_expenses(&mut self) {
-        self.expenses = models::get_expenses().unwrap_or_default();
+    pub fn load_expenses(&mut self) {
+        self.worker.submit(Job::LoadExpenses);
+    }
+
+    // Preselects a category learned from past expenses, without overriding a manual pick
+    pub fn suggest_category(&mut self) {
+        if !self.category.is_empty() && !self.category_suggested {
+            return;
+        }
+        self.category.clear();
+        self.category_suggested = false;
+
+        let draft = Expense {
+            id: 0,
+            date: self.expense_date.clone(),
+            amount: self.expense_amount.parse::<f32>().unwrap_or(0.0),
+            category: String::new(),
+            description: self.expense_name.clone(),
+            payment_method: self.payment_method.clone(),
+            tags: String::new(),
+            payee_id: None,
+            time: None,
+        };
+        // Leave the category on auto when an explicit rule will assign it
+        if rules::find_matching_rule(&self.rules, &draft)
+            .is_some_and(|rule| !rule.category.is_empty())
+        {
+            return;
+        }
+        if let Some((category, confidence)) = self
+            .classifier
+            .predict(&self.expense_name, &self.payment_method)
+        {
+            if confidence >= classifier::MIN_SUGGESTION_CONFIDENCE {
+                self.category = category;
+                self.category_suggested = true;
+            }
+        }
+    }
+
+    // Validates the add form before saving; shared by the GUI and the terminal UI
+    pub fn submit_expense_form(&mut self) {
+        if !self.expense_name.is_empty()
+            && !self.expense_amount.is_empty()
+            && !self.expense_date.is_empty()
+            && !self.payment_method.is_empty()
+        {
+            self.warning_message = None; // Clear any previous warning
+            self.add_expense_to_db(); // Leaves a warning if no category could be assigned
+        } else {
+            self.warning_message = Some("Please fill in all fields".to_string());
+        }
     }
 
-    pub fn add_expense_to_db(&mut self, egui_ctx: &egui::Context) {
+    pub fn add_expense_to_db(&mut self) {
         let amount = self.expense_amount.parse::<f32>().unwrap_or(0.0);
+        let time = Some(self.expense_time.trim())
+            .filter(|time| !time.is_empty())
+            .map(|time| NaiveTime::parse_from_str(time, "%H:%M").map(|_| time.to_string()));
+        let time = match time.transpose() {
+            Ok(time) => time,
+            Err(_) => {
+                self.warning_message = Some("Enter the time as HH:MM".to_string());
+                return;
+            }
+        };
 
-        let expense = Expense {
+        let mut expense = Expense {
             id: 0,
             date: self.expense_date.clone(),
             amount,
             category: self.category.clone(),
             description: self.expense_name.clone(),
             payment_method: self.payment_method.clone(),
+            tags: String::new(),
+            payee_id: None,
+            time,
         };
 
-        if let Err(e) = add_expense(&expense) {
-            eprintln!("Failed to add expense: {}", e);
+        // A category picked by hand wins over the one a rule would assign
+        if let Some(rule) = rules::find_matching_rule(&self.rules, &expense) {
+            rules::apply_rule(rule, &mut expense);
+            if !self.category.is_empty() {
+                expense.category = self.category.clone();
+            }
+        }
+        if expense.category.is_empty() {
+            self.warning_message =
+                Some("No rule matched this expense, please choose an expense type".to_string());
+            return;
         }
-        self.load_expenses();
+        // Bank descriptions carry the most identifying text, so try them before any renamed one
+        expense.payee_id = payees::match_payee(&self.payees, &self.expense_name)
+            .or_else(|| payees::match_payee(&self.payees, &expense.description));
+
+        // The scanned receipt is attached once the expense has an id
+        let receipt_path = Some(self.scan_receipt_path.trim())
+            .filter(|path| !path.is_empty() && !self.server_mode)
+            .map(PathBuf::from);
+        self.worker.submit(Job::AddExpense {
+            expense,
+            receipt_path,
+        });
+        self.scan_receipt_path.clear();
+        self.receipt_suggestion = None;
         self.expense_name.clear();
         self.expense_amount.clear();
+        self.expense_time.clear();
         self.payment_method.clear();
         self.category.clear();
-        self.update_monthly_spending_chart(egui_ctx);
-        self.update_chart(egui_ctx);
+        self.category_suggested = false;
+    }
+
+    pub fn scan_receipt(&mut self) {
+        self.receipt_suggestion = None;
+        if self.server_mode {
+            self.warning_message = Some(LOCAL_ONLY_MESSAGE.to_string());
+            return;
+        }
+        let path = self.scan_receipt_path.trim();
+        if path.is_empty() {
+            self.warning_message = Some("Please enter the path of a receipt".to_string());
+            return;
+        }
+        self.worker.submit(Job::ScanReceipt(PathBuf::from(path)));
+    }
+
+    // Only called once the user has reviewed the suggested fields and their confidence
+    pub fn apply_receipt_suggestion(&mut self) {
+        if let Some(suggestion) = self.receipt_suggestion.take() {
+            if let Some(merchant) = suggestion.merchant {
+                self.expense_name = merchant.value;
+            }
+            if let Some(total) = suggestion.total {
+                self.expense_amount = format!("{:.2}", total.value);
+            }
+            if let Some(date) = suggestion.date {
+                self.expense_date = date.value;
+            }
+        }
+    }
+
+    pub fn add_payee_from_form(&mut self) {
+        let name = self.payee_form.new_name.trim().to_string();
+        if name.is_empty() {
+            self.warning_message = Some("Payee name cannot be empty".to_string());
+            return;
+        }
+        self.worker.submit(Job::AddPayee(name));
+    }
+
+    pub fn add_alias_from_form(&mut self) {
+        let pattern = self.payee_form.new_alias.trim().to_string();
+        let Some(payee_id) = self.payee_form.alias_payee_id else {
+            self.warning_message = Some("Choose a payee for the alias".to_string());
+            return;
+        };
+        if pattern.is_empty() {
+            self.warning_message = Some("Alias pattern cannot be empty".to_string());
+            return;
+        }
+        self.worker.submit(Job::AddPayeeAlias { payee_id, pattern });
+    }
+
+    pub fn delete_payee_alias(&mut self, alias_id: i32) {
+        self.worker.submit(Job::DeletePayeeAlias(alias_id));
+    }
+
+    pub fn merge_payees_from_form(&mut self) {
+        let (Some(source_id), Some(target_id)) = (
+            self.payee_form.merge_source_id,
+            self.payee_form.merge_target_id,
+        ) else {
+            self.warning_message = Some("Choose two payees to merge".to_string());
+            return;
+        };
+        if source_id == target_id {
+            self.warning_message = Some("Cannot merge a payee into itself".to_string());
+            return;
+        }
+        self.worker.submit(Job::MergePayees {
+            source_id,
+            target_id,
+        });
+    }
+
+    // Re-runs payee matching over every expense, e.g. after adding aliases
+    pub fn remap_payees(&mut self) {
+        let remapped: Vec<Expense> = self
+            .expenses
+            .iter()
+            .filter_map(|expense| {
+                let payee_id = payees::match_payee(&self.payees, &expense.description);
+                (payee_id.is_some() && payee_id != expense.payee_id).then(|| Expense {
+                    payee_id,
+                    ..expense.clone()
+                })
+            })
+            .collect();
+        if !remapped.is_empty() {
+            self.worker.submit(Job::UpdateExpenses(remapped));
+        }
+    }
+
+    pub fn goal_progress(&self, today: NaiveDate) -> Vec<(&Goal, GoalProgress)> {
+        self.goals
+            .iter()
+            .map(|goal| (goal, goals::goal_progress(goal, &self.expenses, today)))
+            .collect()
+    }
+
+    // Contributions are counted from the day the goal is added
+    fn parse_goal_form(&self, today: NaiveDate) -> Result<Goal, String> {
+        let form = &self.goal_form;
+        if form.name.trim().is_empty() {
+            return Err("Goal name cannot be empty".to_string());
+        }
+        let target_amount = match form.target_amount.trim().parse::<f32>() {
+            Ok(amount) if amount > 0.0 => amount,
+            _ => return Err("Enter a target amount above zero".to_string()),
+        };
+        let target_date = NaiveDate::parse_from_str(form.target_date.trim(), "%Y-%m-%d")
+            .map_err(|_| "Enter the target date as YYYY-MM-DD".to_string())?;
+        if target_date <= today {
+            return Err("The target date must be in the future".to_string());
+        }
+        if form.category.is_empty() {
+            return Err("Choose the category contributions are recorded under".to_string());
+        }
+        Ok(Goal {
+            id: 0,
+            user_id: self.current_user_id.unwrap_or(0),
+            name: form.name.trim().to_string(),
+            target_amount,
+            target_date: target_date.to_string(),
+            category: form.category.clone(),
+            tag: form.tag.trim().to_string(),
+            start_date: today.to_string(),
+        })
+    }
+
+    pub fn add_goal_from_form(&mut self, today: NaiveDate) {
+        let goal = match self.parse_goal_form(today) {
+            Ok(goal) => goal,
+            Err(message) => {
+                self.warning_message = Some(message);
+                return;
+            }
+        };
+        self.worker.submit(Job::AddGoal(goal));
+    }
+
+    pub fn delete_goal(&mut self, goal_id: i32) {
+        if let Some(user_id) = self.current_user_id {
+            self.worker.submit(Job::DeleteGoal { goal_id, user_id });
+        }
+    }
+
+    pub fn debt_states(&self) -> Vec<DebtState> {
+        self.debts
+            .iter()
+            .map(|debt| debts::current_state(debt, &self.expenses))
+            .collect()
+    }
+
+    // Both strategies from the same balances and budget, avalanche first
+    pub fn payoff_plans(&self, today: NaiveDate) -> Option<[PayoffPlan; 2]> {
+        let states = self.debt_states();
+        if states.iter().all(|state| state.balance <= 0.0) {
+            return None;
+        }
+        let budget = self
+            .debt_form
+            .monthly_budget
+            .trim()
+            .parse::<f32>()
+            .unwrap_or(0.0);
+        Some(
+            [Strategy::Avalanche, Strategy::Snowball]
+                .map(|strategy| debts::simulate(&states, budget, strategy, today)),
+        )
+    }
+
+    // Payments made before today are already part of the balance entered
+    fn parse_debt_form(&self, today: NaiveDate) -> Result<Debt, String> {
+        let form = &self.debt_form;
+        let name = form.name.trim();
+        if name.is_empty() {
+            return Err("Debt name cannot be empty".to_string());
+        }
+        let parse_amount = |text: &str, field: &str| -> Result<f32, String> {
+            match text.trim().parse::<f32>() {
+                Ok(amount) if amount >= 0.0 => Ok(amount),
+                _ => Err(format!("Enter the {} as a number of at least zero", field)),
+            }
+        };
+        let balance = parse_amount(&form.balance, "balance")?;
+        let apr = parse_amount(&form.apr, "APR")?;
+        let minimum_payment = parse_amount(&form.minimum_payment, "minimum payment")?;
+        let tag = match form.tag.trim() {
+            "" => name,
+            tag => tag,
+        };
+        Ok(Debt {
+            id: 0,
+            user_id: self.current_user_id.unwrap_or(0),
+            name: name.to_string(),
+            balance,
+            apr,
+            minimum_payment,
+            tag: tag.to_string(),
+            start_date: today.to_string(),
+        })
+    }
+
+    pub fn add_debt_from_form(&mut self, today: NaiveDate) {
+        let debt = match self.parse_debt_form(today) {
+            Ok(debt) => debt,
+            Err(message) => {
+                self.warning_message = Some(message);
+                return;
+            }
+        };
+        self.worker.submit(Job::AddDebt(debt));
+    }
+
+    pub fn delete_debt(&mut self, debt_id: i32) {
+        if let Some(user_id) = self.current_user_id {
+            self.worker.submit(Job::DeleteDebt { debt_id, user_id });
+        }
+    }
+
+    pub fn net_worth(&self, today: NaiveDate) -> NetWorthSnapshot {
+        networth::current_snapshot(&self.assets, &self.debt_states(), today)
+    }
+
+    pub fn record_net_worth(&mut self, today: NaiveDate) {
+        let Some(user_id) = self.current_user_id else {
+            return;
+        };
+        self.worker.submit(Job::RecordNetWorth {
+            user_id,
+            snapshot: self.net_worth(today),
+        });
+    }
+
+    // Users who track neither assets nor debts don't get a history of zeros
+    fn record_net_worth_if_due(&mut self, today: NaiveDate) {
+        if (self.assets.is_empty() && self.debts.is_empty())
+            || !networth::is_snapshot_due(&self.net_worth_snapshots, today)
+        {
+            return;
+        }
+        self.record_net_worth(today);
+    }
+
+    // Saving a name that already exists updates that asset's value
+    pub fn save_asset_from_form(&mut self) {
+        let name = self.asset_form.name.trim().to_string();
+        if name.is_empty() {
+            self.warning_message = Some("Asset name cannot be empty".to_string());
+            return;
+        }
+        let Ok(value) = self.asset_form.value.trim().parse::<f32>() else {
+            self.warning_message = Some("Enter the value as a number".to_string());
+            return;
+        };
+        let asset = match self
+            .assets
+            .iter()
+            .find(|asset| asset.name.eq_ignore_ascii_case(&name))
+        {
+            Some(existing) => Asset {
+                value,
+                ..existing.clone()
+            },
+            None => Asset {
+                id: 0,
+                user_id: self.current_user_id.unwrap_or(0),
+                name,
+                value,
+            },
+        };
+        self.worker.submit(Job::SaveAsset(asset));
+    }
+
+    pub fn delete_asset(&mut self, asset_id: i32) {
+        if let Some(user_id) = self.current_user_id {
+            self.worker.submit(Job::DeleteAsset { asset_id, user_id });
+        }
+    }
+
+    pub fn calendar(&self, today: NaiveDate) -> CalendarMonth {
+        let month = self.calendar_month.unwrap_or(today);
+        calendar::month_calendar(&self.expenses, &self.schedules, month)
+    }
+
+    // Moves the calendar a month back or forward, clearing the selected day
+    pub fn step_calendar(&mut self, forward: bool, today: NaiveDate) {
+        let month = calendar::first_of_month(self.calendar_month.unwrap_or(today));
+        let months = chrono::Months::new(1);
+        self.calendar_month = if forward {
+            month.checked_add_months(months)
+        } else {
+            month.checked_sub_months(months)
+        };
+        self.calendar_day = None;
+    }
+
+    pub fn calendar_day_expenses(&self) -> Vec<&Expense> {
+        let Some(day) = self.calendar_day else {
+            return Vec::new();
+        };
+        let day = day.format("%Y-%m-%d").to_string();
+        self.expenses
+            .iter()
+            .filter(|expense| expense.date == day)
+            .collect()
+    }
+
+    pub fn rule_form_from_expense(&mut self, expense_id: i32) {
+        if let Some(expense) = self
+            .expenses
+            .iter()
+            .find(|expense| expense.id == expense_id)
+        {
+            let rule = rules::rule_from_expense(expense, self.current_user_id.unwrap_or(0));
+            self.rule_form = RuleForm {
+                description_pattern: rule.description_pattern,
+                is_regex: rule.is_regex,
+                min_amount: String::new(),
+                max_amount: String::new(),
+                payment_method: rule.payment_method,
+                category: rule.category,
+                tags: rule.tags,
+                rename_to: rule.rename_to,
+            };
+        }
+    }
+
+    fn parse_rule_form(&self) -> Result<CategoryRule, String> {
+        let form = &self.rule_form;
+        let parse_amount = |text: &str| -> Result<Option<f32>, String> {
+            if text.trim().is_empty() {
+                Ok(None)
+            } else {
+                text.trim()
+                    .parse::<f32>()
+                    .map(Some)
+                    .map_err(|_| format!("\"{}\" is not a valid amount", text))
+            }
+        };
+        if form.is_regex && regex::Regex::new(&form.description_pattern).is_err() {
+            return Err("The description pattern is not a valid regular expression".to_string());
+        }
+        if form.category.is_empty() && form.tags.trim().is_empty() && form.rename_to.is_empty() {
+            return Err("A rule needs a category, tags or a new description".to_string());
+        }
+        let mut rule = CategoryRule {
+            id: 0,
+            user_id: self.current_user_id.unwrap_or(0),
+            position: 0,
+            description_pattern: form.description_pattern.trim().to_string(),
+            is_regex: form.is_regex,
+            min_amount: parse_amount(&form.min_amount)?,
+            max_amount: parse_amount(&form.max_amount)?,
+            payment_method: form.payment_method.clone(),
+            category: form.category.clone(),
+            tags: rules::merge_tags("", &form.tags),
+            rename_to: form.rename_to.trim().to_string(),
+            description_regex: None,
+        };
+        rule.description_regex = rules::compile_pattern(&rule);
+        Ok(rule)
+    }
+
+    pub fn save_rule_form(&mut self) {
+        match self.parse_rule_form() {
+            Ok(rule) => self.worker.submit(Job::AddRule(rule)),
+            Err(message) => self.warning_message = Some(message),
+        }
+    }
+
+    pub fn delete_rule(&mut self, rule_id: i32) {
+        if let Some(user_id) = self.current_user_id {
+            self.worker.submit(Job::DeleteRule { rule_id, user_id });
+        }
+    }
+
+    pub fn move_rule(&mut self, rule_id: i32, up: bool) {
+        let Some(index) = self.rules.iter().position(|rule| rule.id == rule_id) else {
+            return;
+        };
+        let other = if up {
+            index.checked_sub(1)
+        } else {
+            Some(index + 1).filter(|&other| other < self.rules.len())
+        };
+        if let Some(other) = other {
+            self.worker.submit(Job::SwapRules(
+                self.rules[index].clone(),
+                self.rules[other].clone(),
+            ));
+        }
+    }
+
+    pub fn preview_rules(&mut self) {
+        self.rule_changes = Some(rules::dry_run(&self.rules, &self.expenses));
+    }
+
+    pub fn apply_rule_changes(&mut self) {
+        let Some(changes) = self.rule_changes.take() else {
+            return;
+        };
+        let updated: Vec<Expense> = changes
+            .into_iter()
+            .filter_map(|change| {
+                let mut updated = self
+                    .expenses
+                    .iter()
+                    .find(|expense| expense.id == change.expense_id)?
+                    .clone();
+                updated.description = change.new_description;
+                updated.category = change.new_category;
+                updated.tags = change.new_tags;
+                Some(updated)
+            })
+            .collect();
+        self.worker.submit(Job::UpdateExpenses(updated));
+    }
+
+    pub fn delete_expense_from_db(&mut self, expense_id: i32) {
+        self.worker.submit(Job::DeleteExpense(expense_id));
+    }
+
+    pub fn import_csv(&mut self) {
+        let path = self.import_path.trim();
+        if path.is_empty() {
+            self.warning_message = Some("Please enter the path of a CSV file".to_string());
+            return;
+        }
+        self.worker.submit(Job::Import {
+            path: PathBuf::from(path),
+            rules: self.rules.clone(),
+            payees: self.payees.clone(),
+        });
+    }
+
+    // A PDF of the selected analytics period; the drill-down doesn't apply
+    pub fn save_statement(&mut self, today: NaiveDate) {
+        let path = self.statement_path.trim();
+        if path.is_empty() {
+            self.warning_message = Some("Please enter where to save the statement".to_string());
+            return;
+        }
+        self.worker.submit(Job::SaveStatement {
+            statement: Statement::new(
+                &self.expenses,
+                &self.period,
+                &self.goals,
+                &self.debts,
+                today,
+            ),
+            path: PathBuf::from(path),
+        });
+    }
+
+    pub fn open_receipts(&mut self, expense_id: i32) {
+        if self.server_mode {
+            self.warning_message = Some(LOCAL_ONLY_MESSAGE.to_string());
+            return;
+        }
+        self.receipts_expense_id = Some(expense_id);
+        self.receipts.clear();
+        self.receipt_preview = None;
+        self.reload_receipts(expense_id);
+    }
+
+    pub fn close_receipts(&mut self) {
+        self.receipts_expense_id = None;
+        self.receipts.clear();
+        self.receipt_preview = None;
+        self.receipt_path.clear();
+    }
+
+    fn reload_receipts(&mut self, expense_id: i32) {
+        if self.receipts_expense_id == Some(expense_id) {
+            self.worker.submit(Job::LoadReceipts(expense_id));
+        }
+    }
+
+    pub fn attach_receipt(&mut self) {
+        let Some(expense_id) = self.receipts_expense_id else {
+            return;
+        };
+        let path = self.receipt_path.trim();
+        if path.is_empty() {
+            self.warning_message = Some("Please enter the path of a receipt".to_string());
+            return;
+        }
+        self.worker.submit(Job::AttachReceipt {
+            expense_id,
+            path: PathBuf::from(path),
+        });
+    }
+
+    pub fn remove_receipt(&mut self, attachment_id: i32) {
+        if let Some((attachment, _)) = self
+            .receipts
+            .iter()
+            .find(|(attachment, _)| attachment.id == attachment_id)
+        {
+            self.worker.submit(Job::RemoveReceipt(attachment.clone()));
+        }
     }
 
-    pub fn delete_expense_from_db(&mut self, expense_id: i32, egui_ctx: &egui::Context) {
-        if let Err(e) = models::delete_expense(expense_id) {
-            eprintln!("Failed to delete expense: {}", e);
+    pub fn preview_receipt(&mut self, attachment_id: i32) {
+        if let Some((attachment, _)) = self
+            .receipts
+            .iter()
+            .find(|(attachment, _)| attachment.id == attachment_id)
+        {
+            self.worker.submit(Job::PreviewReceipt(attachment.clone()));
         }
-        self.load_expenses();
-        self.update_monthly_spending_chart(egui_ctx);
-        self.update_chart(egui_ctx);
     }
 }
 
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_worker();
         egui::CentralPanel::default().show(ctx, |ui| {
             if self.is_logged_in {
                 ui::render_expense_tracker_ui(ui, self, ctx);
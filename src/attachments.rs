@@ -0,0 +1,158 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::models;
+use crate::models::{Attachment, MyError};
+use image::ImageOutputFormat;
+#[cfg(not(target_arch = "wasm32"))]
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+// Receipts are copied into this directory next to the database and named after their content
+// hash, so the same file is only stored once
+pub const ATTACHMENTS_DIR: &str = "attachments";
+const THUMBNAIL_SIZE: u32 = 160;
+
+pub fn mime_type_for(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "bmp" => Some("image/bmp"),
+        "webp" => Some("image/webp"),
+        "pdf" => Some("application/pdf"),
+        _ => None,
+    }
+}
+
+fn extension_for(mime_type: &str) -> &'static str {
+    match mime_type {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/bmp" => "bmp",
+        "image/webp" => "webp",
+        "application/pdf" => "pdf",
+        _ => "bin",
+    }
+}
+
+pub fn is_image(attachment: &Attachment) -> bool {
+    attachment.mime_type.starts_with("image/")
+}
+
+pub fn content_hash(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// Resolved from the database path rather than the working directory, so the files follow the data
+pub fn attachments_dir(database_path: &Path) -> PathBuf {
+    database_path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(ATTACHMENTS_DIR)
+}
+
+pub fn stored_path(dir: &Path, attachment: &Attachment) -> PathBuf {
+    dir.join(format!(
+        "{}.{}",
+        attachment.content_hash,
+        extension_for(&attachment.mime_type)
+    ))
+}
+
+pub fn create_thumbnail(image_data: &[u8]) -> Result<Vec<u8>, MyError> {
+    let thumbnail = image::load_from_memory(image_data)?.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    let mut png_bytes = Vec::new();
+    thumbnail.write_to(&mut Cursor::new(&mut png_bytes), ImageOutputFormat::Png)?;
+    Ok(png_bytes)
+}
+
+// Decodes a stored PNG thumbnail into RGBA pixels for load_texture_from_memory
+pub fn decode_thumbnail(png_bytes: &[u8]) -> Result<(Vec<u8>, [usize; 2]), MyError> {
+    let image = image::load_from_memory(png_bytes)?;
+    let size = [image.width() as usize, image.height() as usize];
+    Ok((image.to_rgba8().into_raw(), size))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn attach_file(
+    conn: &Connection,
+    dir: &Path,
+    expense_id: i32,
+    source: &Path,
+) -> Result<Attachment, MyError> {
+    let mime_type = mime_type_for(source)
+        .ok_or_else(|| MyError::UnsupportedFileType(source.display().to_string()))?;
+    let data = fs::read(source)?;
+
+    let mut attachment = Attachment {
+        id: 0,
+        expense_id,
+        file_name: source
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        mime_type: mime_type.to_string(),
+        content_hash: content_hash(&data),
+        thumbnail: None,
+    };
+    if is_image(&attachment) {
+        attachment.thumbnail = Some(create_thumbnail(&data)?);
+    }
+
+    let destination = stored_path(dir, &attachment);
+    if !destination.exists() {
+        fs::create_dir_all(dir)?;
+        fs::write(&destination, &data)?;
+    }
+
+    attachment.id = models::add_attachment(conn, &attachment)?;
+    Ok(attachment)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove_attachment(
+    conn: &Connection,
+    dir: &Path,
+    attachment: &Attachment,
+) -> Result<(), MyError> {
+    models::delete_attachment(conn, attachment.id)?;
+    remove_file_if_unreferenced(conn, dir, attachment)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove_attachments_for_expense(
+    conn: &Connection,
+    dir: &Path,
+    expense_id: i32,
+) -> Result<(), MyError> {
+    let attachments = models::get_attachments(conn, expense_id)?;
+    models::delete_attachments_for_expense(conn, expense_id)?;
+    for attachment in &attachments {
+        remove_file_if_unreferenced(conn, dir, attachment)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn remove_file_if_unreferenced(
+    conn: &Connection,
+    dir: &Path,
+    attachment: &Attachment,
+) -> Result<(), MyError> {
+    if models::count_attachments_with_hash(conn, &attachment.content_hash)? == 0 {
+        match fs::remove_file(stored_path(dir, attachment)) {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
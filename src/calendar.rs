@@ -0,0 +1,106 @@
+use crate::models::{Expense, RecurringSchedule};
+use crate::subscriptions;
+use chrono::{Datelike, Duration, Months, NaiveDate};
+
+// One cell of the cash-flow calendar. The tracker has no income records, so `credits` only
+// sums expenses entered with negative amounts, e.g. refunds in an imported bank statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CalendarDay {
+    pub date: NaiveDate,
+    pub spent: f32,
+    pub credits: f32,
+    pub scheduled: f32, // recurring charges due that day that haven't been recorded yet
+    pub expense_ids: Vec<i32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CalendarMonth {
+    pub first_day: NaiveDate,
+    // Empty cells before the 1st, in a week starting on Monday
+    pub leading_blanks: usize,
+    pub days: Vec<CalendarDay>,
+}
+
+impl CalendarMonth {
+    // The largest day's spending, which the colours are scaled against
+    pub fn busiest_day(&self) -> f32 {
+        self.days.iter().map(|day| day.spent).fold(0.0, f32::max)
+    }
+
+    pub fn total_spent(&self) -> f32 {
+        self.days.iter().map(|day| day.spent).sum()
+    }
+
+    pub fn total_credits(&self) -> f32 {
+        self.days.iter().map(|day| day.credits).sum()
+    }
+
+    pub fn total_scheduled(&self) -> f32 {
+        self.days.iter().map(|day| day.scheduled).sum()
+    }
+}
+
+pub fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap()
+}
+
+// The month containing `date`
+pub fn month_calendar(
+    expenses: &[Expense],
+    schedules: &[RecurringSchedule],
+    date: NaiveDate,
+) -> CalendarMonth {
+    let first_day = first_of_month(date);
+    let next_month = first_day.checked_add_months(Months::new(1)).unwrap();
+    let mut days: Vec<CalendarDay> = first_day
+        .iter_days()
+        .take_while(|day| *day < next_month)
+        .map(|date| CalendarDay {
+            date,
+            spent: 0.0,
+            credits: 0.0,
+            scheduled: 0.0,
+            expense_ids: Vec::new(),
+        })
+        .collect();
+
+    for expense in expenses {
+        let Ok(date) = NaiveDate::parse_from_str(&expense.date, "%Y-%m-%d") else {
+            continue;
+        };
+        if first_of_month(date) != first_day {
+            continue;
+        }
+        let day = &mut days[date.day0() as usize];
+        if expense.amount < 0.0 {
+            day.credits -= expense.amount;
+        } else {
+            day.spent += expense.amount;
+        }
+        day.expense_ids.push(expense.id);
+    }
+
+    for schedule in schedules {
+        let last_day = next_month - Duration::days(1);
+        let Some((due, _)) = subscriptions::due_dates(schedule, last_day) else {
+            continue;
+        };
+        for date in due.into_iter().filter(|date| *date >= first_day) {
+            days[date.day0() as usize].scheduled += schedule.amount;
+        }
+    }
+
+    CalendarMonth {
+        first_day,
+        leading_blanks: first_day.weekday().num_days_from_monday() as usize,
+        days,
+    }
+}
+
+// How strongly to colour a day, from 0.0 for nothing spent to 1.0 for the busiest day
+pub fn intensity(spent: f32, busiest_day: f32) -> f32 {
+    if busiest_day <= 0.0 {
+        return 0.0;
+    }
+    (spent / busiest_day).clamp(0.0, 1.0)
+}
@@ -0,0 +1,607 @@
+use eframe::egui;
+use eframe::egui::{Align2, Color32, FontId, Mesh, Pos2, Rect, Sense, Shape, Stroke, Vec2};
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+// Same palette the plotters pie chart used
+pub const CHART_COLORS: [Color32; 11] = [
+    Color32::from_rgb(255, 0, 0),
+    Color32::from_rgb(0, 0, 255),
+    Color32::from_rgb(0, 255, 0),
+    Color32::from_rgb(255, 255, 0),
+    Color32::from_rgb(0, 255, 255),
+    Color32::from_rgb(255, 0, 255),
+    Color32::from_rgb(255, 165, 0),
+    Color32::from_rgb(255, 192, 203),
+    Color32::from_rgb(128, 0, 128),
+    Color32::from_rgb(50, 205, 50),
+    Color32::from_rgb(75, 0, 130),
+];
+
+const CHART_SIZE: Vec2 = Vec2::new(640.0, 360.0);
+const AXIS_MARGIN: f32 = 40.0;
+const POINT_RADIUS: f32 = 4.0;
+// Long series only label every few columns so the labels don't overlap
+const MAX_AXIS_LABELS: usize = 12;
+
+pub struct PieSlice {
+    pub label: String,
+    pub amount: f32,
+    pub share: f32,
+    // Radians clockwise from 12 o'clock
+    pub start_angle: f32,
+    pub end_angle: f32,
+}
+
+// Largest slice first so the order is stable between frames
+pub fn pie_slices(data: &HashMap<String, f32>) -> Vec<PieSlice> {
+    let mut entries: Vec<(&String, f32)> = data
+        .iter()
+        .filter(|(_, &amount)| amount > 0.0)
+        .map(|(label, &amount)| (label, amount))
+        .collect();
+    entries.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(b.0)));
+    let total: f32 = entries.iter().map(|(_, amount)| amount).sum();
+
+    let mut start_angle = 0.0;
+    entries
+        .into_iter()
+        .map(|(label, amount)| {
+            let share = amount / total;
+            let end_angle = start_angle + share * TAU;
+            let slice = PieSlice {
+                label: label.clone(),
+                amount,
+                share,
+                start_angle,
+                end_angle,
+            };
+            start_angle = end_angle;
+            slice
+        })
+        .collect()
+}
+
+// Index of the slice under a point given relative to the centre, if it is inside the circle
+pub fn slice_at(slices: &[PieSlice], offset: Vec2, radius: f32) -> Option<usize> {
+    if offset.length() > radius {
+        return None;
+    }
+    let angle = offset.x.atan2(-offset.y).rem_euclid(TAU);
+    slices
+        .iter()
+        .position(|slice| angle >= slice.start_angle && angle < slice.end_angle)
+}
+
+// Bars and line points share one column per label, sorted by label (months and years sort as text)
+pub fn sorted_series(data: &HashMap<String, f32>) -> Vec<(String, f32)> {
+    let mut series: Vec<(String, f32)> = data
+        .iter()
+        .map(|(label, &amount)| (label.clone(), amount))
+        .collect();
+    series.sort_by(|a, b| a.0.cmp(&b.0));
+    series
+}
+
+pub fn column_at(count: usize, plot: Rect, x: f32) -> Option<usize> {
+    if count == 0 || x < plot.left() || x >= plot.right() {
+        return None;
+    }
+    let column = ((x - plot.left()) / plot.width() * count as f32) as usize;
+    Some(column.min(count - 1))
+}
+
+// Fill for heatmap-style cells: transparent at 0.0, strong red at 1.0
+pub fn heat_color(intensity: f32) -> Color32 {
+    let alpha = (intensity.clamp(0.0, 1.0) * 200.0).round() as u8;
+    Color32::from_rgba_unmultiplied(220, 40, 40, alpha)
+}
+
+pub fn tooltip_text(label: &str, amount: f32, share: Option<f32>) -> String {
+    match share {
+        Some(share) => format!("{}: {:.2} ({:.1}%)", label, amount, share * 100.0),
+        None => format!("{}: {:.2}", label, amount),
+    }
+}
+
+// Returns the label of a slice that was clicked this frame
+pub fn pie_chart(ui: &mut egui::Ui, title: &str, data: &HashMap<String, f32>) -> Option<String> {
+    ui.label(egui::RichText::new(title).strong());
+    let slices = pie_slices(data);
+    if slices.is_empty() {
+        ui.label("No expenses to chart yet.");
+        return None;
+    }
+
+    let (response, painter) = ui.allocate_painter(CHART_SIZE, Sense::click());
+    let rect = response.rect;
+    let radius = rect.height() / 2.0 - 10.0;
+    let center = Pos2::new(rect.left() + radius + 10.0, rect.center().y);
+    let hovered = response
+        .hover_pos()
+        .and_then(|pos| slice_at(&slices, pos - center, radius));
+
+    let mut mesh = Mesh::default();
+    for (i, slice) in slices.iter().enumerate() {
+        let color = CHART_COLORS[i % CHART_COLORS.len()];
+        // Pull the hovered slice out a little
+        let offset = if hovered == Some(i) {
+            direction((slice.start_angle + slice.end_angle) / 2.0) * 6.0
+        } else {
+            Vec2::ZERO
+        };
+        add_slice(&mut mesh, center + offset, radius, slice, color);
+    }
+    painter.add(Shape::mesh(mesh));
+
+    // Legend to the right of the pie
+    let text_color = ui.visuals().text_color();
+    let mut legend_pos = Pos2::new(center.x + radius + 30.0, rect.top() + 10.0);
+    for (i, slice) in slices.iter().enumerate() {
+        let swatch = Rect::from_min_size(legend_pos, Vec2::splat(12.0));
+        painter.rect_filled(swatch, 2.0, CHART_COLORS[i % CHART_COLORS.len()]);
+        painter.text(
+            legend_pos + Vec2::new(18.0, 6.0),
+            Align2::LEFT_CENTER,
+            format!("{:.2}% {}", slice.share * 100.0, slice.label),
+            FontId::proportional(14.0),
+            text_color,
+        );
+        legend_pos.y += 20.0;
+    }
+
+    let clicked = response.clicked();
+    let slice = &slices[hovered?];
+    response
+        .on_hover_cursor(egui::CursorIcon::PointingHand)
+        .on_hover_text_at_pointer(tooltip_text(&slice.label, slice.amount, Some(slice.share)));
+    clicked.then(|| slice.label.clone())
+}
+
+// One projected month. The projected months are their own series: a dashed line carrying on
+// from the last bar before them, with each point labelled inside a shaded low-high band.
+pub struct Projection {
+    pub label: String,
+    pub expected: f32,
+    pub low: f32,
+    pub high: f32,
+}
+
+// Returns the label of a bar that was clicked this frame; projected months with nothing spent
+// yet can't be drilled into
+pub fn bar_chart(
+    ui: &mut egui::Ui,
+    title: &str,
+    data: &HashMap<String, f32>,
+    projection: &[Projection],
+) -> Option<String> {
+    ui.label(egui::RichText::new(title).strong());
+    let mut series = sorted_series(data);
+    for point in projection {
+        if !data.contains_key(&point.label) {
+            series.push((point.label.clone(), 0.0));
+        }
+    }
+    series.sort_by(|a, b| a.0.cmp(&b.0));
+    let projected_at = |label: &str| projection.iter().find(|point| point.label == label);
+    // The bands may reach above every bar, so the axis has to make room for them
+    let scale: Vec<(String, f32)> = series
+        .iter()
+        .map(|(label, amount)| match projected_at(label) {
+            Some(point) => (label.clone(), amount.max(point.high)),
+            None => (label.clone(), *amount),
+        })
+        .collect();
+    let (response, painter, plot, range) = allocate_plot(ui, &scale, Sense::click())?;
+    let hovered = response
+        .hover_pos()
+        .and_then(|pos| column_at(series.len(), plot, pos.x));
+
+    let column_width = plot.width() / series.len() as f32;
+    let label_every = series.len().div_ceil(MAX_AXIS_LABELS);
+    for (i, (label, amount)) in series.iter().enumerate() {
+        let left = plot.left() + column_width * i as f32;
+        let top = value_y(plot, *amount, range);
+        let bar = Rect::from_two_pos(
+            Pos2::new(left + column_width * 0.1, top),
+            Pos2::new(left + column_width * 0.9, value_y(plot, 0.0, range)),
+        );
+        let color = if hovered == Some(i) {
+            Color32::from_rgb(100, 100, 255)
+        } else {
+            Color32::BLUE
+        };
+        painter.rect_filled(bar, 0.0, color);
+        if i % label_every == 0 {
+            column_label(ui, &painter, plot, left + column_width / 2.0, label);
+        }
+    }
+
+    let projected: Vec<(usize, &Projection)> = series
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (label, _))| Some((i, projected_at(label)?)))
+        .collect();
+    if let Some(&(first, _)) = projected.first() {
+        let center = |column: usize| plot.left() + column_width * (column as f32 + 0.5);
+        let mut line = Vec::new();
+        if first > 0 {
+            line.push(Pos2::new(
+                center(first - 1),
+                value_y(plot, series[first - 1].1, range),
+            ));
+        }
+        for (i, point) in &projected {
+            let band = Rect::from_x_y_ranges(
+                center(*i) - column_width * 0.4..=center(*i) + column_width * 0.4,
+                value_y(plot, point.high, range)..=value_y(plot, point.low, range),
+            );
+            painter.rect_filled(band, 0.0, Color32::from_rgba_unmultiplied(255, 0, 0, 40));
+            line.push(Pos2::new(center(*i), value_y(plot, point.expected, range)));
+        }
+        painter.extend(Shape::dashed_line(
+            &line,
+            Stroke::new(2.0, Color32::RED),
+            6.0,
+            4.0,
+        ));
+        for (point, (_, projection)) in line[line.len() - projected.len()..].iter().zip(&projected)
+        {
+            painter.circle_filled(*point, POINT_RADIUS, Color32::RED);
+            painter.text(
+                *point - Vec2::new(0.0, POINT_RADIUS + 2.0),
+                Align2::CENTER_BOTTOM,
+                format!("{:.0}", projection.expected),
+                FontId::proportional(12.0),
+                Color32::RED,
+            );
+        }
+        draw_legend(
+            ui,
+            &painter,
+            plot,
+            ["Spent", "Projected"],
+            [Color32::BLUE, Color32::RED],
+        );
+    }
+
+    let clicked = response.clicked();
+    let hovered = hovered?;
+    let (label, amount) = &series[hovered];
+    let mut text = tooltip_text(label, *amount, None);
+    if let Some(point) = projected_at(label) {
+        text.push_str(&format!(
+            "\nProjected: {:.2} ({:.2} to {:.2})",
+            point.expected, point.low, point.high
+        ));
+    }
+    response
+        .on_hover_cursor(egui::CursorIcon::PointingHand)
+        .on_hover_text_at_pointer(text);
+    (clicked && data.contains_key(label)).then(|| label.clone())
+}
+
+pub fn line_chart(ui: &mut egui::Ui, title: &str, data: &HashMap<String, f32>) {
+    ui.label(egui::RichText::new(title).strong());
+    let series = sorted_series(data);
+    let Some((response, painter, plot, range)) = allocate_plot(ui, &series, Sense::hover()) else {
+        return;
+    };
+    let hovered = response
+        .hover_pos()
+        .and_then(|pos| column_at(series.len(), plot, pos.x));
+
+    let column_width = plot.width() / series.len() as f32;
+    let points: Vec<Pos2> = series
+        .iter()
+        .enumerate()
+        .map(|(i, (_, amount))| {
+            Pos2::new(
+                plot.left() + column_width * (i as f32 + 0.5),
+                value_y(plot, *amount, range),
+            )
+        })
+        .collect();
+    painter.add(Shape::line(points.clone(), Stroke::new(2.0, Color32::RED)));
+    for (i, point) in points.iter().enumerate() {
+        let radius = if hovered == Some(i) {
+            POINT_RADIUS * 1.5
+        } else {
+            POINT_RADIUS
+        };
+        painter.circle_filled(*point, radius, Color32::RED);
+        column_label(ui, &painter, plot, point.x, &series[i].0);
+    }
+
+    if let Some(i) = hovered {
+        let (label, amount) = &series[i];
+        response.on_hover_text_at_pointer(tooltip_text(label, *amount, None));
+    }
+}
+
+// Two bars per label, the baseline next to the current value; series_names name them in the
+// legend and tooltips
+pub fn grouped_bar_chart(
+    ui: &mut egui::Ui,
+    title: &str,
+    series_names: [&str; 2],
+    groups: &[(String, [f32; 2])],
+) {
+    ui.label(egui::RichText::new(title).strong());
+    let tallest: Vec<(String, f32)> = groups
+        .iter()
+        .map(|(label, values)| (label.clone(), values[0].max(values[1])))
+        .collect();
+    let Some((response, painter, plot, range)) = allocate_plot(ui, &tallest, Sense::hover()) else {
+        return;
+    };
+    let hovered = response
+        .hover_pos()
+        .and_then(|pos| column_at(groups.len(), plot, pos.x));
+
+    let colors = [Color32::GRAY, Color32::BLUE];
+    let column_width = plot.width() / groups.len() as f32;
+    for (i, (label, values)) in groups.iter().enumerate() {
+        let left = plot.left() + column_width * i as f32;
+        if hovered == Some(i) {
+            let column = Rect::from_x_y_ranges(left..=left + column_width, plot.y_range());
+            painter.rect_filled(column, 0.0, ui.visuals().faint_bg_color);
+        }
+        for (series, value) in values.iter().enumerate() {
+            let bar_left = left + column_width * (0.1 + 0.4 * series as f32);
+            let bar = Rect::from_two_pos(
+                Pos2::new(bar_left, value_y(plot, *value, range)),
+                Pos2::new(bar_left + column_width * 0.4, value_y(plot, 0.0, range)),
+            );
+            painter.rect_filled(bar, 0.0, colors[series]);
+        }
+        column_label(ui, &painter, plot, left + column_width / 2.0, label);
+    }
+
+    draw_legend(ui, &painter, plot, series_names, colors);
+
+    if let Some(i) = hovered {
+        let (label, values) = &groups[i];
+        response.on_hover_text_at_pointer(format!(
+            "{}\n{}\n{}",
+            label,
+            tooltip_text(series_names[0], values[0], None),
+            tooltip_text(series_names[1], values[1], None)
+        ));
+    }
+}
+
+// Two lines over the same labels, e.g. the balance left under two plans
+pub fn paired_line_chart(
+    ui: &mut egui::Ui,
+    title: &str,
+    series_names: [&str; 2],
+    points: &[(String, [f32; 2])],
+) {
+    ui.label(egui::RichText::new(title).strong());
+    let highest: Vec<(String, f32)> = points
+        .iter()
+        .map(|(label, values)| (label.clone(), values[0].max(values[1])))
+        .collect();
+    let Some((response, painter, plot, range)) = allocate_plot(ui, &highest, Sense::hover()) else {
+        return;
+    };
+    let hovered = response
+        .hover_pos()
+        .and_then(|pos| column_at(points.len(), plot, pos.x));
+
+    let colors = [Color32::RED, Color32::BLUE];
+    let column_width = plot.width() / points.len() as f32;
+    let x = |i: usize| plot.left() + column_width * (i as f32 + 0.5);
+    for (series, color) in colors.iter().enumerate() {
+        let line: Vec<Pos2> = points
+            .iter()
+            .enumerate()
+            .map(|(i, (_, values))| Pos2::new(x(i), value_y(plot, values[series], range)))
+            .collect();
+        painter.add(Shape::line(line, Stroke::new(2.0, *color)));
+    }
+    let label_every = points.len().div_ceil(MAX_AXIS_LABELS);
+    for (i, (label, values)) in points.iter().enumerate() {
+        if hovered == Some(i) {
+            for (value, color) in values.iter().zip(colors) {
+                let point = Pos2::new(x(i), value_y(plot, *value, range));
+                painter.circle_filled(point, POINT_RADIUS, color);
+            }
+        }
+        if i % label_every == 0 {
+            column_label(ui, &painter, plot, x(i), label);
+        }
+    }
+    draw_legend(ui, &painter, plot, series_names, colors);
+
+    if let Some(i) = hovered {
+        let (label, values) = &points[i];
+        response.on_hover_text_at_pointer(format!(
+            "{}\n{}\n{}",
+            label,
+            tooltip_text(series_names[0], values[0], None),
+            tooltip_text(series_names[1], values[1], None)
+        ));
+    }
+}
+
+// A grid of cells shaded by value; cells[row][column], with the largest cell the darkest
+pub fn heatmap(
+    ui: &mut egui::Ui,
+    title: &str,
+    row_labels: &[&str],
+    column_labels: &[String],
+    cells: &[Vec<f32>],
+) {
+    ui.label(egui::RichText::new(title).strong());
+    if column_labels.is_empty() || row_labels.is_empty() {
+        ui.label("No expenses to chart yet.");
+        return;
+    }
+    let (response, painter) = ui.allocate_painter(CHART_SIZE, Sense::hover());
+    let rect = response.rect;
+    let plot = Rect::from_min_max(
+        Pos2::new(rect.left() + AXIS_MARGIN, rect.top() + 10.0),
+        Pos2::new(rect.right() - 10.0, rect.bottom() - AXIS_MARGIN),
+    );
+    let highest = cells.iter().flatten().copied().fold(0.0, f32::max);
+    let cell_size = Vec2::new(
+        plot.width() / column_labels.len() as f32,
+        plot.height() / row_labels.len() as f32,
+    );
+    let grid = Stroke::new(0.5, ui.visuals().weak_text_color());
+    for (row, label) in row_labels.iter().enumerate() {
+        let top = plot.top() + cell_size.y * row as f32;
+        painter.text(
+            Pos2::new(plot.left() - 6.0, top + cell_size.y / 2.0),
+            Align2::RIGHT_CENTER,
+            label,
+            FontId::proportional(12.0),
+            ui.visuals().text_color(),
+        );
+        for (column, value) in cells[row].iter().enumerate() {
+            let cell = Rect::from_min_size(
+                Pos2::new(plot.left() + cell_size.x * column as f32, top),
+                cell_size,
+            );
+            let intensity = if highest > 0.0 { value / highest } else { 0.0 };
+            painter.rect_filled(cell, 0.0, heat_color(intensity));
+            painter.rect_stroke(cell, 0.0, grid);
+        }
+    }
+    let label_every = column_labels.len().div_ceil(MAX_AXIS_LABELS);
+    for (column, label) in column_labels.iter().enumerate() {
+        if column % label_every == 0 {
+            let x = plot.left() + cell_size.x * (column as f32 + 0.5);
+            column_label(ui, &painter, plot, x, label);
+        }
+    }
+
+    let hovered = response.hover_pos().and_then(|pos| {
+        let column = column_at(column_labels.len(), plot, pos.x)?;
+        if pos.y < plot.top() || pos.y >= plot.bottom() {
+            return None;
+        }
+        let row = ((pos.y - plot.top()) / cell_size.y) as usize;
+        Some((row.min(row_labels.len() - 1), column))
+    });
+    if let Some((row, column)) = hovered {
+        let label = format!("{} {}", row_labels[row], column_labels[column]);
+        response.on_hover_text_at_pointer(tooltip_text(&label, cells[row][column], None));
+    }
+}
+
+fn draw_legend(
+    ui: &egui::Ui,
+    painter: &egui::Painter,
+    plot: Rect,
+    series_names: [&str; 2],
+    colors: [Color32; 2],
+) {
+    let mut legend_pos = Pos2::new(plot.right() - 150.0, plot.top());
+    for (name, color) in series_names.iter().zip(colors) {
+        painter.rect_filled(
+            Rect::from_min_size(legend_pos, Vec2::splat(10.0)),
+            2.0,
+            color,
+        );
+        painter.text(
+            legend_pos + Vec2::new(14.0, 5.0),
+            Align2::LEFT_CENTER,
+            name,
+            FontId::proportional(12.0),
+            ui.visuals().text_color(),
+        );
+        legend_pos.y += 16.0;
+    }
+}
+
+// What the vertical axis spans; always includes zero so bars have a baseline
+#[derive(Clone, Copy)]
+struct ValueRange {
+    min: f32,
+    max: f32,
+}
+
+// Reserves the chart area and draws the axes; None (after a note) when there is nothing to plot
+fn allocate_plot(
+    ui: &mut egui::Ui,
+    series: &[(String, f32)],
+    sense: Sense,
+) -> Option<(egui::Response, egui::Painter, Rect, ValueRange)> {
+    if series.is_empty() {
+        ui.label("No expenses to chart yet.");
+        return None;
+    }
+    let (response, painter) = ui.allocate_painter(CHART_SIZE, sense);
+    let rect = response.rect;
+    let plot = Rect::from_min_max(
+        Pos2::new(rect.left() + AXIS_MARGIN * 1.5, rect.top() + 10.0),
+        Pos2::new(rect.right() - 10.0, rect.bottom() - AXIS_MARGIN),
+    );
+    let min = series.iter().map(|(_, amount)| *amount).fold(0.0, f32::min);
+    let max = series
+        .iter()
+        .map(|(_, amount)| *amount)
+        .fold(0.0, f32::max)
+        .max(min + f32::EPSILON);
+    let range = ValueRange { min, max };
+
+    let axis = Stroke::new(1.0, ui.visuals().weak_text_color());
+    let zero = value_y(plot, 0.0, range);
+    painter.line_segment(
+        [Pos2::new(plot.left(), zero), Pos2::new(plot.right(), zero)],
+        axis,
+    );
+    painter.line_segment([plot.left_bottom(), plot.left_top()], axis);
+    for step in 0..=4 {
+        let value = min + (max - min) * step as f32 / 4.0;
+        let y = value_y(plot, value, range);
+        painter.line_segment(
+            [Pos2::new(plot.left() - 4.0, y), Pos2::new(plot.left(), y)],
+            axis,
+        );
+        painter.text(
+            Pos2::new(plot.left() - 6.0, y),
+            Align2::RIGHT_CENTER,
+            format!("{:.0}", value),
+            FontId::proportional(12.0),
+            ui.visuals().text_color(),
+        );
+    }
+    Some((response, painter, plot, range))
+}
+
+fn value_y(plot: Rect, value: f32, range: ValueRange) -> f32 {
+    plot.bottom() - plot.height() * ((value - range.min) / (range.max - range.min)).max(0.0)
+}
+
+fn column_label(ui: &egui::Ui, painter: &egui::Painter, plot: Rect, x: f32, label: &str) {
+    painter.text(
+        Pos2::new(x, plot.bottom() + 6.0),
+        Align2::CENTER_TOP,
+        label,
+        FontId::proportional(12.0),
+        ui.visuals().text_color(),
+    );
+}
+
+fn direction(angle: f32) -> Vec2 {
+    Vec2::new(angle.sin(), -angle.cos())
+}
+
+// Triangle fan with a vertex every few degrees, so large slices stay round
+fn add_slice(mesh: &mut Mesh, center: Pos2, radius: f32, slice: &PieSlice, color: Color32) {
+    let sweep = slice.end_angle - slice.start_angle;
+    let segments = ((sweep / TAU * 128.0).ceil() as usize).max(1);
+    let first = mesh.vertices.len() as u32;
+    mesh.colored_vertex(center, color);
+    for step in 0..=segments {
+        let angle = slice.start_angle + sweep * step as f32 / segments as f32;
+        mesh.colored_vertex(center + direction(angle) * radius, color);
+    }
+    for step in 0..segments as u32 {
+        mesh.add_triangle(first, first + 1 + step, first + 2 + step);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::models::Expense;
+use std::collections::{HashMap, HashSet};
+
+// Predictions below this probability are not used to preselect a category
+pub const MIN_SUGGESTION_CONFIDENCE: f32 = 0.5;
+
+// Multinomial naive Bayes over description words plus the payment method, trained on past expenses
+#[derive(Default)]
+pub struct CategoryClassifier {
+    category_counts: HashMap<String, usize>,
+    token_counts: HashMap<String, HashMap<String, usize>>,
+    category_token_totals: HashMap<String, usize>,
+    vocabulary: HashSet<String>,
+    total_examples: usize,
+}
+
+pub fn tokenize(description: &str, payment_method: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = description
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        // Store numbers and reference codes say little about the category
+        .filter(|token| token.len() >= 2 && !token.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_string)
+        .collect();
+    if !payment_method.is_empty() {
+        tokens.push(format!("payment:{}", payment_method.to_lowercase()));
+    }
+    tokens
+}
+
+impl CategoryClassifier {
+    pub fn train(expenses: &[Expense]) -> Self {
+        let mut classifier = CategoryClassifier::default();
+        for expense in expenses {
+            classifier.add_example(
+                &expense.description,
+                &expense.payment_method,
+                &expense.category,
+            );
+        }
+        classifier
+    }
+
+    pub fn add_example(&mut self, description: &str, payment_method: &str, category: &str) {
+        if category.is_empty() {
+            return;
+        }
+        self.total_examples += 1;
+        *self
+            .category_counts
+            .entry(category.to_string())
+            .or_insert(0) += 1;
+
+        let counts = self.token_counts.entry(category.to_string()).or_default();
+        for token in tokenize(description, payment_method) {
+            *counts.entry(token.clone()).or_insert(0) += 1;
+            *self
+                .category_token_totals
+                .entry(category.to_string())
+                .or_insert(0) += 1;
+            self.vocabulary.insert(token);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total_examples == 0
+    }
+
+    // Returns the most likely category and its probability, or None before any training data
+    pub fn predict(&self, description: &str, payment_method: &str) -> Option<(String, f32)> {
+        if self.is_empty() {
+            return None;
+        }
+        let tokens = tokenize(description, payment_method);
+        let vocabulary_size = self.vocabulary.len() as f64;
+
+        // Log-space scores with Laplace smoothing so unseen words don't zero out a category
+        let scores: Vec<(&String, f64)> = self
+            .category_counts
+            .iter()
+            .map(|(category, &count)| {
+                let prior = (count as f64 / self.total_examples as f64).ln();
+                let token_total = *self.category_token_totals.get(category).unwrap_or(&0) as f64;
+                let counts = self.token_counts.get(category);
+                let likelihood: f64 = tokens
+                    .iter()
+                    .map(|token| {
+                        let token_count = counts
+                            .and_then(|counts| counts.get(token))
+                            .copied()
+                            .unwrap_or(0) as f64;
+                        ((token_count + 1.0) / (token_total + vocabulary_size)).ln()
+                    })
+                    .sum();
+                (category, prior + likelihood)
+            })
+            .collect();
+
+        let max_score = scores
+            .iter()
+            .map(|(_, score)| *score)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let normalizer: f64 = scores
+            .iter()
+            .map(|(_, score)| (score - max_score).exp())
+            .sum();
+        scores
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(category, score)| {
+                (
+                    category.to_string(),
+                    ((score - max_score).exp() / normalizer) as f32,
+                )
+            })
+    }
+}
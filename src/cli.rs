@@ -0,0 +1,524 @@
+use crate::app::is_password_valid;
+use crate::import_export;
+use crate::models::{self, Expense, MyError, User};
+use crate::payees;
+use crate::periods::Period;
+use crate::server::{self, ApiServer};
+use crate::statement::{self, Statement};
+use crate::storage::{ExpenseStore, SqliteStore};
+use chrono::{NaiveDate, NaiveTime};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+use rusqlite::Connection;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "expense_tracker", version, about = "Personal expense tracker")]
+pub struct Cli {
+    /// Path to the SQLite database
+    #[arg(long, global = true, default_value = models::DEFAULT_DATABASE_PATH)]
+    pub db: String,
+
+    /// Print machine-readable JSON instead of text
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Use a server started with `serve` (e.g. http://192.168.1.5:8080) instead of the local database
+    #[arg(long)]
+    pub server: Option<String>,
+
+    /// Start the interactive terminal UI instead of the GUI
+    #[arg(long)]
+    pub tui: bool,
+
+    /// Runs the GUI when no subcommand is given
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Add an expense
+    Add {
+        /// Negative for a refund or credit
+        #[arg(long, value_parser = parse_amount_arg, allow_negative_numbers = true)]
+        amount: f32,
+        #[arg(long)]
+        description: String,
+        /// Defaults to today (YYYY-MM-DD)
+        #[arg(long)]
+        date: Option<String>,
+        /// Time of day (HH:MM), used by the spending patterns heatmap
+        #[arg(long)]
+        time: Option<String>,
+        /// Assigned by the user's rules or learned history when omitted
+        #[arg(long)]
+        category: Option<String>,
+        #[arg(long, default_value = "Card")]
+        payment_method: String,
+        #[arg(long, default_value = "")]
+        tags: String,
+        /// Apply this user's categorization rules
+        #[arg(long)]
+        user: Option<String>,
+    },
+    /// List expenses
+    List {
+        #[arg(long)]
+        category: Option<String>,
+        /// First date to include (YYYY-MM-DD)
+        #[arg(long, value_parser = parse_date_arg)]
+        from: Option<String>,
+        /// Last date to include (YYYY-MM-DD)
+        #[arg(long, value_parser = parse_date_arg)]
+        to: Option<String>,
+        /// Only show the most recent N expenses
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Delete an expense and its receipts
+    Delete { id: i32 },
+    /// Show spending totals by category and payee
+    Report {
+        #[arg(long, value_parser = parse_date_arg)]
+        from: Option<String>,
+        #[arg(long, value_parser = parse_date_arg)]
+        to: Option<String>,
+    },
+    /// Save a PDF statement with totals, charts and every transaction in the period
+    Statement {
+        output: PathBuf,
+        /// Month to cover (YYYY-MM); defaults to last month
+        #[arg(long, conflicts_with_all = ["from", "to"])]
+        month: Option<String>,
+        /// First date of a custom range (YYYY-MM-DD)
+        #[arg(long, requires = "to", value_parser = parse_date_arg)]
+        from: Option<String>,
+        /// Last date of a custom range (YYYY-MM-DD)
+        #[arg(long, requires = "from", value_parser = parse_date_arg)]
+        to: Option<String>,
+        /// Include this user's savings goals and debts in the budget status
+        #[arg(long)]
+        user: Option<String>,
+    },
+    /// Import expenses from a CSV file (date,amount,category,description,payment_method,tags,time)
+    Import {
+        file: PathBuf,
+        /// Apply this user's categorization rules
+        #[arg(long)]
+        user: Option<String>,
+        /// Show what would be imported without saving
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Export all expenses as CSV, or JSON with --json
+    Export {
+        /// Write to a file instead of standard output
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Serve the JSON API over HTTP
+    Serve {
+        /// Address to listen on; use 0.0.0.0 to accept connections from the LAN
+        #[arg(long, default_value = server::DEFAULT_HOST)]
+        host: String,
+        #[arg(long, default_value_t = server::DEFAULT_PORT)]
+        port: u16,
+    },
+    /// Manage user accounts
+    User {
+        #[command(subcommand)]
+        command: UserCommand,
+    },
+}
+
+impl Cli {
+    // Checks that need more than one argument, which clap's value parsers can't see
+    pub fn validate(&self) -> Result<(), clap::Error> {
+        let range = match &self.command {
+            Some(Command::List { from, to, .. })
+            | Some(Command::Report { from, to })
+            | Some(Command::Statement { from, to, .. }) => (from, to),
+            _ => return Ok(()),
+        };
+        if let (Some(from), Some(to)) = range {
+            // Both were normalized to YYYY-MM-DD, so string order is date order
+            if from > to {
+                return Err(Cli::command().error(
+                    ErrorKind::ValueValidation,
+                    format!("--from {} is after --to {}", from, to),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Subcommand)]
+pub enum UserCommand {
+    /// Create a new user
+    Create {
+        username: String,
+        /// Read from standard input when omitted
+        #[arg(long)]
+        password: Option<String>,
+    },
+}
+
+// Opens (and if needed creates) the database at `db` for the one command
+pub fn run(
+    command: Command,
+    db: &str,
+    json_output: bool,
+    out: &mut dyn Write,
+) -> Result<(), MyError> {
+    let mut store = SqliteStore::open(db)?;
+    let conn = store.connection();
+    match command {
+        Command::Add {
+            amount,
+            description,
+            date,
+            time,
+            category,
+            payment_method,
+            tags,
+            user,
+        } => {
+            let date = match date {
+                Some(date) => parse_date(&date)?,
+                None => chrono::Local::now().format("%Y-%m-%d").to_string(),
+            };
+            let mut expenses = vec![Expense {
+                id: 0,
+                date,
+                amount,
+                category: category.unwrap_or_default(),
+                description,
+                payment_method,
+                tags,
+                payee_id: None,
+                time: time.as_deref().map(parse_time).transpose()?,
+            }];
+            categorize(conn, &mut expenses, user.as_deref())?;
+            let expense = &mut expenses[0];
+            expense.id = models::add_expense(conn, expense)?;
+            if json_output {
+                writeln!(out, "{}", to_json(&*expense))?;
+            } else {
+                writeln!(
+                    out,
+                    "Added expense {} ({:.2} {})",
+                    expense.id, expense.amount, expense.category
+                )?;
+            }
+        }
+        Command::List {
+            category,
+            from,
+            to,
+            limit,
+        } => {
+            let mut expenses = filter_expenses(models::get_expenses(conn)?, from, to)?;
+            if let Some(category) = category {
+                expenses.retain(|expense| expense.category.eq_ignore_ascii_case(&category));
+            }
+            expenses.sort_by(|a, b| a.date.cmp(&b.date).then(a.id.cmp(&b.id)));
+            if let Some(limit) = limit {
+                let skip = expenses.len().saturating_sub(limit);
+                expenses.drain(..skip);
+            }
+            if json_output {
+                writeln!(out, "{}", to_json(&expenses))?;
+            } else {
+                print_expense_table(out, &expenses)?;
+            }
+        }
+        Command::Delete { id } => {
+            if !models::get_expenses(conn)?
+                .iter()
+                .any(|expense| expense.id == id)
+            {
+                return Err(MyError::InvalidInput(format!("no expense with id {}", id)));
+            }
+            // Through the store so the receipt copies go too
+            store.delete_expense(id)?;
+            if json_output {
+                writeln!(out, "{}", json!({ "deleted": id }))?;
+            } else {
+                writeln!(out, "Deleted expense {}", id)?;
+            }
+        }
+        Command::Report { from, to } => {
+            let expenses = filter_expenses(models::get_expenses(conn)?, from, to)?;
+            let total: f32 = expenses.iter().map(|expense| expense.amount).sum();
+            let mut category_totals: HashMap<String, f32> = HashMap::new();
+            for expense in &expenses {
+                *category_totals
+                    .entry(expense.category.clone())
+                    .or_insert(0.0) += expense.amount;
+            }
+            let payee_totals =
+                payees::calculate_payee_totals(&expenses, &models::get_payees(conn)?);
+            if json_output {
+                writeln!(
+                    out,
+                    "{}",
+                    json!({
+                        "count": expenses.len(),
+                        "total": total,
+                        "categories": category_totals,
+                        "payees": payee_totals,
+                    })
+                )?;
+            } else {
+                writeln!(out, "Total: {:.2} over {} expenses", total, expenses.len())?;
+                writeln!(out, "\nBy category:")?;
+                print_totals(out, &category_totals)?;
+                writeln!(out, "\nBy payee:")?;
+                print_totals(out, &payee_totals)?;
+            }
+        }
+        Command::Statement {
+            output,
+            month,
+            from,
+            to,
+            user,
+        } => {
+            let today = chrono::Local::now().date_naive();
+            let period = match (month, from, to) {
+                (Some(month), _, _) => parse_month(&month)?,
+                (None, Some(from), Some(to)) => Period::Custom {
+                    from: parse_day(&from)?,
+                    to: parse_day(&to)?,
+                },
+                _ => Period::last_month(today),
+            };
+            let (goals, debts) = match find_user_id(conn, user.as_deref())? {
+                Some(user_id) => (
+                    models::get_goals(conn, user_id)?,
+                    models::get_debts(conn, user_id)?,
+                ),
+                None => (Vec::new(), Vec::new()),
+            };
+            let statement =
+                Statement::new(&models::get_expenses(conn)?, &period, &goals, &debts, today);
+            statement::write_statement(&statement, &output)?;
+            if json_output {
+                writeln!(
+                    out,
+                    "{}",
+                    json!({
+                        "path": output,
+                        "period": period.label(),
+                        "count": statement.transactions.len(),
+                        "spent": statement.spent,
+                    })
+                )?;
+            } else {
+                writeln!(
+                    out,
+                    "Saved the {} statement ({} expenses) to {}",
+                    period.label(),
+                    statement.transactions.len(),
+                    output.display()
+                )?;
+            }
+        }
+        Command::Import {
+            file,
+            user,
+            dry_run,
+        } => {
+            let mut expenses = import_export::read_expenses_csv(File::open(&file)?)?;
+            categorize(conn, &mut expenses, user.as_deref())?;
+            if !dry_run {
+                let ids = models::add_expenses(conn, &expenses)?;
+                for (expense, id) in expenses.iter_mut().zip(ids) {
+                    expense.id = id;
+                }
+            }
+            if json_output {
+                writeln!(
+                    out,
+                    "{}",
+                    json!({ "imported": if dry_run { 0 } else { expenses.len() }, "expenses": expenses })
+                )?;
+            } else {
+                print_expense_table(out, &expenses)?;
+                if dry_run {
+                    writeln!(
+                        out,
+                        "Dry run: {} expenses would be imported",
+                        expenses.len()
+                    )?;
+                } else {
+                    writeln!(out, "Imported {} expenses", expenses.len())?;
+                }
+            }
+        }
+        Command::Export { output } => {
+            let expenses = models::get_expenses(conn)?;
+            let mut writer: Box<dyn Write + '_> = match output {
+                Some(path) => Box::new(File::create(path)?),
+                None => Box::new(&mut *out),
+            };
+            if json_output {
+                writeln!(writer, "{}", to_json(&expenses))?;
+            } else {
+                import_export::write_expenses_csv(&mut writer, &expenses)?;
+            }
+        }
+        Command::Serve { host, port } => {
+            let api_server = ApiServer::bind(&format!("{}:{}", host, port), store)?;
+            if let Some(address) = api_server.local_addr() {
+                writeln!(out, "Serving on http://{}", address)?;
+                out.flush()?;
+            }
+            api_server.run();
+        }
+        Command::User {
+            command: UserCommand::Create { username, password },
+        } => {
+            let password = match password {
+                Some(password) => password,
+                None => read_password()?,
+            };
+            if !models::is_username_unique(conn, &username)? {
+                return Err(MyError::InvalidInput("Username already exists".to_string()));
+            }
+            if !is_password_valid(&password) {
+                return Err(MyError::InvalidInput(
+                    "Password must be at least 5 characters long, include a number and a symbol"
+                        .to_string(),
+                ));
+            }
+            let user = User {
+                id: 0,
+                username: username.clone(),
+                password_hash: String::new(), // This will be set in add_user
+            };
+            models::add_user(conn, &user, &password)?;
+            if json_output {
+                writeln!(out, "{}", json!({ "created": username }))?;
+            } else {
+                writeln!(out, "Created user {}", username)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn categorize(
+    conn: &Connection,
+    expenses: &mut [Expense],
+    username: Option<&str>,
+) -> Result<(), MyError> {
+    import_export::categorize_from_database(conn, expenses, find_user_id(conn, username)?)
+}
+
+fn find_user_id(conn: &Connection, username: Option<&str>) -> Result<Option<i32>, MyError> {
+    match username {
+        Some(username) => Ok(Some(models::get_user_id(conn, username)?.ok_or_else(
+            || MyError::InvalidInput(format!("no user named \"{}\"", username)),
+        )?)),
+        None => Ok(None),
+    }
+}
+
+pub(crate) fn parse_date(date: &str) -> Result<String, MyError> {
+    parse_day(date).map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+fn parse_day(date: &str) -> Result<NaiveDate, MyError> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| MyError::InvalidInput(format!("\"{}\" is not a YYYY-MM-DD date", date)))
+}
+
+fn parse_date_arg(date: &str) -> Result<String, String> {
+    parse_date(date).map_err(|e| e.to_string())
+}
+
+// Negative amounts are refunds and credits like everywhere else; NaN, infinity and zero are
+// rejected up front
+fn parse_amount_arg(amount: &str) -> Result<f32, String> {
+    match amount.parse::<f32>() {
+        Ok(amount) if amount.is_finite() && amount != 0.0 => Ok(amount),
+        _ => Err(format!("\"{}\" is not a non-zero amount", amount)),
+    }
+}
+
+pub(crate) fn parse_time(time: &str) -> Result<String, MyError> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .map(|time| time.format("%H:%M").to_string())
+        .map_err(|_| MyError::InvalidInput(format!("\"{}\" is not an HH:MM time", time)))
+}
+
+fn parse_month(month: &str) -> Result<Period, MyError> {
+    NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+        .map(Period::this_month)
+        .map_err(|_| MyError::InvalidInput(format!("\"{}\" is not a YYYY-MM month", month)))
+}
+
+// Dates are stored as YYYY-MM-DD, so string comparison orders them correctly
+pub(crate) fn filter_expenses(
+    mut expenses: Vec<Expense>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<Expense>, MyError> {
+    if let Some(from) = from {
+        let from = parse_date(&from)?;
+        expenses.retain(|expense| expense.date >= from);
+    }
+    if let Some(to) = to {
+        let to = parse_date(&to)?;
+        expenses.retain(|expense| expense.date <= to);
+    }
+    Ok(expenses)
+}
+
+fn to_json<T: serde::Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+fn print_expense_table(out: &mut dyn Write, expenses: &[Expense]) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:>5}  {:<10}  {:>10}  {:<24}  {:<8}  Description",
+        "ID", "Date", "Amount", "Category", "Payment"
+    )?;
+    for expense in expenses {
+        writeln!(
+            out,
+            "{:>5}  {:<10}  {:>10.2}  {:<24}  {:<8}  {}",
+            expense.id,
+            expense.date,
+            expense.amount,
+            expense.category,
+            expense.payment_method,
+            expense.description
+        )?;
+    }
+    Ok(())
+}
+
+fn print_totals(out: &mut dyn Write, totals: &HashMap<String, f32>) -> io::Result<()> {
+    let mut sorted: Vec<(&String, &f32)> = totals.iter().collect();
+    sorted.sort_by(|a, b| b.1.total_cmp(a.1));
+    for (name, amount) in sorted {
+        writeln!(out, "  {:<38} {:>10.2}", name, amount)?;
+    }
+    Ok(())
+}
+
+fn read_password() -> Result<String, MyError> {
+    eprint!("Password: ");
+    let mut password = String::new();
+    io::stdin().lock().read_line(&mut password)?;
+    Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
@@ -0,0 +1,121 @@
+use crate::models::Expense;
+use crate::periods::Period;
+use std::collections::HashMap;
+
+// What the selected period is compared against
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Baseline {
+    #[default]
+    PreviousPeriod,
+    SamePeriodLastYear,
+}
+
+impl Baseline {
+    pub fn period(&self, current: &Period) -> Period {
+        match self {
+            Baseline::PreviousPeriod => current.previous(),
+            Baseline::SamePeriodLastYear => current.year_earlier(),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Baseline::PreviousPeriod => "Previous period",
+            Baseline::SamePeriodLastYear => "Same period last year",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GroupBy {
+    #[default]
+    Category,
+    PaymentMethod,
+}
+
+impl GroupBy {
+    fn key<'a>(&self, expense: &'a Expense) -> &'a str {
+        match self {
+            GroupBy::Category => &expense.category,
+            GroupBy::PaymentMethod => &expense.payment_method,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GroupBy::Category => "Category",
+            GroupBy::PaymentMethod => "Payment Method",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ComparisonOptions {
+    pub baseline: Baseline,
+    pub group_by: GroupBy,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComparisonRow {
+    pub key: String,
+    pub current: f32,
+    pub previous: f32,
+}
+
+impl ComparisonRow {
+    pub fn change(&self) -> f32 {
+        self.current - self.previous
+    }
+
+    // None when there was nothing to compare against
+    pub fn percent_change(&self) -> Option<f32> {
+        (self.previous != 0.0).then(|| self.change() / self.previous * 100.0)
+    }
+
+    pub fn percent_label(&self) -> String {
+        match self.percent_change() {
+            Some(percent) => format!("{:+.1}%", percent),
+            None => "new".to_string(),
+        }
+    }
+}
+
+// Totals per group in both periods, largest absolute change first
+pub fn compare_periods(
+    expenses: &[Expense],
+    current: &Period,
+    previous: &Period,
+    group_by: GroupBy,
+) -> Vec<ComparisonRow> {
+    let mut totals: HashMap<&str, (f32, f32)> = HashMap::new();
+    for expense in expenses {
+        let in_current = current.contains(&expense.date);
+        let in_previous = previous.contains(&expense.date);
+        if !in_current && !in_previous {
+            continue;
+        }
+        let entry = totals.entry(group_by.key(expense)).or_insert((0.0, 0.0));
+        if in_current {
+            entry.0 += expense.amount;
+        }
+        if in_previous {
+            entry.1 += expense.amount;
+        }
+    }
+
+    let mut rows: Vec<ComparisonRow> = totals
+        .into_iter()
+        .map(|(key, (current, previous))| ComparisonRow {
+            key: key.to_string(),
+            current,
+            previous,
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        b.change()
+            .abs()
+            .total_cmp(&a.change().abs())
+            .then(a.key.cmp(&b.key))
+    });
+    rows
+}
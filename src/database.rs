@@ -0,0 +1,805 @@
+use crate::models::{
+    Asset, Attachment, Cadence, CategoryRule, Debt, DueCharges, Expense, Goal, MyError,
+    NetWorthSnapshot, Payee, PayeeAlias, RecurringSchedule, ReviewStatus, User,
+};
+use crate::rules;
+use bcrypt::verify;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use std::collections::HashMap;
+use std::time::Duration;
+
+// Enough for every statement in this file, so hot paths never re-prepare their SQL
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+
+// WAL lets readers on other threads (or processes) work while a write is in progress
+pub fn open_database(path: &str) -> Result<Connection> {
+    let conn = Connection::open(path)?;
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    conn.busy_timeout(Duration::from_secs(5))?;
+    conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+    Ok(conn)
+}
+
+pub fn create_expense_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS expenses (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL,
+            amount REAL NOT NULL,
+            category TEXT NOT NULL,
+            description TEXT,
+            payment_method TEXT
+            )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS users (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            username TEXT NOT NULL UNIQUE,
+            password_hash TEXT NOT NULL
+            )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS attachments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            expense_id INTEGER NOT NULL,
+            file_name TEXT NOT NULL,
+            mime_type TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            thumbnail BLOB
+            )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS category_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            description_pattern TEXT NOT NULL,
+            is_regex INTEGER NOT NULL DEFAULT 0,
+            min_amount REAL,
+            max_amount REAL,
+            payment_method TEXT NOT NULL,
+            category TEXT NOT NULL,
+            tags TEXT NOT NULL,
+            rename_to TEXT NOT NULL
+            )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS payees (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE
+            )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS payee_aliases (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            payee_id INTEGER NOT NULL,
+            pattern TEXT NOT NULL
+            )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS anomaly_reviews (
+            expense_id INTEGER PRIMARY KEY,
+            status TEXT NOT NULL
+            )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS goals (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            target_amount REAL NOT NULL,
+            target_date TEXT NOT NULL,
+            category TEXT NOT NULL,
+            tag TEXT NOT NULL DEFAULT '',
+            start_date TEXT NOT NULL
+            )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS debts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            balance REAL NOT NULL,
+            apr REAL NOT NULL,
+            minimum_payment REAL NOT NULL,
+            tag TEXT NOT NULL,
+            start_date TEXT NOT NULL
+            )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS assets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            value REAL NOT NULL
+            )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS net_worth_snapshots (
+            user_id INTEGER NOT NULL,
+            date TEXT NOT NULL,
+            assets REAL NOT NULL,
+            liabilities REAL NOT NULL,
+            PRIMARY KEY (user_id, date)
+            )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS recurring_schedules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            description TEXT NOT NULL,
+            amount REAL NOT NULL,
+            category TEXT NOT NULL,
+            payment_method TEXT NOT NULL,
+            payee_id INTEGER,
+            cadence TEXT NOT NULL,
+            next_date TEXT NOT NULL
+            )",
+        [],
+    )?;
+    add_column_if_missing(conn, "expenses", "tags", "TEXT NOT NULL DEFAULT ''")?;
+    add_column_if_missing(conn, "expenses", "payee_id", "INTEGER")?;
+    add_column_if_missing(conn, "expenses", "time", "TEXT")?;
+    // Schedules saved before this column existed count from their next date
+    add_column_if_missing(conn, "recurring_schedules", "anchor_date", "TEXT")?;
+    Ok(())
+}
+
+// Existing databases were created before some columns existed, so add them in place
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let column_names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in column_names {
+        if name? == column {
+            return Ok(());
+        }
+    }
+    conn.execute(
+        &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+        [],
+    )?;
+    Ok(())
+}
+
+pub fn add_expense(conn: &Connection, expense: &Expense) -> Result<i32> {
+    conn.prepare_cached(
+        "INSERT INTO expenses (date, amount, category, description, payment_method, tags, payee_id, time) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?
+    .execute(params![expense.date, expense.amount, expense.category, expense.description, expense.payment_method, expense.tags, expense.payee_id, expense.time],
+    )?;
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+// All or nothing, so a failed import doesn't leave half a file behind
+pub fn add_expenses(conn: &Connection, expenses: &[Expense]) -> Result<Vec<i32>> {
+    let tx = conn.unchecked_transaction()?;
+    let ids = insert_expenses(&tx, expenses)?;
+    tx.commit()?;
+    Ok(ids)
+}
+
+// Saves the charges and moves their schedules on together, so a charge is never skipped or
+// recorded twice. Returns the new expense ids in order.
+pub fn record_due_charges(conn: &Connection, charges: &[DueCharges]) -> Result<Vec<i32>> {
+    let tx = conn.unchecked_transaction()?;
+    let mut ids = Vec::new();
+    for charge in charges {
+        ids.extend(insert_expenses(&tx, &charge.expenses)?);
+        tx.execute(
+            "UPDATE recurring_schedules SET next_date = ?1 WHERE id = ?2",
+            params![charge.next_date, charge.schedule_id],
+        )?;
+    }
+    tx.commit()?;
+    Ok(ids)
+}
+
+fn insert_expenses(conn: &Connection, expenses: &[Expense]) -> Result<Vec<i32>> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO expenses (date, amount, category, description, payment_method, tags, payee_id, time) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+    let mut ids = Vec::with_capacity(expenses.len());
+    for expense in expenses {
+        stmt.execute(params![
+            expense.date,
+            expense.amount,
+            expense.category,
+            expense.description,
+            expense.payment_method,
+            expense.tags,
+            expense.payee_id,
+            expense.time
+        ])?;
+        ids.push(conn.last_insert_rowid() as i32);
+    }
+    Ok(ids)
+}
+
+pub fn update_expense(conn: &Connection, expense: &Expense) -> Result<()> {
+    conn.prepare_cached(
+        "UPDATE expenses SET date = ?1, amount = ?2, category = ?3, description = ?4, payment_method = ?5, tags = ?6, payee_id = ?7, time = ?8 WHERE id = ?9",
+    )?
+    .execute(params![expense.date, expense.amount, expense.category, expense.description, expense.payment_method, expense.tags, expense.payee_id, expense.time, expense.id],
+    )?;
+    Ok(())
+}
+
+pub fn get_expenses(conn: &Connection) -> Result<Vec<Expense>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, date, amount, category, description, payment_method, tags, payee_id, time FROM expenses",
+    )?;
+    let expense_iter = stmt.query_map([], |row| {
+        Ok(Expense {
+            id: row.get(0)?,
+            date: row.get(1)?,
+            amount: row.get(2)?,
+            category: row.get(3)?,
+            description: row.get(4)?,
+            payment_method: row.get(5)?,
+            tags: row.get(6)?,
+            payee_id: row.get(7)?,
+            time: row.get(8)?,
+        })
+    })?;
+
+    let mut expenses = Vec::new();
+    for expense in expense_iter {
+        expenses.push(expense?);
+    }
+    Ok(expenses)
+}
+
+pub fn add_payee(conn: &Connection, name: &str) -> Result<i32> {
+    conn.execute("INSERT INTO payees (name) VALUES (?1)", params![name])?;
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+pub fn add_payee_alias(conn: &Connection, payee_id: i32, pattern: &str) -> Result<i32> {
+    conn.execute(
+        "INSERT INTO payee_aliases (payee_id, pattern) VALUES (?1, ?2)",
+        params![payee_id, pattern],
+    )?;
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+pub fn delete_payee_alias(conn: &Connection, alias_id: i32) -> Result<()> {
+    conn.execute("DELETE FROM payee_aliases WHERE id = ?1", params![alias_id])?;
+    Ok(())
+}
+
+pub fn get_payees(conn: &Connection) -> Result<Vec<Payee>> {
+    let mut stmt = conn.prepare_cached("SELECT id, name FROM payees ORDER BY name")?;
+    let payee_iter = stmt.query_map([], |row| {
+        Ok(Payee {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            aliases: Vec::new(),
+        })
+    })?;
+    let mut payees = Vec::new();
+    for payee in payee_iter {
+        payees.push(payee?);
+    }
+
+    let mut stmt =
+        conn.prepare_cached("SELECT id, payee_id, pattern FROM payee_aliases ORDER BY id")?;
+    let alias_iter = stmt.query_map([], |row| {
+        Ok(PayeeAlias {
+            id: row.get(0)?,
+            payee_id: row.get(1)?,
+            pattern: row.get(2)?,
+        })
+    })?;
+    for alias in alias_iter {
+        let alias = alias?;
+        if let Some(payee) = payees.iter_mut().find(|payee| payee.id == alias.payee_id) {
+            payee.aliases.push(alias);
+        }
+    }
+    Ok(payees)
+}
+
+// Folds one payee into another: its expenses and aliases move over and its name becomes an alias
+pub fn merge_payees(conn: &Connection, source_id: i32, target_id: i32) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    let source_name: String = tx.query_row(
+        "SELECT name FROM payees WHERE id = ?1",
+        params![source_id],
+        |row| row.get(0),
+    )?;
+    tx.execute(
+        "UPDATE expenses SET payee_id = ?1 WHERE payee_id = ?2",
+        params![target_id, source_id],
+    )?;
+    tx.execute(
+        "UPDATE payee_aliases SET payee_id = ?1 WHERE payee_id = ?2",
+        params![target_id, source_id],
+    )?;
+    tx.execute(
+        "UPDATE recurring_schedules SET payee_id = ?1 WHERE payee_id = ?2",
+        params![target_id, source_id],
+    )?;
+    tx.execute(
+        "INSERT INTO payee_aliases (payee_id, pattern) VALUES (?1, ?2)",
+        params![target_id, source_name],
+    )?;
+    tx.execute("DELETE FROM payees WHERE id = ?1", params![source_id])?;
+    tx.commit()
+}
+
+pub fn add_rule(conn: &Connection, rule: &CategoryRule) -> Result<i32> {
+    // New rules go to the end of the user's list
+    let position: i32 = conn.query_row(
+        "SELECT COALESCE(MAX(position), 0) + 1 FROM category_rules WHERE user_id = ?1",
+        params![rule.user_id],
+        |row| row.get(0),
+    )?;
+    conn.execute(
+        "INSERT INTO category_rules (user_id, position, description_pattern, is_regex, min_amount, max_amount, payment_method, category, tags, rename_to)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            rule.user_id,
+            position,
+            rule.description_pattern,
+            rule.is_regex,
+            rule.min_amount,
+            rule.max_amount,
+            rule.payment_method,
+            rule.category,
+            rule.tags,
+            rule.rename_to
+        ],
+    )?;
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+pub fn get_rules(conn: &Connection, user_id: i32) -> Result<Vec<CategoryRule>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, user_id, position, description_pattern, is_regex, min_amount, max_amount, payment_method, category, tags, rename_to
+        FROM category_rules WHERE user_id = ?1 ORDER BY position",
+    )?;
+    let rule_iter = stmt.query_map(params![user_id], |row| {
+        Ok(CategoryRule {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            position: row.get(2)?,
+            description_pattern: row.get(3)?,
+            is_regex: row.get(4)?,
+            min_amount: row.get(5)?,
+            max_amount: row.get(6)?,
+            payment_method: row.get(7)?,
+            category: row.get(8)?,
+            tags: row.get(9)?,
+            rename_to: row.get(10)?,
+            description_regex: None,
+        })
+    })?;
+
+    let mut rules = Vec::new();
+    for rule in rule_iter {
+        let mut rule = rule?;
+        rule.description_regex = rules::compile_pattern(&rule);
+        rules.push(rule);
+    }
+    Ok(rules)
+}
+
+pub fn delete_rule(conn: &Connection, rule_id: i32) -> Result<()> {
+    conn.execute("DELETE FROM category_rules WHERE id = ?1", params![rule_id])?;
+    Ok(())
+}
+
+pub fn swap_rule_positions(
+    conn: &Connection,
+    first: &CategoryRule,
+    second: &CategoryRule,
+) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE category_rules SET position = ?1 WHERE id = ?2",
+        params![second.position, first.id],
+    )?;
+    tx.execute(
+        "UPDATE category_rules SET position = ?1 WHERE id = ?2",
+        params![first.position, second.id],
+    )?;
+    tx.commit()
+}
+
+pub fn is_username_unique(conn: &Connection, username: &str) -> Result<bool, MyError> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM users WHERE username = ?1",
+        params![username],
+        |row| row.get(0),
+    )?;
+    Ok(count == 0)
+}
+
+pub fn delete_expense(conn: &Connection, expense_id: i32) -> Result<()> {
+    conn.prepare_cached("DELETE FROM expenses WHERE id = ?1")?
+        .execute(params![expense_id])?;
+    conn.prepare_cached("DELETE FROM anomaly_reviews WHERE expense_id = ?1")?
+        .execute(params![expense_id])?;
+    Ok(())
+}
+
+pub fn set_anomaly_review(conn: &Connection, expense_id: i32, status: ReviewStatus) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO anomaly_reviews (expense_id, status) VALUES (?1, ?2)",
+        params![expense_id, status.as_str()],
+    )?;
+    Ok(())
+}
+
+// Unknown statuses are skipped rather than failing the whole load
+pub fn get_anomaly_reviews(conn: &Connection) -> Result<HashMap<i32, ReviewStatus>> {
+    let mut stmt = conn.prepare_cached("SELECT expense_id, status FROM anomaly_reviews")?;
+    let review_iter = stmt.query_map([], |row| {
+        Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    let mut reviews = HashMap::new();
+    for review in review_iter {
+        let (expense_id, status) = review?;
+        if let Some(status) = ReviewStatus::parse(&status) {
+            reviews.insert(expense_id, status);
+        }
+    }
+    Ok(reviews)
+}
+
+pub fn add_recurring_schedule(conn: &Connection, schedule: &RecurringSchedule) -> Result<i32> {
+    conn.execute(
+        "INSERT INTO recurring_schedules (description, amount, category, payment_method, payee_id, cadence, next_date, anchor_date) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            schedule.description,
+            schedule.amount,
+            schedule.category,
+            schedule.payment_method,
+            schedule.payee_id,
+            schedule.cadence.as_str(),
+            schedule.next_date,
+            schedule.anchor_date
+        ],
+    )?;
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+// Schedules with an unknown cadence are skipped rather than failing the whole load
+pub fn get_recurring_schedules(conn: &Connection) -> Result<Vec<RecurringSchedule>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, description, amount, category, payment_method, payee_id, cadence, next_date, COALESCE(anchor_date, next_date) FROM recurring_schedules ORDER BY next_date, id",
+    )?;
+    let schedule_iter = stmt.query_map([], |row| {
+        let cadence: String = row.get(6)?;
+        let Some(cadence) = Cadence::parse(&cadence) else {
+            return Ok(None);
+        };
+        Ok(Some(RecurringSchedule {
+            id: row.get(0)?,
+            description: row.get(1)?,
+            amount: row.get(2)?,
+            category: row.get(3)?,
+            payment_method: row.get(4)?,
+            payee_id: row.get(5)?,
+            cadence,
+            next_date: row.get(7)?,
+            anchor_date: row.get(8)?,
+        }))
+    })?;
+
+    let mut schedules = Vec::new();
+    for schedule in schedule_iter {
+        schedules.extend(schedule?);
+    }
+    Ok(schedules)
+}
+
+pub fn delete_recurring_schedule(conn: &Connection, schedule_id: i32) -> Result<()> {
+    conn.execute(
+        "DELETE FROM recurring_schedules WHERE id = ?1",
+        params![schedule_id],
+    )?;
+    Ok(())
+}
+
+pub fn add_goal(conn: &Connection, goal: &Goal) -> Result<i32> {
+    conn.execute(
+        "INSERT INTO goals (user_id, name, target_amount, target_date, category, tag, start_date) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            goal.user_id,
+            goal.name,
+            goal.target_amount,
+            goal.target_date,
+            goal.category,
+            goal.tag,
+            goal.start_date
+        ],
+    )?;
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+pub fn get_goals(conn: &Connection, user_id: i32) -> Result<Vec<Goal>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, user_id, name, target_amount, target_date, category, tag, start_date
+        FROM goals WHERE user_id = ?1 ORDER BY target_date, id",
+    )?;
+    let goal_iter = stmt.query_map(params![user_id], |row| {
+        Ok(Goal {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            name: row.get(2)?,
+            target_amount: row.get(3)?,
+            target_date: row.get(4)?,
+            category: row.get(5)?,
+            tag: row.get(6)?,
+            start_date: row.get(7)?,
+        })
+    })?;
+
+    let mut goals = Vec::new();
+    for goal in goal_iter {
+        goals.push(goal?);
+    }
+    Ok(goals)
+}
+
+pub fn delete_goal(conn: &Connection, goal_id: i32) -> Result<()> {
+    conn.execute("DELETE FROM goals WHERE id = ?1", params![goal_id])?;
+    Ok(())
+}
+
+pub fn add_debt(conn: &Connection, debt: &Debt) -> Result<i32> {
+    conn.execute(
+        "INSERT INTO debts (user_id, name, balance, apr, minimum_payment, tag, start_date) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            debt.user_id,
+            debt.name,
+            debt.balance,
+            debt.apr,
+            debt.minimum_payment,
+            debt.tag,
+            debt.start_date
+        ],
+    )?;
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+pub fn get_debts(conn: &Connection, user_id: i32) -> Result<Vec<Debt>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, user_id, name, balance, apr, minimum_payment, tag, start_date
+        FROM debts WHERE user_id = ?1 ORDER BY id",
+    )?;
+    let debt_iter = stmt.query_map(params![user_id], |row| {
+        Ok(Debt {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            name: row.get(2)?,
+            balance: row.get(3)?,
+            apr: row.get(4)?,
+            minimum_payment: row.get(5)?,
+            tag: row.get(6)?,
+            start_date: row.get(7)?,
+        })
+    })?;
+
+    let mut debts = Vec::new();
+    for debt in debt_iter {
+        debts.push(debt?);
+    }
+    Ok(debts)
+}
+
+pub fn delete_debt(conn: &Connection, debt_id: i32) -> Result<()> {
+    conn.execute("DELETE FROM debts WHERE id = ?1", params![debt_id])?;
+    Ok(())
+}
+
+pub fn add_asset(conn: &Connection, asset: &Asset) -> Result<i32> {
+    conn.execute(
+        "INSERT INTO assets (user_id, name, value) VALUES (?1, ?2, ?3)",
+        params![asset.user_id, asset.name, asset.value],
+    )?;
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+pub fn get_assets(conn: &Connection, user_id: i32) -> Result<Vec<Asset>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, user_id, name, value FROM assets WHERE user_id = ?1 ORDER BY id",
+    )?;
+    let asset_iter = stmt.query_map(params![user_id], |row| {
+        Ok(Asset {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            name: row.get(2)?,
+            value: row.get(3)?,
+        })
+    })?;
+
+    let mut assets = Vec::new();
+    for asset in asset_iter {
+        assets.push(asset?);
+    }
+    Ok(assets)
+}
+
+pub fn set_asset_value(conn: &Connection, asset_id: i32, value: f32) -> Result<()> {
+    conn.execute(
+        "UPDATE assets SET value = ?1 WHERE id = ?2",
+        params![value, asset_id],
+    )?;
+    Ok(())
+}
+
+pub fn delete_asset(conn: &Connection, asset_id: i32) -> Result<()> {
+    conn.execute("DELETE FROM assets WHERE id = ?1", params![asset_id])?;
+    Ok(())
+}
+
+// A second snapshot on the same day replaces the first
+pub fn save_net_worth_snapshot(
+    conn: &Connection,
+    user_id: i32,
+    snapshot: &NetWorthSnapshot,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO net_worth_snapshots (user_id, date, assets, liabilities) VALUES (?1, ?2, ?3, ?4)",
+        params![user_id, snapshot.date, snapshot.assets, snapshot.liabilities],
+    )?;
+    Ok(())
+}
+
+pub fn get_net_worth_snapshots(conn: &Connection, user_id: i32) -> Result<Vec<NetWorthSnapshot>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT date, assets, liabilities FROM net_worth_snapshots WHERE user_id = ?1 ORDER BY date",
+    )?;
+    let snapshot_iter = stmt.query_map(params![user_id], |row| {
+        Ok(NetWorthSnapshot {
+            date: row.get(0)?,
+            assets: row.get(1)?,
+            liabilities: row.get(2)?,
+        })
+    })?;
+
+    let mut snapshots = Vec::new();
+    for snapshot in snapshot_iter {
+        snapshots.push(snapshot?);
+    }
+    Ok(snapshots)
+}
+
+pub fn add_attachment(conn: &Connection, attachment: &Attachment) -> Result<i32> {
+    conn.execute(
+        "INSERT INTO attachments (expense_id, file_name, mime_type, content_hash, thumbnail) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            attachment.expense_id,
+            attachment.file_name,
+            attachment.mime_type,
+            attachment.content_hash,
+            attachment.thumbnail
+        ],
+    )?;
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+pub fn get_attachments(conn: &Connection, expense_id: i32) -> Result<Vec<Attachment>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, expense_id, file_name, mime_type, content_hash, thumbnail FROM attachments WHERE expense_id = ?1",
+    )?;
+    let attachment_iter = stmt.query_map(params![expense_id], |row| {
+        Ok(Attachment {
+            id: row.get(0)?,
+            expense_id: row.get(1)?,
+            file_name: row.get(2)?,
+            mime_type: row.get(3)?,
+            content_hash: row.get(4)?,
+            thumbnail: row.get(5)?,
+        })
+    })?;
+
+    let mut attachments = Vec::new();
+    for attachment in attachment_iter {
+        attachments.push(attachment?);
+    }
+    Ok(attachments)
+}
+
+pub fn delete_attachment(conn: &Connection, attachment_id: i32) -> Result<()> {
+    conn.execute(
+        "DELETE FROM attachments WHERE id = ?1",
+        params![attachment_id],
+    )?;
+    Ok(())
+}
+
+pub fn delete_attachments_for_expense(conn: &Connection, expense_id: i32) -> Result<()> {
+    conn.execute(
+        "DELETE FROM attachments WHERE expense_id = ?1",
+        params![expense_id],
+    )?;
+    Ok(())
+}
+
+// Several expenses may share the same stored file, so only remove it once nothing points at it
+pub fn count_attachments_with_hash(conn: &Connection, content_hash: &str) -> Result<i64> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM attachments WHERE content_hash = ?1",
+        params![content_hash],
+        |row| row.get(0),
+    )
+}
+
+pub fn add_user(conn: &Connection, user: &User, password: &str) -> Result<(), MyError> {
+    let password_hash = bcrypt::hash(password, bcrypt::DEFAULT_COST)?;
+    conn.execute(
+        "INSERT INTO users (username, password_hash) VALUES (?1, ?2)",
+        rusqlite::params![user.username, password_hash],
+    )?;
+    Ok(())
+}
+
+pub fn get_user_id(conn: &Connection, username: &str) -> Result<Option<i32>> {
+    conn.query_row(
+        "SELECT id FROM users WHERE username = ?1",
+        params![username],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn authenticate_user(
+    conn: &Connection,
+    username: &str,
+    password: &str,
+) -> Result<Option<User>> {
+    if let Ok(mut stmt) =
+        conn.prepare_cached("SELECT id, username, password_hash FROM users WHERE username = ?1")
+    {
+        if let Some(row) = stmt
+            .query_row(params![username], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .optional()?
+        {
+            let (user_id, user_name, password_hash): (i32, String, String) = row;
+            // Correctly handle bcrypt errors
+            match verify(password, &password_hash) {
+                Ok(valid) => {
+                    if valid {
+                        return Ok(Some(User {
+                            id: user_id,
+                            username: user_name,
+                            password_hash,
+                        }));
+                    }
+                }
+                Err(_) => {
+                    // Handle bcrypt error (e.g., log it or return a specific error)
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn delete_user(conn: &Connection, username: &str) -> Result<()> {
+    conn.execute("DELETE FROM users WHERE username = ?1", params![username])?;
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use crate::models::{Debt, Expense};
+use crate::rules;
+use chrono::{Datelike, Months, NaiveDate};
+
+pub const DEBT_PAYMENTS_CATEGORY: &str = "Debt Payments";
+// Plans that still owe money after 50 years are reported as never finishing
+const MAX_MONTHS: usize = 600;
+// Balances below this are rounding left over from interest, not money owed
+const PAID_OFF: f32 = 0.005;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    Avalanche, // highest rate first, pays the least interest
+    Snowball,  // smallest balance first, clears debts soonest
+}
+
+impl Strategy {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Strategy::Avalanche => "Avalanche",
+            Strategy::Snowball => "Snowball",
+        }
+    }
+}
+
+// A debt as the simulator sees it
+#[derive(Clone, Debug, PartialEq)]
+pub struct DebtState {
+    pub name: String,
+    pub balance: f32,
+    pub apr: f32,
+    pub minimum_payment: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlanMonth {
+    pub month: String, // YYYY-MM
+    pub payment: f32,
+    pub interest: f32,
+    pub balances: Vec<f32>, // after this month's payments, in the order the debts were given
+}
+
+impl PlanMonth {
+    pub fn remaining(&self) -> f32 {
+        self.balances.iter().sum()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PayoffPlan {
+    pub strategy: Strategy,
+    pub months: Vec<PlanMonth>,
+    pub payoff_months: Vec<Option<String>>, // None for debts still owed when the plan stops
+    pub total_interest: f32,
+    pub total_paid: f32,
+    pub finished: bool,
+}
+
+impl PayoffPlan {
+    pub fn debt_free_month(&self) -> Option<&str> {
+        if !self.finished {
+            return None;
+        }
+        self.months.last().map(|month| month.month.as_str())
+    }
+}
+
+pub fn is_payment(debt: &Debt, expense: &Expense) -> bool {
+    expense.category == DEBT_PAYMENTS_CATEGORY
+        && expense.date >= debt.start_date
+        && rules::has_tag(&expense.tags, &debt.tag)
+}
+
+// The interest charged since the start date isn't known, so this is the starting balance less
+// the payments logged since; updating the debt's balance from a statement corrects it
+pub fn current_state(debt: &Debt, expenses: &[Expense]) -> DebtState {
+    let paid: f32 = expenses
+        .iter()
+        .filter(|expense| is_payment(debt, expense))
+        .map(|expense| expense.amount)
+        .sum();
+    DebtState {
+        name: debt.name.clone(),
+        balance: (debt.balance - paid).max(0.0),
+        apr: debt.apr,
+        minimum_payment: debt.minimum_payment,
+    }
+}
+
+// Pays every debt's minimum each month and puts the rest of the budget towards the debt the
+// strategy picks, moving on to the next once it is cleared. Interest is charged monthly at
+// APR / 12 before payments. The plan starts the month after `today`; a budget below the sum of
+// the minimums is raised to it.
+pub fn simulate(
+    debts: &[DebtState],
+    monthly_budget: f32,
+    strategy: Strategy,
+    today: NaiveDate,
+) -> PayoffPlan {
+    let mut balances: Vec<f32> = debts.iter().map(|debt| debt.balance.max(0.0)).collect();
+    let minimums: f32 = debts.iter().map(|debt| debt.minimum_payment).sum();
+    let budget = monthly_budget.max(minimums);
+    let mut order: Vec<usize> = (0..debts.len()).collect();
+    match strategy {
+        Strategy::Avalanche => order.sort_by(|&a, &b| {
+            debts[b]
+                .apr
+                .total_cmp(&debts[a].apr)
+                .then(balances[a].total_cmp(&balances[b]))
+        }),
+        Strategy::Snowball => order.sort_by(|&a, &b| {
+            balances[a]
+                .total_cmp(&balances[b])
+                .then(debts[b].apr.total_cmp(&debts[a].apr))
+        }),
+    }
+
+    let mut month = NaiveDate::from_ymd_opt(today.year(), today.month(), 1).unwrap();
+    let mut months = Vec::new();
+    let mut payoff_months = vec![None; debts.len()];
+    let mut total_interest = 0.0;
+    let mut total_paid = 0.0;
+    while balances.iter().any(|balance| *balance > PAID_OFF) && months.len() < MAX_MONTHS {
+        month = month.checked_add_months(Months::new(1)).unwrap();
+        let label = month.format("%Y-%m").to_string();
+
+        let mut interest = 0.0;
+        for (balance, debt) in balances.iter_mut().zip(debts) {
+            let charge = *balance * debt.apr / 1200.0;
+            *balance += charge;
+            interest += charge;
+        }
+
+        let mut available = budget;
+        let pay = |balance: &mut f32, amount: f32, available: &mut f32| {
+            let payment = amount.min(*balance).min(*available);
+            *balance -= payment;
+            *available -= payment;
+            payment
+        };
+        let mut payment = 0.0;
+        for (balance, debt) in balances.iter_mut().zip(debts) {
+            payment += pay(balance, debt.minimum_payment, &mut available);
+        }
+        for &i in &order {
+            payment += pay(&mut balances[i], available, &mut available);
+        }
+
+        for (i, balance) in balances.iter_mut().enumerate() {
+            if *balance <= PAID_OFF && payoff_months[i].is_none() && debts[i].balance > 0.0 {
+                *balance = 0.0;
+                payoff_months[i] = Some(label.clone());
+            }
+        }
+        total_interest += interest;
+        total_paid += payment;
+        months.push(PlanMonth {
+            month: label,
+            payment,
+            interest,
+            balances: balances.clone(),
+        });
+    }
+
+    PayoffPlan {
+        strategy,
+        finished: balances.iter().all(|balance| *balance <= PAID_OFF),
+        months,
+        payoff_months,
+        total_interest,
+        total_paid,
+    }
+}
@@ -0,0 +1,345 @@
+use crate::models::Expense;
+use chrono::{Datelike, NaiveDate};
+use std::collections::{HashMap, HashSet};
+
+// How many earlier months feed the per-category averages
+const HISTORY_MONTHS: i32 = 12;
+// An item counts as recurring when it shows up in at least this many of the last few months
+const RECURRING_MIN_MONTHS: usize = 2;
+const RECURRING_LOOKBACK_MONTHS: i32 = 3;
+// Keeps one unusual year from swinging the projection too far
+const SEASONAL_INDEX_RANGE: (f32, f32) = (0.5, 2.0);
+// Spread used when there is no history to measure it from
+const DEFAULT_SPREAD: f32 = 0.25;
+// Whole months after the current one that the trend chart projects
+pub const FOLLOWING_MONTHS: usize = 3;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CategoryForecast {
+    pub category: String,
+    pub spent: f32,
+    // Recurring items seen in earlier months that haven't shown up this month yet
+    pub upcoming_recurring: f32,
+    pub projected: f32,
+    // One standard deviation of the historical months, shrinking as the month goes on
+    pub low: f32,
+    pub high: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonthForecast {
+    pub month: String, // YYYY-MM
+    pub categories: Vec<CategoryForecast>,
+    pub spent: f32,
+    pub projected: f32,
+    pub low: f32,
+    pub high: f32,
+}
+
+// One point of the projected series on the monthly trend chart
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonthProjection {
+    pub month: String, // YYYY-MM
+    pub expected: f32,
+    pub low: f32,
+    pub high: f32,
+}
+
+// Projects where each category will end the month containing `today`.
+//
+// Recurring items still due are added at their last amount. Everything else is extrapolated
+// from this month's daily pace, blended with the category's average month (adjusted for how
+// this calendar month usually compares) while there are few days to go on.
+pub fn forecast_month(expenses: &[Expense], today: NaiveDate) -> MonthForecast {
+    let current = month_index(today);
+    let days_in_month = days_in_month(today);
+    let elapsed = today.day() as f32;
+    let pace_weight = elapsed / days_in_month;
+
+    let dated = dated_expenses(expenses, current);
+    let recurring = recurring_items(&dated, current);
+    let seasonal_index = seasonal_index(&dated, today);
+    let history_months = history_months(&dated, current);
+
+    // Per category: spent this month, non-recurring spent this month, non-recurring per earlier month
+    let mut spent: HashMap<&str, f32> = HashMap::new();
+    let mut variable_spent: HashMap<&str, f32> = HashMap::new();
+    let mut history: HashMap<&str, HashMap<i32, f32>> = HashMap::new();
+    for (month, expense) in &dated {
+        let category = expense.category.as_str();
+        let is_recurring = recurring.contains_key(&recurring_key(expense));
+        if *month == current {
+            *spent.entry(category).or_insert(0.0) += expense.amount;
+            if !is_recurring {
+                *variable_spent.entry(category).or_insert(0.0) += expense.amount;
+            }
+        } else if !is_recurring && history_months.contains(month) {
+            *history
+                .entry(category)
+                .or_default()
+                .entry(*month)
+                .or_insert(0.0) += expense.amount;
+        }
+    }
+
+    let seen_this_month: HashSet<(String, String)> = dated
+        .iter()
+        .filter(|(month, _)| *month == current)
+        .map(|(_, expense)| recurring_key(expense))
+        .collect();
+    let mut upcoming: HashMap<&str, f32> = HashMap::new();
+    for (key, amount) in &recurring {
+        if !seen_this_month.contains(key) {
+            *upcoming.entry(key.0.as_str()).or_insert(0.0) += amount;
+        }
+    }
+
+    let categories: HashSet<&str> = spent
+        .keys()
+        .chain(upcoming.keys())
+        .chain(history.keys())
+        .copied()
+        .collect();
+    let mut forecasts: Vec<CategoryForecast> = categories
+        .into_iter()
+        .map(|category| {
+            let spent = spent.get(category).copied().unwrap_or(0.0);
+            let variable_spent = variable_spent.get(category).copied().unwrap_or(0.0);
+            let upcoming_recurring = upcoming.get(category).copied().unwrap_or(0.0);
+            let pace_total = variable_spent / elapsed * days_in_month;
+
+            let monthly: Vec<f32> = history_months
+                .iter()
+                .map(|month| {
+                    history
+                        .get(category)
+                        .and_then(|totals| totals.get(month))
+                        .copied()
+                        .unwrap_or(0.0)
+                })
+                .collect();
+            let (history_total, spread) = if monthly.is_empty() {
+                (pace_total, pace_total * DEFAULT_SPREAD)
+            } else {
+                let (mean, deviation) = mean_and_deviation(&monthly);
+                (mean * seasonal_index, deviation)
+            };
+
+            let variable_total = pace_weight * pace_total + (1.0 - pace_weight) * history_total;
+            let projected = spent + upcoming_recurring + (variable_total - variable_spent).max(0.0);
+            let floor = spent + upcoming_recurring;
+            let margin = spread * (1.0 - pace_weight);
+            CategoryForecast {
+                category: category.to_string(),
+                spent,
+                upcoming_recurring,
+                projected,
+                low: (projected - margin).max(floor),
+                high: projected + margin,
+            }
+        })
+        .collect();
+    forecasts.sort_by(|a, b| {
+        b.projected
+            .total_cmp(&a.projected)
+            .then(a.category.cmp(&b.category))
+    });
+
+    // Categories vary independently, so their spreads add in quadrature
+    let projected: f32 = forecasts.iter().map(|f| f.projected).sum();
+    let spread_below: f32 = forecasts
+        .iter()
+        .map(|f| (f.projected - f.low).powi(2))
+        .sum::<f32>()
+        .sqrt();
+    let spread_above: f32 = forecasts
+        .iter()
+        .map(|f| (f.high - f.projected).powi(2))
+        .sum::<f32>()
+        .sqrt();
+    MonthForecast {
+        month: today.format("%Y-%m").to_string(),
+        spent: forecasts.iter().map(|f| f.spent).sum(),
+        projected,
+        low: projected - spread_below,
+        high: projected + spread_above,
+        categories: forecasts,
+    }
+}
+
+// The month containing `today` followed by `following` whole months. Later months have no pace
+// to go on, so each category is its average month over the past year, scaled for the season,
+// plus every recurring item at its last amount. Without earlier months to average, the current
+// month's projection carries forward.
+pub fn project_months(
+    expenses: &[Expense],
+    today: NaiveDate,
+    following: usize,
+) -> Vec<MonthProjection> {
+    let forecast = forecast_month(expenses, today);
+    let mut months = vec![MonthProjection {
+        month: forecast.month,
+        expected: forecast.projected,
+        low: forecast.low,
+        high: forecast.high,
+    }];
+
+    let current = month_index(today);
+    let dated = dated_expenses(expenses, current);
+    let recurring = recurring_items(&dated, current);
+    let recurring_total: f32 = recurring.values().sum();
+    let history_months = history_months(&dated, current);
+    let mut history: HashMap<&str, HashMap<i32, f32>> = HashMap::new();
+    for (month, expense) in &dated {
+        if history_months.contains(month) && !recurring.contains_key(&recurring_key(expense)) {
+            *history
+                .entry(expense.category.as_str())
+                .or_default()
+                .entry(*month)
+                .or_insert(0.0) += expense.amount;
+        }
+    }
+
+    for offset in 1..=following as i32 {
+        let month = current + offset;
+        let first_day = NaiveDate::from_ymd_opt(month / 12, (month % 12) as u32 + 1, 1).unwrap();
+        let label = first_day.format("%Y-%m").to_string();
+        if history_months.is_empty() {
+            let previous = months[0].clone();
+            months.push(MonthProjection {
+                month: label,
+                ..previous
+            });
+            continue;
+        }
+        let seasonal_index = seasonal_index(&dated, first_day);
+        let mut expected = recurring_total;
+        let mut variance = 0.0;
+        for totals in history.values() {
+            let monthly: Vec<f32> = history_months
+                .iter()
+                .map(|month| totals.get(month).copied().unwrap_or(0.0))
+                .collect();
+            let (mean, deviation) = mean_and_deviation(&monthly);
+            expected += mean * seasonal_index;
+            variance += deviation.powi(2);
+        }
+        // Categories vary independently, as in forecast_month
+        let spread = variance.sqrt();
+        months.push(MonthProjection {
+            month: label,
+            expected,
+            low: (expected - spread).max(recurring_total),
+            high: expected + spread,
+        });
+    }
+    months
+}
+
+// Expenses up to the end of the `current` month, with the month each falls in
+fn dated_expenses(expenses: &[Expense], current: i32) -> Vec<(i32, &Expense)> {
+    expenses
+        .iter()
+        .filter_map(|expense| {
+            let date = NaiveDate::parse_from_str(&expense.date, "%Y-%m-%d").ok()?;
+            Some((month_index(date), expense))
+        })
+        .filter(|(month, _)| *month <= current)
+        .collect()
+}
+
+// The earlier months averaged over, leaving out those before the first expense
+fn history_months(dated: &[(i32, &Expense)], current: i32) -> Vec<i32> {
+    let first_month = dated
+        .iter()
+        .map(|(month, _)| *month)
+        .min()
+        .unwrap_or(current);
+    (current - HISTORY_MONTHS..current)
+        .filter(|month| *month >= first_month)
+        .collect()
+}
+
+fn month_index(date: NaiveDate) -> i32 {
+    date.year() * 12 + date.month0() as i32
+}
+
+fn days_in_month(date: NaiveDate) -> f32 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    let first_of_next = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+    first_of_next.pred_opt().unwrap().day() as f32
+}
+
+fn recurring_key(expense: &Expense) -> (String, String) {
+    (
+        expense.category.clone(),
+        expense.description.trim().to_lowercase(),
+    )
+}
+
+// Items seen in enough of the last few full months, with the amount they last had
+fn recurring_items(dated: &[(i32, &Expense)], current: i32) -> HashMap<(String, String), f32> {
+    let lookback = current - RECURRING_LOOKBACK_MONTHS..current;
+    let mut months_seen: HashMap<(String, String), HashSet<i32>> = HashMap::new();
+    let mut last_seen: HashMap<(String, String), (&str, f32)> = HashMap::new();
+    for (month, expense) in dated {
+        if !lookback.contains(month) {
+            continue;
+        }
+        let key = recurring_key(expense);
+        months_seen.entry(key.clone()).or_default().insert(*month);
+        let latest = last_seen
+            .entry(key)
+            .or_insert((&expense.date, expense.amount));
+        if expense.date.as_str() >= latest.0 {
+            *latest = (&expense.date, expense.amount);
+        }
+    }
+    months_seen
+        .into_iter()
+        .filter(|(_, months)| months.len() >= RECURRING_MIN_MONTHS)
+        .map(|(key, _)| {
+            let amount = last_seen[&key].1;
+            (key, amount)
+        })
+        .collect()
+}
+
+// How this calendar month compared with an average month of the same year, averaged over the
+// earlier years that have data for it
+fn seasonal_index(dated: &[(i32, &Expense)], today: NaiveDate) -> f32 {
+    let mut monthly_totals: HashMap<i32, f32> = HashMap::new();
+    for (month, expense) in dated {
+        if month / 12 < today.year() {
+            *monthly_totals.entry(*month).or_insert(0.0) += expense.amount;
+        }
+    }
+    let ratios: Vec<f32> = monthly_totals
+        .iter()
+        .filter(|(month, _)| *month % 12 == today.month0() as i32)
+        .filter_map(|(month, total)| {
+            let year: Vec<f32> = monthly_totals
+                .iter()
+                .filter(|(other, _)| *other / 12 == month / 12)
+                .map(|(_, total)| *total)
+                .collect();
+            let average = year.iter().sum::<f32>() / year.len() as f32;
+            (average > 0.0).then(|| total / average)
+        })
+        .collect();
+    if ratios.is_empty() {
+        return 1.0;
+    }
+    let index = ratios.iter().sum::<f32>() / ratios.len() as f32;
+    index.clamp(SEASONAL_INDEX_RANGE.0, SEASONAL_INDEX_RANGE.1)
+}
+
+fn mean_and_deviation(values: &[f32]) -> (f32, f32) {
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32;
+    (mean, variance.sqrt())
+}
@@ -0,0 +1,74 @@
+use crate::models::{Expense, Goal};
+use crate::rules;
+use chrono::{Duration, NaiveDate};
+
+const AVERAGE_MONTH_DAYS: f32 = 30.44;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GoalProgress {
+    pub saved: f32,
+    pub remaining: f32,
+    pub fraction: f32, // 0.0 to 1.0, for the progress bar
+    // Monthly amount that still reaches the target by its date; all of the remainder once the
+    // date has passed
+    pub required_monthly: f32,
+    // Average saved per month since the goal was set up
+    pub monthly_pace: f32,
+    // When the goal is reached at that pace; None when reached already or nothing is saved yet
+    pub projected_date: Option<NaiveDate>,
+    pub on_track: bool,
+}
+
+impl GoalProgress {
+    pub fn is_reached(&self) -> bool {
+        self.remaining <= 0.0
+    }
+}
+
+pub fn is_contribution(goal: &Goal, expense: &Expense) -> bool {
+    expense.category == goal.category
+        && expense.date >= goal.start_date
+        && (goal.tag.trim().is_empty() || rules::has_tag(&expense.tags, &goal.tag))
+}
+
+pub fn goal_progress(goal: &Goal, expenses: &[Expense], today: NaiveDate) -> GoalProgress {
+    let saved: f32 = expenses
+        .iter()
+        .filter(|expense| is_contribution(goal, expense))
+        .map(|expense| expense.amount)
+        .sum();
+    let remaining = (goal.target_amount - saved).max(0.0);
+    let fraction = if goal.target_amount > 0.0 {
+        (saved / goal.target_amount).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+
+    let months_between =
+        |from: NaiveDate, to: NaiveDate| (to - from).num_days().max(0) as f32 / AVERAGE_MONTH_DAYS;
+    let months_left = NaiveDate::parse_from_str(&goal.target_date, "%Y-%m-%d")
+        .map(|target| months_between(today, target))
+        .unwrap_or(0.0);
+    let required_monthly = remaining / months_left.max(1.0);
+
+    // A goal set up this month has had a month's worth of time at most
+    let months_saving = NaiveDate::parse_from_str(&goal.start_date, "%Y-%m-%d")
+        .map(|start| months_between(start, today))
+        .unwrap_or(0.0)
+        .max(1.0);
+    let monthly_pace = saved / months_saving;
+    let projected_date = (remaining > 0.0 && monthly_pace > 0.0).then(|| {
+        let days = (remaining / monthly_pace * AVERAGE_MONTH_DAYS).ceil();
+        today + Duration::days(days as i64)
+    });
+
+    GoalProgress {
+        saved,
+        remaining,
+        fraction,
+        required_monthly,
+        monthly_pace,
+        projected_date,
+        on_track: remaining <= 0.0 || (months_left > 0.0 && monthly_pace >= required_monthly),
+    }
+}
@@ -0,0 +1,136 @@
+use crate::classifier::{self, CategoryClassifier};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::models;
+use crate::models::{CategoryRule, Expense, MyError, Payee};
+use crate::payees;
+use crate::rules;
+use chrono::{NaiveDate, NaiveTime};
+#[cfg(not(target_arch = "wasm32"))]
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+// Imported rows nobody could categorize end up here rather than being rejected
+pub const FALLBACK_CATEGORY: &str = "Miscellaneous";
+
+// One CSV row; only date, amount and description are required when importing
+#[derive(Serialize, Deserialize)]
+struct ExpenseRecord {
+    date: String,
+    amount: f32,
+    #[serde(default)]
+    category: String,
+    description: String,
+    #[serde(default)]
+    payment_method: String,
+    #[serde(default)]
+    tags: String,
+    #[serde(default)]
+    time: Option<String>,
+}
+
+pub fn read_expenses_csv<R: Read>(reader: R) -> Result<Vec<Expense>, MyError> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let mut expenses = Vec::new();
+    for (index, record) in csv_reader.deserialize::<ExpenseRecord>().enumerate() {
+        let record = record?;
+        if NaiveDate::parse_from_str(&record.date, "%Y-%m-%d").is_err() {
+            // Row numbers are 1-based and the header is row 1
+            return Err(MyError::InvalidInput(format!(
+                "row {}: date \"{}\" is not in YYYY-MM-DD format",
+                index + 2,
+                record.date
+            )));
+        }
+        let time = record.time.filter(|time| !time.is_empty());
+        if let Some(time) = &time {
+            if NaiveTime::parse_from_str(time, "%H:%M").is_err() {
+                return Err(MyError::InvalidInput(format!(
+                    "row {}: time \"{}\" is not in HH:MM format",
+                    index + 2,
+                    time
+                )));
+            }
+        }
+        expenses.push(Expense {
+            id: 0,
+            date: record.date,
+            amount: record.amount,
+            category: record.category,
+            description: record.description,
+            payment_method: record.payment_method,
+            tags: record.tags,
+            payee_id: None,
+            time,
+        });
+    }
+    Ok(expenses)
+}
+
+pub fn write_expenses_csv<W: Write>(writer: W, expenses: &[Expense]) -> Result<(), MyError> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    for expense in expenses {
+        csv_writer.serialize(ExpenseRecord {
+            date: expense.date.clone(),
+            amount: expense.amount,
+            category: expense.category.clone(),
+            description: expense.description.clone(),
+            payment_method: expense.payment_method.clone(),
+            tags: expense.tags.clone(),
+            time: expense.time.clone(),
+        })?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+// Applies the same categorization as the add form: rules first, then the learned classifier
+pub fn categorize_imported(
+    expenses: &mut [Expense],
+    category_rules: &[CategoryRule],
+    payee_list: &[Payee],
+    category_classifier: &CategoryClassifier,
+) {
+    for expense in expenses.iter_mut() {
+        let original_description = expense.description.clone();
+        let given_category = expense.category.clone();
+        if let Some(rule) = rules::find_matching_rule(category_rules, expense) {
+            rules::apply_rule(rule, expense);
+            if !given_category.is_empty() {
+                expense.category = given_category;
+            }
+        }
+        if expense.category.is_empty() {
+            expense.category = category_classifier
+                .predict(&expense.description, &expense.payment_method)
+                .filter(|(_, confidence)| *confidence >= classifier::MIN_SUGGESTION_CONFIDENCE)
+                .map(|(category, _)| category)
+                .unwrap_or_else(|| FALLBACK_CATEGORY.to_string());
+        }
+        expense.payee_id = payees::match_payee(payee_list, &original_description)
+            .or_else(|| payees::match_payee(payee_list, &expense.description));
+    }
+}
+
+// Rules need a user; the classifier and payees work from the whole database
+#[cfg(not(target_arch = "wasm32"))]
+pub fn categorize_from_database(
+    conn: &Connection,
+    expenses: &mut [Expense],
+    user_id: Option<i32>,
+) -> Result<(), MyError> {
+    let category_rules = match user_id {
+        Some(user_id) => models::get_rules(conn, user_id)?,
+        None => Vec::new(),
+    };
+    let classifier = CategoryClassifier::train(&models::get_expenses(conn)?);
+    categorize_imported(
+        expenses,
+        &category_rules,
+        &models::get_payees(conn)?,
+        &classifier,
+    );
+    Ok(())
+}
@@ -1,3 +1,31 @@
+pub mod anomalies;
 pub mod app;
+pub mod attachments;
+pub mod calendar;
+pub mod charts;
+pub mod classifier;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cli;
+pub mod comparison;
+#[cfg(not(target_arch = "wasm32"))]
+mod database;
+pub mod debts;
+pub mod forecast;
+pub mod goals;
+pub mod import_export;
 pub mod models;
+pub mod networth;
+pub mod patterns;
+pub mod payees;
+pub mod periods;
+pub mod receipts;
+pub mod rules;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+pub mod statement;
+pub mod storage;
+pub mod subscriptions;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tui;
 pub mod ui;
+pub mod worker;
@@ -1,16 +1,81 @@
-mod app;
-mod models;
-mod ui;
+#[cfg(not(target_arch = "wasm32"))]
+use clap::Parser;
+#[cfg(not(target_arch = "wasm32"))]
+use eframe::egui;
+use expense_tracker::app;
+use expense_tracker::storage::HttpStore;
+#[cfg(not(target_arch = "wasm32"))]
+use expense_tracker::storage::{ExpenseStore, SqliteStore};
+#[cfg(not(target_arch = "wasm32"))]
+use expense_tracker::{cli, tui};
 
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
-    let options = eframe::NativeOptions::default();
-    match models::create_expense_table() {
-        Ok(_) => println!("Table created successfully"),
-        Err(e) => println!("Failed to create table: {}", e),
+    let cli = cli::Cli::parse();
+    if let Err(e) = cli.validate() {
+        e.exit();
+    }
+
+    // Any subcommand runs headless; without one the GUI starts as before
+    if let Some(command) = cli.command {
+        if let Err(e) = cli::run(command, &cli.db, cli.json, &mut std::io::stdout()) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // The GUI and terminal UI can keep expenses on a server instead of the local file. A
+    // server keeps its own database, so nothing is created locally in that case
+    let store: Box<dyn ExpenseStore> = match &cli.server {
+        Some(url) => Box::new(HttpStore::new(url)),
+        None => match SqliteStore::open(&cli.db) {
+            Ok(store) => Box::new(store),
+            Err(e) => {
+                eprintln!("Failed to open database: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    if cli.tui {
+        // No window to repaint, so the worker gets a headless context
+        let ctx = egui::Context::default();
+        let app = app::MyApp::with_store(&ctx, store);
+        if let Err(e) = tui::run(app) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
     }
+
+    let options = eframe::NativeOptions::default();
     let _ = eframe::run_native(
         "Expense Tracker",
         options,
-        Box::new(|cc| Box::new(app::MyApp::new(&cc.egui_ctx))),
+        Box::new(move |cc| Box::new(app::MyApp::with_store(&cc.egui_ctx, store))),
     );
 }
+
+// The browser build, served by trunk from index.html. Expenses live on the server named by the
+// page's ?server= parameter, or on the one the page came from
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    wasm_bindgen_futures::spawn_local(async {
+        let result = eframe::WebRunner::new()
+            .start(
+                "expense_tracker_canvas",
+                eframe::WebOptions::default(),
+                Box::new(|cc| {
+                    let location = &cc.integration_info.web_info.location;
+                    let server = location.query_map.get("server").unwrap_or(&location.origin);
+                    let store = HttpStore::new(server);
+                    Box::new(app::MyApp::with_server(&cc.egui_ctx, store))
+                }),
+            )
+            .await;
+        if let Err(e) = result {
+            eframe::web_sys::console::error_1(&e);
+        }
+    });
+}
@@ -1,6 +1,13 @@
-use bcrypt::verify;
-use rusqlite::{params, Connection, OptionalExtension, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 
+// The SQLite functions only exist where there is a database; the browser build talks to a server
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::database::*;
+
+pub const DEFAULT_DATABASE_PATH: &str = "expenses.db";
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Expense {
     pub id: i32,
     pub date: String,
@@ -8,91 +15,240 @@ pub struct Expense {
     pub category: String,
     pub description: String,
     pub payment_method: String,
+    #[serde(default)]
+    pub tags: String, //comma separated, usually assigned by categorization rules
+    #[serde(default)]
+    pub payee_id: Option<i32>,
+    #[serde(default)]
+    pub time: Option<String>, //HH:MM, when the time of day is known
 }
 
-pub struct User {
+#[derive(Clone)]
+pub struct Attachment {
     pub id: i32,
-    pub username: String,
-    pub password_hash: String, //used password_hash instead of password for security reasons
+    pub expense_id: i32,
+    pub file_name: String,
+    pub mime_type: String,
+    pub content_hash: String,
+    pub thumbnail: Option<Vec<u8>>, //PNG bytes, only present for image receipts
 }
 
-pub fn create_expense_table() -> Result<()> {
-    let conn = Connection::open("expenses.db")?;
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS expenses (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            date TEXT NOT NULL,
-            amount REAL NOT NULL,
-            category TEXT NOT NULL,
-            description TEXT,
-            payment_method TEXT
-            )",
-        [],
-    )?;
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS users (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            username TEXT NOT NULL UNIQUE,
-            password_hash TEXT NOT NULL
-            )",
-        [],
-    )?;
-    Ok(())
-}
-
-pub fn add_expense(expense: &Expense) -> Result<()> {
-    let conn = Connection::open("expenses.db")?;
-    conn.execute(
-        "INSERT INTO expenses (date, amount, category, description, payment_method) VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![expense.date, expense.amount, expense.category, expense.description, expense.payment_method],
-    )?;
-    Ok(())
-}
-
-pub fn get_expenses() -> Result<Vec<Expense>> {
-    let conn = Connection::open("expenses.db")?;
-    let mut stmt = conn
-        .prepare("SELECT id, date, amount, category, description, payment_method FROM expenses")?;
-    let expense_iter = stmt.query_map([], |row| {
-        Ok(Expense {
-            id: row.get(0)?,
-            date: row.get(1)?,
-            amount: row.get(2)?,
-            category: row.get(3)?,
-            description: row.get(4)?,
-            payment_method: row.get(5)?,
-        })
-    })?;
-
-    let mut expenses = Vec::new();
-    for expense in expense_iter {
-        expenses.push(expense?);
+#[derive(Clone)]
+pub struct PayeeAlias {
+    pub id: i32,
+    pub payee_id: i32,
+    pub pattern: String, //matched case-insensitively anywhere in the description
+}
+
+#[derive(Clone)]
+pub struct Payee {
+    pub id: i32,
+    pub name: String,
+    pub aliases: Vec<PayeeAlias>,
+}
+
+#[derive(Clone)]
+pub struct CategoryRule {
+    pub id: i32,
+    pub user_id: i32,
+    pub position: i32,
+    pub description_pattern: String, //empty matches any description
+    pub is_regex: bool,
+    pub min_amount: Option<f32>,
+    pub max_amount: Option<f32>,
+    pub payment_method: String, //empty matches any payment method
+    pub category: String,
+    pub tags: String,
+    pub rename_to: String, //empty keeps the original description
+    // Compiled once when the rule is loaded; see rules::compile_pattern
+    pub description_regex: Option<Regex>,
+}
+
+// What the user decided about a flagged charge
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReviewStatus {
+    Dismissed, // the charge is fine
+    Confirmed, // the charge really was unusual
+}
+
+impl ReviewStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewStatus::Dismissed => "dismissed",
+            ReviewStatus::Confirmed => "confirmed",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "dismissed" => Some(ReviewStatus::Dismissed),
+            "confirmed" => Some(ReviewStatus::Confirmed),
+            _ => None,
+        }
     }
-    Ok(expenses)
 }
 
-pub fn is_username_unique(username: &str) -> Result<bool, MyError> {
-    let conn = Connection::open("expenses.db")?;
-    let count: i64 = conn.query_row(
-        "SELECT COUNT(*) FROM users WHERE username = ?1",
-        params![username],
-        |row| row.get(0),
-    )?;
-    Ok(count == 0)
+// How often a recurring charge comes around
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cadence {
+    Weekly,
+    Monthly,
+    Quarterly,
+    Yearly,
 }
 
-pub fn delete_expense(expense_id: i32) -> Result<()> {
-    let conn = Connection::open("expenses.db")?;
-    conn.execute("DELETE FROM expenses WHERE id = ?1", params![expense_id])?;
-    Ok(())
+impl Cadence {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Cadence::Weekly => "weekly",
+            Cadence::Monthly => "monthly",
+            Cadence::Quarterly => "quarterly",
+            Cadence::Yearly => "yearly",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "weekly" => Some(Cadence::Weekly),
+            "monthly" => Some(Cadence::Monthly),
+            "quarterly" => Some(Cadence::Quarterly),
+            "yearly" => Some(Cadence::Yearly),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Cadence::Weekly => "Weekly",
+            Cadence::Monthly => "Monthly",
+            Cadence::Quarterly => "Quarterly",
+            Cadence::Yearly => "Yearly",
+        }
+    }
+
+    pub fn per_year(&self) -> f32 {
+        match self {
+            Cadence::Weekly => 52.0,
+            Cadence::Monthly => 12.0,
+            Cadence::Quarterly => 4.0,
+            Cadence::Yearly => 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecurringSchedule {
+    pub id: i32,
+    pub description: String,
+    pub amount: f32,
+    pub category: String,
+    pub payment_method: String,
+    pub payee_id: Option<i32>,
+    pub cadence: Cadence,
+    pub next_date: String, // YYYY-MM-DD of the next charge still to be recorded
+    pub anchor_date: String, // YYYY-MM-DD every charge date is counted from, see subscriptions::nth_date
+}
+
+// The charges a schedule has come due for, and where it moves on to once they are recorded
+#[derive(Clone)]
+pub struct DueCharges {
+    pub schedule_id: i32,
+    pub expenses: Vec<Expense>,
+    pub next_date: String,
+}
+
+// Money set aside for something specific; contributions are expenses in the goal's category,
+// optionally only those carrying its tag
+#[derive(Clone, Debug, PartialEq)]
+pub struct Goal {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub target_amount: f32,
+    pub target_date: String, // YYYY-MM-DD
+    pub category: String,
+    pub tag: String,        //empty counts the whole category
+    pub start_date: String, //contributions before this date belong to something else
+}
+
+// Payments are "Debt Payments" expenses carrying the debt's tag
+#[derive(Clone, Debug, PartialEq)]
+pub struct Debt {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub balance: f32, //owed on start_date
+    pub apr: f32,     //annual percentage rate, e.g. 19.9
+    pub minimum_payment: f32,
+    pub tag: String,
+    pub start_date: String,
+}
+
+// A bank account balance or anything else owned, entered by hand
+#[derive(Clone, Debug, PartialEq)]
+pub struct Asset {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub value: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetWorthSnapshot {
+    pub date: String, // YYYY-MM-DD; one snapshot per user and day
+    pub assets: f32,
+    pub liabilities: f32,
+}
+
+impl NetWorthSnapshot {
+    pub fn net_worth(&self) -> f32 {
+        self.assets - self.liabilities
+    }
+}
+
+#[derive(Clone)]
+pub struct User {
+    pub id: i32,
+    pub username: String,
+    pub password_hash: String, //used password_hash instead of password for security reasons
 }
 
 #[derive(Debug)]
 pub enum MyError {
+    #[cfg(not(target_arch = "wasm32"))]
     SqliteError(rusqlite::Error),
     BcryptError(bcrypt::BcryptError),
+    IoError(std::io::Error),
+    ImageError(image::ImageError),
+    UnsupportedFileType(String),
+    ReceiptParseError(String),
+    CsvError(csv::Error),
+    InvalidInput(String),
+    HttpError(String),
+    PdfError(lopdf::Error),
+}
+
+impl std::fmt::Display for MyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            MyError::SqliteError(e) => write!(f, "database error: {}", e),
+            MyError::BcryptError(e) => write!(f, "password hashing error: {}", e),
+            MyError::IoError(e) => write!(f, "file error: {}", e),
+            MyError::ImageError(e) => write!(f, "image error: {}", e),
+            MyError::UnsupportedFileType(path) => write!(f, "unsupported file type: {}", path),
+            MyError::ReceiptParseError(message) => write!(f, "could not read receipt: {}", message),
+            MyError::CsvError(e) => write!(f, "CSV error: {}", e),
+            MyError::InvalidInput(message) => write!(f, "{}", message),
+            MyError::HttpError(message) => write!(f, "server error: {}", message),
+            MyError::PdfError(e) => write!(f, "PDF error: {}", e),
+        }
+    }
 }
 
+impl std::error::Error for MyError {}
+
+#[cfg(not(target_arch = "wasm32"))]
 impl From<rusqlite::Error> for MyError {
     fn from(error: rusqlite::Error) -> Self {
         MyError::SqliteError(error)
@@ -105,51 +261,26 @@ impl From<bcrypt::BcryptError> for MyError {
     }
 }
 
-pub fn add_user(user: &User, password: &str) -> Result<(), MyError> {
-    let password_hash = bcrypt::hash(password, bcrypt::DEFAULT_COST)?;
-    let conn = rusqlite::Connection::open("expenses.db")?;
-    conn.execute(
-        "INSERT INTO users (username, password_hash) VALUES (?1, ?2)",
-        rusqlite::params![user.username, password_hash],
-    )?;
-    Ok(())
-}
-
-pub fn authenticate_user(username: &str, password: &str) -> Result<Option<User>> {
-    let conn = Connection::open("expenses.db")?;
-    if let Ok(mut stmt) =
-        conn.prepare("SELECT id, username, password_hash FROM users WHERE username = ?1")
-    {
-        if let Some(row) = stmt
-            .query_row(params![username], |row| {
-                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
-            })
-            .optional()?
-        {
-            let (user_id, user_name, password_hash): (i32, String, String) = row;
-            // Correctly handle bcrypt errors
-            match verify(password, &password_hash) {
-                Ok(valid) => {
-                    if valid {
-                        return Ok(Some(User {
-                            id: user_id,
-                            username: user_name,
-                            password_hash,
-                        }));
-                    }
-                }
-                Err(_) => {
-                    // Handle bcrypt error (e.g., log it or return a specific error)
-                }
-            }
-        }
+impl From<std::io::Error> for MyError {
+    fn from(error: std::io::Error) -> Self {
+        MyError::IoError(error)
+    }
+}
+
+impl From<csv::Error> for MyError {
+    fn from(error: csv::Error) -> Self {
+        MyError::CsvError(error)
     }
-    Ok(None)
 }
 
-#[cfg_attr(not(test), allow(dead_code))]
-pub fn delete_user(username: &str) -> Result<()> {
-    let conn = Connection::open("expenses.db")?;
-    conn.execute("DELETE FROM users WHERE username = ?1", params![username])?;
-    Ok(())
+impl From<image::ImageError> for MyError {
+    fn from(error: image::ImageError) -> Self {
+        MyError::ImageError(error)
+    }
+}
+
+impl From<lopdf::Error> for MyError {
+    fn from(error: lopdf::Error) -> Self {
+        MyError::PdfError(error)
+    }
 }
@@ -0,0 +1,43 @@
+use crate::debts::DebtState;
+use crate::models::{Asset, NetWorthSnapshot};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+// Liabilities are what is still owed on the tracked debts
+pub fn current_snapshot(
+    assets: &[Asset],
+    debts: &[DebtState],
+    today: NaiveDate,
+) -> NetWorthSnapshot {
+    NetWorthSnapshot {
+        date: today.format("%Y-%m-%d").to_string(),
+        assets: assets.iter().map(|asset| asset.value).sum(),
+        liabilities: debts.iter().map(|debt| debt.balance).sum(),
+    }
+}
+
+// Snapshots are taken at least once a month
+pub fn is_snapshot_due(snapshots: &[NetWorthSnapshot], today: NaiveDate) -> bool {
+    let month = today.format("%Y-%m").to_string();
+    !snapshots
+        .iter()
+        .any(|snapshot| snapshot.date.starts_with(&month))
+}
+
+// Net worth per YYYY-MM from the last snapshot of each month, for the line chart
+pub fn monthly_history(snapshots: &[NetWorthSnapshot]) -> HashMap<String, f32> {
+    let mut latest: HashMap<String, &NetWorthSnapshot> = HashMap::new();
+    for snapshot in snapshots {
+        let Some(month) = snapshot.date.get(..7) else {
+            continue;
+        };
+        let entry = latest.entry(month.to_string()).or_insert(snapshot);
+        if snapshot.date > entry.date {
+            *entry = snapshot;
+        }
+    }
+    latest
+        .into_iter()
+        .map(|(month, snapshot)| (month, snapshot.net_worth()))
+        .collect()
+}
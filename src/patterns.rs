@@ -0,0 +1,176 @@
+use crate::models::Expense;
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Timelike, Weekday};
+
+// Heatmap rows, in a week starting on Monday
+pub const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+// Spending from 22:00 until 05:00 counts as late at night
+pub const LATE_NIGHT_START: u32 = 22;
+pub const LATE_NIGHT_END: u32 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PatternView {
+    HourOfDay,
+    WeekOfYear,
+}
+
+impl PatternView {
+    pub fn label(self) -> &'static str {
+        match self {
+            PatternView::HourOfDay => "Weekday x Hour",
+            PatternView::WeekOfYear => "Weekday x Week",
+        }
+    }
+}
+
+// Spending per weekday (rows) and hour or week (columns)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heatmap {
+    pub columns: Vec<String>,
+    pub cells: Vec<Vec<f32>>, // cells[weekday][column]
+}
+
+impl Heatmap {
+    fn new(columns: Vec<String>) -> Self {
+        let cells = vec![vec![0.0; columns.len()]; WEEKDAYS.len()];
+        Heatmap { columns, cells }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeekdayAverage {
+    pub weekday: Weekday,
+    pub total: f32,
+    pub days: u32, // how many of this weekday fall between the first and last expense
+    pub average: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternSummary {
+    pub weekday_averages: Vec<WeekdayAverage>,
+    pub weekend_share: f32,
+    pub late_night_share: Option<f32>, // None when no expense has a time
+    pub timed_expenses: usize,
+    pub expenses: usize,
+}
+
+pub fn expense_time(expense: &Expense) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(expense.time.as_deref()?, "%H:%M").ok()
+}
+
+pub fn is_late_night(time: NaiveTime) -> bool {
+    time.hour() >= LATE_NIGHT_START || time.hour() < LATE_NIGHT_END
+}
+
+// Only money going out is a spending pattern, so refunds and income are left out
+fn spending(expenses: &[Expense]) -> impl Iterator<Item = (NaiveDate, &Expense)> {
+    expenses
+        .iter()
+        .filter(|expense| expense.amount > 0.0)
+        .filter_map(|expense| {
+            NaiveDate::parse_from_str(&expense.date, "%Y-%m-%d")
+                .ok()
+                .map(|date| (date, expense))
+        })
+}
+
+fn monday_of(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+// Expenses without a time can't be placed in an hour and are skipped
+pub fn hour_heatmap(expenses: &[Expense]) -> Heatmap {
+    let mut heatmap = Heatmap::new((0..24).map(|hour| format!("{:02}", hour)).collect());
+    for (date, expense) in spending(expenses) {
+        if let Some(time) = expense_time(expense) {
+            let row = date.weekday().num_days_from_monday() as usize;
+            heatmap.cells[row][time.hour() as usize] += expense.amount;
+        }
+    }
+    heatmap
+}
+
+// One column per week from the first expense to the last, including quiet weeks
+pub fn week_heatmap(expenses: &[Expense]) -> Heatmap {
+    let dates: Vec<NaiveDate> = spending(expenses).map(|(date, _)| date).collect();
+    let (Some(first), Some(last)) = (dates.iter().min(), dates.iter().max()) else {
+        return Heatmap::new(Vec::new());
+    };
+    let first_monday = monday_of(*first);
+    let weeks = (monday_of(*last) - first_monday).num_weeks() as usize + 1;
+    let columns = (0..weeks)
+        .map(|week| {
+            let week = (first_monday + Duration::weeks(week as i64)).iso_week();
+            format!("{}-W{:02}", week.year(), week.week())
+        })
+        .collect();
+
+    let mut heatmap = Heatmap::new(columns);
+    for (date, expense) in spending(expenses) {
+        let row = date.weekday().num_days_from_monday() as usize;
+        let column = (monday_of(date) - first_monday).num_weeks() as usize;
+        heatmap.cells[row][column] += expense.amount;
+    }
+    heatmap
+}
+
+pub fn summarize(expenses: &[Expense]) -> PatternSummary {
+    let spent: Vec<(NaiveDate, &Expense)> = spending(expenses).collect();
+    let mut weekday_averages: Vec<WeekdayAverage> = WEEKDAYS
+        .iter()
+        .map(|weekday| WeekdayAverage {
+            weekday: *weekday,
+            total: 0.0,
+            days: 0,
+            average: 0.0,
+        })
+        .collect();
+    let first = spent.iter().map(|(date, _)| *date).min();
+    let last = spent.iter().map(|(date, _)| *date).max();
+    if let (Some(first), Some(last)) = (first, last) {
+        for day in first.iter_days().take_while(|day| *day <= last) {
+            weekday_averages[day.weekday().num_days_from_monday() as usize].days += 1;
+        }
+    }
+    for (date, expense) in &spent {
+        weekday_averages[date.weekday().num_days_from_monday() as usize].total += expense.amount;
+    }
+    for weekday in &mut weekday_averages {
+        if weekday.days > 0 {
+            weekday.average = weekday.total / weekday.days as f32;
+        }
+    }
+
+    let total: f32 = spent.iter().map(|(_, expense)| expense.amount).sum();
+    let weekend: f32 = spent
+        .iter()
+        .filter(|(date, _)| matches!(date.weekday(), Weekday::Sat | Weekday::Sun))
+        .map(|(_, expense)| expense.amount)
+        .sum();
+    let timed: Vec<(NaiveTime, f32)> = spent
+        .iter()
+        .filter_map(|(_, expense)| expense_time(expense).map(|time| (time, expense.amount)))
+        .collect();
+    let timed_total: f32 = timed.iter().map(|(_, amount)| amount).sum();
+    let late_night: f32 = timed
+        .iter()
+        .filter(|(time, _)| is_late_night(*time))
+        .map(|(_, amount)| amount)
+        .sum();
+
+    PatternSummary {
+        weekday_averages,
+        weekend_share: if total > 0.0 { weekend / total } else { 0.0 },
+        late_night_share: (timed_total > 0.0).then(|| late_night / timed_total),
+        timed_expenses: timed.len(),
+        expenses: spent.len(),
+    }
+}
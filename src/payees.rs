@@ -0,0 +1,39 @@
+use crate::models::{Expense, Payee};
+use std::collections::HashMap;
+
+// Returns the alias length when the description matches, so longer, more specific aliases win
+fn match_length(payee: &Payee, description: &str) -> Option<usize> {
+    let description = description.to_lowercase();
+    std::iter::once(payee.name.as_str())
+        .chain(payee.aliases.iter().map(|alias| alias.pattern.as_str()))
+        .filter(|pattern| !pattern.trim().is_empty())
+        .filter(|pattern| description.contains(&pattern.trim().to_lowercase()))
+        .map(|pattern| pattern.trim().len())
+        .max()
+}
+
+pub fn match_payee(payees: &[Payee], description: &str) -> Option<i32> {
+    payees
+        .iter()
+        .filter_map(|payee| match_length(payee, description).map(|length| (payee.id, length)))
+        .max_by_key(|&(_, length)| length)
+        .map(|(payee_id, _)| payee_id)
+}
+
+pub fn payee_name(payees: &[Payee], payee_id: Option<i32>) -> Option<&str> {
+    let payee_id = payee_id?;
+    payees
+        .iter()
+        .find(|payee| payee.id == payee_id)
+        .map(|payee| payee.name.as_str())
+}
+
+// Expenses without a payee are grouped under their own description
+pub fn calculate_payee_totals(expenses: &[Expense], payees: &[Payee]) -> HashMap<String, f32> {
+    let mut payee_totals = HashMap::new();
+    for expense in expenses {
+        let name = payee_name(payees, expense.payee_id).unwrap_or(&expense.description);
+        *payee_totals.entry(name.to_string()).or_insert(0.0) += expense.amount;
+    }
+    payee_totals
+}
@@ -0,0 +1,172 @@
+use chrono::{Datelike, Duration, NaiveDate};
+
+// The date range the analytics section aggregates over. Every variant except AllTime can be
+// stepped backwards and forwards, keeping its length.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Period {
+    AllTime,
+    Month { year: i32, month: u32 },
+    Quarter { year: i32, quarter: u32 },
+    YearToDate { through: NaiveDate },
+    Custom { from: NaiveDate, to: NaiveDate },
+    Rolling { days: u32, through: NaiveDate },
+}
+
+impl Period {
+    pub fn this_month(today: NaiveDate) -> Self {
+        Period::Month {
+            year: today.year(),
+            month: today.month(),
+        }
+    }
+
+    pub fn last_month(today: NaiveDate) -> Self {
+        Period::this_month(today).previous()
+    }
+
+    pub fn this_quarter(today: NaiveDate) -> Self {
+        Period::Quarter {
+            year: today.year(),
+            quarter: (today.month() - 1) / 3 + 1,
+        }
+    }
+
+    pub fn year_to_date(today: NaiveDate) -> Self {
+        Period::YearToDate { through: today }
+    }
+
+    pub fn rolling(days: u32, today: NaiveDate) -> Self {
+        Period::Rolling {
+            days: days.max(1),
+            through: today,
+        }
+    }
+
+    // First and last day, both inclusive; None for all time
+    pub fn range(&self) -> Option<(NaiveDate, NaiveDate)> {
+        match *self {
+            Period::AllTime => None,
+            Period::Month { year, month } => {
+                Some((first_of_month(year, month), end_of_month(year, month)))
+            }
+            Period::Quarter { year, quarter } => Some((
+                first_of_month(year, quarter * 3 - 2),
+                end_of_month(year, quarter * 3),
+            )),
+            Period::YearToDate { through } => Some((first_of_month(through.year(), 1), through)),
+            Period::Custom { from, to } => Some((from, to)),
+            Period::Rolling { days, through } => {
+                Some((through - Duration::days(days as i64 - 1), through))
+            }
+        }
+    }
+
+    // Dates that don't parse only count towards all time
+    pub fn contains(&self, date: &str) -> bool {
+        let Some((from, to)) = self.range() else {
+            return true;
+        };
+        match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(date) => from <= date && date <= to,
+            Err(_) => false,
+        }
+    }
+
+    pub fn previous(&self) -> Self {
+        self.step(-1)
+    }
+
+    pub fn next(&self) -> Self {
+        self.step(1)
+    }
+
+    // The same stretch of the calendar one year before
+    pub fn year_earlier(&self) -> Self {
+        match *self {
+            Period::AllTime => Period::AllTime,
+            Period::Month { year, month } => Period::Month {
+                year: year - 1,
+                month,
+            },
+            Period::Quarter { year, quarter } => Period::Quarter {
+                year: year - 1,
+                quarter,
+            },
+            Period::YearToDate { .. } => self.previous(),
+            Period::Custom { from, to } => Period::Custom {
+                from: same_day_in_year(from, from.year() - 1),
+                to: same_day_in_year(to, to.year() - 1),
+            },
+            Period::Rolling { days, through } => Period::Rolling {
+                days,
+                through: same_day_in_year(through, through.year() - 1),
+            },
+        }
+    }
+
+    fn step(&self, direction: i32) -> Self {
+        match *self {
+            Period::AllTime => Period::AllTime,
+            Period::Month { year, month } => {
+                let (year, month) = add_months(year, month, direction);
+                Period::Month { year, month }
+            }
+            Period::Quarter { year, quarter } => {
+                let (year, month) = add_months(year, quarter * 3, direction * 3);
+                Period::Quarter {
+                    year,
+                    quarter: month / 3,
+                }
+            }
+            Period::YearToDate { through } => Period::YearToDate {
+                through: same_day_in_year(through, through.year() + direction),
+            },
+            Period::Custom { from, to } => {
+                let length = Duration::days((to - from).num_days() + 1) * direction;
+                Period::Custom {
+                    from: from + length,
+                    to: to + length,
+                }
+            }
+            Period::Rolling { days, through } => Period::Rolling {
+                days,
+                through: through + Duration::days(days as i64) * direction,
+            },
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Period::AllTime => "All time".to_string(),
+            Period::Month { year, month } => {
+                first_of_month(*year, *month).format("%B %Y").to_string()
+            }
+            Period::Quarter { year, quarter } => format!("Q{} {}", quarter, year),
+            Period::YearToDate { through } => {
+                format!("{} through {}", through.year(), through.format("%b %-d"))
+            }
+            Period::Custom { from, to } => format!("{} to {}", from, to),
+            Period::Rolling { days, through } => format!("{} days to {}", days, through),
+        }
+    }
+}
+
+fn first_of_month(year: i32, month: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, 1).unwrap()
+}
+
+fn end_of_month(year: i32, month: u32) -> NaiveDate {
+    let (next_year, next_month) = add_months(year, month, 1);
+    first_of_month(next_year, next_month) - Duration::days(1)
+}
+
+fn add_months(year: i32, month: u32, delta: i32) -> (i32, u32) {
+    let index = year * 12 + month as i32 - 1 + delta;
+    (index.div_euclid(12), index.rem_euclid(12) as u32 + 1)
+}
+
+// 29 February becomes 28 February in years without it
+fn same_day_in_year(date: NaiveDate, year: i32) -> NaiveDate {
+    date.with_year(year)
+        .unwrap_or_else(|| NaiveDate::from_ymd_opt(year, date.month(), 28).unwrap())
+}
@@ -0,0 +1,231 @@
+use crate::attachments;
+use crate::models::MyError;
+use chrono::NaiveDate;
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+pub struct ReceiptField<T> {
+    pub value: T,
+    pub confidence: f32, //0.0 to 1.0, shown to the user before the form is prefilled
+}
+
+#[derive(Default)]
+pub struct ReceiptSuggestion {
+    pub merchant: Option<ReceiptField<String>>,
+    pub date: Option<ReceiptField<String>>, //always formatted as YYYY-MM-DD
+    pub total: Option<ReceiptField<f32>>,
+}
+
+impl ReceiptSuggestion {
+    pub fn is_empty(&self) -> bool {
+        self.merchant.is_none() && self.date.is_none() && self.total.is_none()
+    }
+}
+
+// Implement this to plug in another offline engine (e.g. an OCR library for photographed receipts)
+pub trait ReceiptParser {
+    fn supports(&self, mime_type: &str) -> bool;
+    fn parse(&self, path: &Path) -> Result<ReceiptSuggestion, MyError>;
+}
+
+// Reads the embedded text layer of digital PDF receipts; scanned PDFs without text yield no fields
+pub struct PdfTextParser;
+
+impl ReceiptParser for PdfTextParser {
+    fn supports(&self, mime_type: &str) -> bool {
+        mime_type == "application/pdf"
+    }
+
+    fn parse(&self, path: &Path) -> Result<ReceiptSuggestion, MyError> {
+        let text = pdf_extract::extract_text(path)
+            .map_err(|e| MyError::ReceiptParseError(e.to_string()))?;
+        Ok(suggest_from_text(&text))
+    }
+}
+
+// Runs Tesseract over photographed receipts; only built with `--features ocr`
+#[cfg(feature = "ocr")]
+pub struct TesseractParser {
+    pub language: String, // a Tesseract language code such as "eng"
+}
+
+#[cfg(feature = "ocr")]
+impl ReceiptParser for TesseractParser {
+    fn supports(&self, mime_type: &str) -> bool {
+        mime_type.starts_with("image/")
+    }
+
+    fn parse(&self, path: &Path) -> Result<ReceiptSuggestion, MyError> {
+        let file_name = path.to_str().ok_or_else(|| {
+            MyError::ReceiptParseError(format!("{} is not a valid UTF-8 path", path.display()))
+        })?;
+        let text = tesseract::ocr(file_name, &self.language)
+            .map_err(|e| MyError::ReceiptParseError(e.to_string()))?;
+        Ok(suggest_from_text(&text))
+    }
+}
+
+pub fn default_parsers() -> Vec<Box<dyn ReceiptParser>> {
+    vec![
+        Box::new(PdfTextParser),
+        #[cfg(feature = "ocr")]
+        Box::new(TesseractParser {
+            language: "eng".to_string(),
+        }),
+    ]
+}
+
+pub fn parse_receipt(
+    parsers: &[Box<dyn ReceiptParser>],
+    path: &Path,
+) -> Result<ReceiptSuggestion, MyError> {
+    let mime_type = attachments::mime_type_for(path)
+        .ok_or_else(|| MyError::UnsupportedFileType(path.display().to_string()))?;
+    let parser = parsers
+        .iter()
+        .find(|parser| parser.supports(mime_type))
+        .ok_or_else(|| {
+            if mime_type.starts_with("image/") {
+                MyError::ReceiptParseError(
+                    "image receipts need a build with OCR support (--features ocr); \
+                     PDF receipts with a text layer can be read without it"
+                        .to_string(),
+                )
+            } else {
+                MyError::ReceiptParseError(format!("no offline reader available for {}", mime_type))
+            }
+        })?;
+    parser.parse(path)
+}
+
+pub fn suggest_from_text(text: &str) -> ReceiptSuggestion {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    ReceiptSuggestion {
+        merchant: find_merchant(&lines),
+        date: find_date(text),
+        total: find_total(&lines),
+    }
+}
+
+// Compiled on first use; find_date runs once per line while looking for the merchant
+static AMOUNT_REGEX: OnceLock<Regex> = OnceLock::new();
+static ISO_DATE_REGEX: OnceLock<Regex> = OnceLock::new();
+static MONTH_NAME_DATE_REGEX: OnceLock<Regex> = OnceLock::new();
+static SLASHED_DATE_REGEX: OnceLock<Regex> = OnceLock::new();
+
+fn amount_regex() -> &'static Regex {
+    AMOUNT_REGEX.get_or_init(|| Regex::new(r"\$?\s*(\d{1,3}(?:,\d{3})+|\d+)\.(\d{2})\b").unwrap())
+}
+
+fn parse_amount(captures: &regex::Captures) -> Option<f32> {
+    format!("{}.{}", captures[1].replace(',', ""), &captures[2])
+        .parse()
+        .ok()
+}
+
+// The store name is usually printed at the top, before any dates or prices
+fn find_merchant(lines: &[&str]) -> Option<ReceiptField<String>> {
+    let amount_regex = amount_regex();
+    lines
+        .iter()
+        .take(5)
+        .enumerate()
+        .find(|(_, line)| {
+            line.chars().filter(|c| c.is_alphabetic()).count() >= 3
+                && !amount_regex.is_match(line)
+                && find_date(line).is_none()
+        })
+        .map(|(index, line)| ReceiptField {
+            value: line.to_string(),
+            confidence: if index == 0 { 0.6 } else { 0.4 },
+        })
+}
+
+fn find_date(text: &str) -> Option<ReceiptField<String>> {
+    let iso = ISO_DATE_REGEX.get_or_init(|| Regex::new(r"\b(\d{4})-(\d{2})-(\d{2})\b").unwrap());
+    let month_name = MONTH_NAME_DATE_REGEX.get_or_init(|| {
+        Regex::new(r"(?i)\b(jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\.?\s+(\d{1,2}),?\s+(\d{4})\b")
+            .unwrap()
+    });
+    let slashed = SLASHED_DATE_REGEX
+        .get_or_init(|| Regex::new(r"\b(\d{1,2})/(\d{1,2})/(\d{2}|\d{4})\b").unwrap());
+
+    let mut candidates: Vec<(Option<NaiveDate>, f32)> = Vec::new();
+    if let Some(c) = iso.captures(text) {
+        candidates.push((
+            NaiveDate::from_ymd_opt(c[1].parse().ok()?, c[2].parse().ok()?, c[3].parse().ok()?),
+            0.9,
+        ));
+    }
+    if let Some(c) = month_name.captures(text) {
+        let date_text = format!("{} {} {}", &c[1], &c[2], &c[3]);
+        candidates.push((NaiveDate::parse_from_str(&date_text, "%b %d %Y").ok(), 0.8));
+    }
+    if let Some(c) = slashed.captures(text) {
+        // Assume the US month/day order; two digit years are taken as 20xx
+        let year: i32 = c[3].parse().ok()?;
+        let year = if year < 100 { 2000 + year } else { year };
+        candidates.push((
+            NaiveDate::from_ymd_opt(year, c[1].parse().ok()?, c[2].parse().ok()?),
+            0.6,
+        ));
+    }
+
+    candidates
+        .into_iter()
+        .find_map(|(date, confidence)| date.map(|date| (date, confidence)))
+        .map(|(date, confidence)| ReceiptField {
+            value: date.format("%Y-%m-%d").to_string(),
+            confidence,
+        })
+}
+
+fn find_total(lines: &[&str]) -> Option<ReceiptField<f32>> {
+    let amount_regex = amount_regex();
+    let last_amount = |line: &str| {
+        amount_regex
+            .captures_iter(line)
+            .last()
+            .and_then(|c| parse_amount(&c))
+    };
+
+    // Prefer a labelled grand total, scanning from the bottom where it is normally printed
+    for (keywords, confidence) in [
+        (&["grand total", "total"][..], 0.9),
+        (&["amount due", "balance due", "amount paid"][..], 0.8),
+    ] {
+        for line in lines.iter().rev() {
+            let lower = line.to_lowercase();
+            if lower.contains("subtotal") || lower.contains("sub total") {
+                continue;
+            }
+            if keywords.iter().any(|keyword| lower.contains(keyword)) {
+                if let Some(amount) = last_amount(line) {
+                    return Some(ReceiptField {
+                        value: amount,
+                        confidence,
+                    });
+                }
+            }
+        }
+    }
+
+    // Fall back to the largest amount on the receipt
+    lines
+        .iter()
+        .flat_map(|line| amount_regex.captures_iter(line))
+        .filter_map(|c| parse_amount(&c))
+        .fold(None, |largest: Option<f32>, amount| {
+            Some(largest.map_or(amount, |largest| largest.max(amount)))
+        })
+        .map(|amount| ReceiptField {
+            value: amount,
+            confidence: 0.4,
+        })
+}
@@ -0,0 +1,137 @@
+use crate::models::{CategoryRule, Expense};
+use regex::{Regex, RegexBuilder};
+
+pub struct RuleChange {
+    pub expense_id: i32,
+    pub rule_id: i32,
+    pub old_description: String,
+    pub new_description: String,
+    pub old_category: String,
+    pub new_category: String,
+    pub old_tags: String,
+    pub new_tags: String,
+}
+
+// Call again whenever description_pattern or is_regex changes.
+// An invalid pattern compiles to None, so the rule never matches rather than failing the whole run
+pub fn compile_pattern(rule: &CategoryRule) -> Option<Regex> {
+    if !rule.is_regex || rule.description_pattern.is_empty() {
+        return None;
+    }
+    RegexBuilder::new(&rule.description_pattern)
+        .case_insensitive(true)
+        .build()
+        .ok()
+}
+
+pub fn rule_matches(rule: &CategoryRule, expense: &Expense) -> bool {
+    if !rule.description_pattern.is_empty() {
+        let description_matches = if rule.is_regex {
+            rule.description_regex
+                .as_ref()
+                .is_some_and(|regex| regex.is_match(&expense.description))
+        } else {
+            expense
+                .description
+                .to_lowercase()
+                .contains(&rule.description_pattern.to_lowercase())
+        };
+        if !description_matches {
+            return false;
+        }
+    }
+    if rule.min_amount.is_some_and(|min| expense.amount < min) {
+        return false;
+    }
+    if rule.max_amount.is_some_and(|max| expense.amount > max) {
+        return false;
+    }
+    rule.payment_method.is_empty() || rule.payment_method == expense.payment_method
+}
+
+// Rules are kept ordered by position, so the first match wins
+pub fn find_matching_rule<'a>(
+    rules: &'a [CategoryRule],
+    expense: &Expense,
+) -> Option<&'a CategoryRule> {
+    rules.iter().find(|rule| rule_matches(rule, expense))
+}
+
+pub fn merge_tags(existing: &str, added: &str) -> String {
+    let mut tags: Vec<&str> = Vec::new();
+    for tag in existing.split(',').chain(added.split(',')) {
+        let tag = tag.trim();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags.join(", ")
+}
+
+// Case-insensitive check against a comma separated tag list
+pub fn has_tag(tags: &str, tag: &str) -> bool {
+    tags.split(',')
+        .any(|existing| existing.trim().eq_ignore_ascii_case(tag.trim()))
+}
+
+pub fn apply_rule(rule: &CategoryRule, expense: &mut Expense) {
+    if !rule.category.is_empty() {
+        expense.category = rule.category.clone();
+    }
+    expense.tags = merge_tags(&expense.tags, &rule.tags);
+    if !rule.rename_to.is_empty() {
+        expense.description = rule.rename_to.clone();
+    }
+}
+
+// Returns the matching rule's id, or None when the expense was left untouched
+pub fn apply_rules(rules: &[CategoryRule], expense: &mut Expense) -> Option<i32> {
+    let rule = find_matching_rule(rules, expense)?;
+    apply_rule(rule, expense);
+    Some(rule.id)
+}
+
+// Lists what running the rules over existing expenses would change, without saving anything
+pub fn dry_run(rules: &[CategoryRule], expenses: &[Expense]) -> Vec<RuleChange> {
+    let mut changes = Vec::new();
+    for expense in expenses {
+        let Some(rule) = find_matching_rule(rules, expense) else {
+            continue;
+        };
+        let mut updated = expense.clone();
+        apply_rule(rule, &mut updated);
+        if updated.category != expense.category
+            || updated.tags != expense.tags
+            || updated.description != expense.description
+        {
+            changes.push(RuleChange {
+                expense_id: expense.id,
+                rule_id: rule.id,
+                old_description: expense.description.clone(),
+                new_description: updated.description,
+                old_category: expense.category.clone(),
+                new_category: updated.category,
+                old_tags: expense.tags.clone(),
+                new_tags: updated.tags,
+            });
+        }
+    }
+    changes
+}
+
+pub fn rule_from_expense(expense: &Expense, user_id: i32) -> CategoryRule {
+    CategoryRule {
+        id: 0,
+        user_id,
+        position: 0,
+        description_pattern: expense.description.clone(),
+        is_regex: false,
+        min_amount: None,
+        max_amount: None,
+        payment_method: expense.payment_method.clone(),
+        category: expense.category.clone(),
+        tags: expense.tags.clone(),
+        rename_to: String::new(),
+        description_regex: None,
+    }
+}
@@ -0,0 +1,453 @@
+use crate::app::is_password_valid;
+use crate::cli::{filter_expenses, parse_date, parse_time};
+use crate::import_export;
+use crate::models::{self, Expense, MyError, User};
+use crate::storage::{ExpenseStore, SqliteStore};
+use crate::ui::calculate_monthly_trends;
+use rusqlite::Connection;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Request, Response};
+
+pub const DEFAULT_HOST: &str = "127.0.0.1";
+pub const DEFAULT_PORT: u16 = 8080;
+
+// The browser build is usually served from somewhere other than the API
+const CORS_HEADERS: [(&str, &str); 3] = [
+    ("Access-Control-Allow-Origin", "*"),
+    (
+        "Access-Control-Allow-Headers",
+        "Authorization, Content-Type",
+    ),
+    (
+        "Access-Control-Allow-Methods",
+        "GET, POST, PUT, DELETE, OPTIONS",
+    ),
+];
+
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: &str) -> Self {
+        ApiError {
+            status,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<MyError> for ApiError {
+    fn from(error: MyError) -> Self {
+        let status = match error {
+            MyError::InvalidInput(_) => 400,
+            _ => 500,
+        };
+        ApiError {
+            status,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for ApiError {
+    fn from(error: rusqlite::Error) -> Self {
+        MyError::SqliteError(error).into()
+    }
+}
+
+type ApiResult = Result<(u16, Value), ApiError>;
+
+#[derive(Deserialize)]
+struct Credentials {
+    username: String,
+    password: String,
+}
+
+// Body for creating expenses; omitted fields are filled in like the add form does
+#[derive(Deserialize)]
+struct ExpenseRequest {
+    date: Option<String>,
+    amount: f32,
+    description: String,
+    #[serde(default)]
+    category: String,
+    #[serde(default = "default_payment_method")]
+    payment_method: String,
+    #[serde(default)]
+    tags: String,
+    time: Option<String>,
+    payee_id: Option<i32>,
+}
+
+fn default_payment_method() -> String {
+    "Card".to_string()
+}
+
+// Body for updating expenses; only the fields present are changed
+#[derive(Deserialize)]
+struct ExpenseUpdate {
+    date: Option<String>,
+    amount: Option<f32>,
+    description: Option<String>,
+    category: Option<String>,
+    payment_method: Option<String>,
+    tags: Option<String>,
+    time: Option<String>,
+    // null clears the payee, leaving it out keeps it
+    #[serde(default, deserialize_with = "present")]
+    payee_id: Option<Option<i32>>,
+}
+
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+// Tokens stop working after this long without a request
+pub const DEFAULT_SESSION_TIMEOUT: Duration = Duration::from_secs(12 * 60 * 60);
+
+struct Session {
+    user_id: i32,
+    last_used: Instant,
+}
+
+// Serves the JSON API on a single thread; sessions expire when idle and are lost on restart
+pub struct ApiServer {
+    server: tiny_http::Server,
+    store: SqliteStore,
+    sessions: HashMap<String, Session>, // keyed by token
+    session_timeout: Duration,
+}
+
+impl ApiServer {
+    // Port 0 picks a free port, see local_addr
+    pub fn bind(address: &str, store: SqliteStore) -> Result<Self, MyError> {
+        let server = tiny_http::Server::http(address)
+            .map_err(|e| MyError::IoError(io::Error::other(e.to_string())))?;
+        Ok(ApiServer {
+            server,
+            store,
+            sessions: HashMap::new(),
+            session_timeout: DEFAULT_SESSION_TIMEOUT,
+        })
+    }
+
+    pub fn with_session_timeout(mut self, session_timeout: Duration) -> Self {
+        self.session_timeout = session_timeout;
+        self
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    pub fn run(mut self) {
+        loop {
+            let mut request = match self.server.recv() {
+                Ok(request) => request,
+                Err(e) => {
+                    eprintln!("Failed to receive request: {}", e);
+                    continue;
+                }
+            };
+            let mut response = match request.method() {
+                // Browsers ask before sending a token to another origin
+                Method::Options => Response::from_string("").with_status_code(204),
+                _ => {
+                    let (status, body) = match self.handle(&mut request) {
+                        Ok(response) => response,
+                        Err(error) => (error.status, json!({ "error": error.message })),
+                    };
+                    let content_type =
+                        Header::from_bytes("Content-Type", "application/json").unwrap();
+                    Response::from_string(body.to_string())
+                        .with_status_code(status)
+                        .with_header(content_type)
+                }
+            };
+            for (name, value) in CORS_HEADERS {
+                response.add_header(Header::from_bytes(name, value).unwrap());
+            }
+            if let Err(e) = request.respond(response) {
+                eprintln!("Failed to send response: {}", e);
+            }
+        }
+    }
+
+    fn handle(&mut self, request: &mut Request) -> ApiResult {
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        let query = parse_query(query);
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let method = request.method().clone();
+
+        match (&method, segments.as_slice()) {
+            (Method::Post, ["api", "login"]) => return self.login(request),
+            (Method::Post, ["api", "users"]) => {
+                return create_user(self.store.connection(), request)
+            }
+            _ => {}
+        }
+
+        let token = bearer_token(request).ok_or_else(|| ApiError::new(401, "missing token"))?;
+        self.remove_expired_sessions();
+        let session = self
+            .sessions
+            .get_mut(&token)
+            .ok_or_else(|| ApiError::new(401, "invalid or expired token"))?;
+        session.last_used = Instant::now();
+        let user_id = session.user_id;
+        let conn = self.store.connection();
+
+        match (&method, segments.as_slice()) {
+            (Method::Post, ["api", "logout"]) => {
+                self.sessions.remove(&token);
+                Ok((200, json!({ "logged_out": true })))
+            }
+            (Method::Get, ["api", "expenses"]) => {
+                let mut expenses = query_expenses(conn, &query)?;
+                if let Some(category) = query.get("category") {
+                    expenses.retain(|expense| expense.category.eq_ignore_ascii_case(category));
+                }
+                Ok((200, json!(expenses)))
+            }
+            (Method::Post, ["api", "expenses"]) => {
+                let body: ExpenseRequest = read_json(request)?;
+                check_amount(body.amount)?;
+                if let Some(payee_id) = body.payee_id {
+                    check_payee(conn, payee_id)?;
+                }
+                let date = match body.date {
+                    Some(date) => parse_date(&date)?,
+                    None => chrono::Local::now().format("%Y-%m-%d").to_string(),
+                };
+                let mut expenses = vec![Expense {
+                    id: 0,
+                    date,
+                    amount: body.amount,
+                    category: body.category,
+                    description: body.description,
+                    payment_method: body.payment_method,
+                    tags: body.tags,
+                    payee_id: None,
+                    time: body.time.as_deref().map(parse_time).transpose()?,
+                }];
+                import_export::categorize_from_database(conn, &mut expenses, Some(user_id))?;
+                let mut expense = expenses.remove(0);
+                // A payee chosen by the client wins over one matched from the description
+                expense.payee_id = body.payee_id.or(expense.payee_id);
+                expense.id = models::add_expense(conn, &expense)?;
+                Ok((201, json!(expense)))
+            }
+            (Method::Get, ["api", "expenses", id]) => Ok((200, json!(find_expense(conn, id)?))),
+            (Method::Put, ["api", "expenses", id]) => {
+                let mut expense = find_expense(conn, id)?;
+                let body: ExpenseUpdate = read_json(request)?;
+                if let Some(date) = body.date {
+                    expense.date = parse_date(&date)?;
+                }
+                if let Some(time) = body.time {
+                    expense.time = Some(parse_time(&time)?);
+                }
+                if let Some(amount) = body.amount {
+                    expense.amount = check_amount(amount)?;
+                }
+                if let Some(description) = body.description {
+                    expense.description = description;
+                }
+                if let Some(category) = body.category.filter(|category| !category.is_empty()) {
+                    expense.category = category;
+                }
+                if let Some(payment_method) = body.payment_method {
+                    expense.payment_method = payment_method;
+                }
+                if let Some(tags) = body.tags {
+                    expense.tags = tags;
+                }
+                if let Some(payee_id) = body.payee_id {
+                    if let Some(payee_id) = payee_id {
+                        check_payee(conn, payee_id)?;
+                    }
+                    expense.payee_id = payee_id;
+                }
+                models::update_expense(conn, &expense)?;
+                Ok((200, json!(expense)))
+            }
+            (Method::Delete, ["api", "expenses", id]) => {
+                let expense = find_expense(conn, id)?;
+                // Through the store so the receipt copies go too
+                self.store.delete_expense(expense.id)?;
+                Ok((200, json!({ "deleted": expense.id })))
+            }
+            (Method::Get, ["api", "totals", "categories"]) => {
+                let mut category_totals: BTreeMap<String, f32> = BTreeMap::new();
+                for expense in query_expenses(conn, &query)? {
+                    *category_totals.entry(expense.category).or_insert(0.0) += expense.amount;
+                }
+                Ok((200, json!(category_totals)))
+            }
+            (Method::Get, ["api", "trends", "monthly"]) => {
+                let monthly_totals: BTreeMap<String, f32> =
+                    calculate_monthly_trends(&query_expenses(conn, &query)?)
+                        .into_iter()
+                        .collect();
+                Ok((200, json!(monthly_totals)))
+            }
+            _ => Err(ApiError::new(404, "not found")),
+        }
+    }
+
+    fn login(&mut self, request: &mut Request) -> ApiResult {
+        let body: Credentials = read_json(request)?;
+        match models::authenticate_user(self.store.connection(), &body.username, &body.password)? {
+            Some(user) => {
+                let bytes: [u8; 32] = rand::random();
+                let token: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+                self.remove_expired_sessions();
+                self.sessions.insert(
+                    token.clone(),
+                    Session {
+                        user_id: user.id,
+                        last_used: Instant::now(),
+                    },
+                );
+                Ok((200, json!({ "token": token, "user_id": user.id })))
+            }
+            None => Err(ApiError::new(401, "Invalid username or password")),
+        }
+    }
+
+    fn remove_expired_sessions(&mut self) {
+        let session_timeout = self.session_timeout;
+        self.sessions
+            .retain(|_, session| session.last_used.elapsed() < session_timeout);
+    }
+}
+
+// Signing up needs no session, like the signup page
+fn create_user(conn: &Connection, request: &mut Request) -> ApiResult {
+    let body: Credentials = read_json(request)?;
+    if body.username.is_empty() || body.password.is_empty() {
+        return Err(ApiError::new(400, "Username and password cannot be empty"));
+    }
+    if !models::is_username_unique(conn, &body.username)? {
+        return Err(ApiError::new(409, "Username already exists"));
+    }
+    if !is_password_valid(&body.password) {
+        return Err(ApiError::new(
+            400,
+            "Password must be at least 5 characters long, include a number and a symbol",
+        ));
+    }
+    let user = User {
+        id: 0,
+        username: body.username.clone(),
+        password_hash: String::new(), // This will be set in add_user
+    };
+    models::add_user(conn, &user, &body.password)?;
+    Ok((201, json!({ "created": body.username })))
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(request: &mut Request) -> Result<T, ApiError> {
+    serde_json::from_reader(request.as_reader())
+        .map_err(|e| ApiError::new(400, &format!("invalid request body: {}", e)))
+}
+
+fn bearer_token(request: &Request) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+}
+
+// Negative amounts are refunds, as in the CLI; NaN, infinity and zero are rejected
+fn check_amount(amount: f32) -> Result<f32, ApiError> {
+    if amount.is_finite() && amount != 0.0 {
+        Ok(amount)
+    } else {
+        Err(ApiError::new(400, "amount must be a non-zero number"))
+    }
+}
+
+fn check_payee(conn: &Connection, payee_id: i32) -> Result<(), ApiError> {
+    if models::get_payees(conn)?
+        .iter()
+        .any(|payee| payee.id == payee_id)
+    {
+        Ok(())
+    } else {
+        Err(ApiError::new(
+            400,
+            &format!("no payee with id {}", payee_id),
+        ))
+    }
+}
+
+fn find_expense(conn: &Connection, id: &str) -> Result<Expense, ApiError> {
+    let id: i32 = id
+        .parse()
+        .map_err(|_| ApiError::new(400, "expense id must be a number"))?;
+    models::get_expenses(conn)?
+        .into_iter()
+        .find(|expense| expense.id == id)
+        .ok_or_else(|| ApiError::new(404, &format!("no expense with id {}", id)))
+}
+
+// Applies the optional from/to query parameters shared by the read endpoints
+fn query_expenses(
+    conn: &Connection,
+    query: &HashMap<String, String>,
+) -> Result<Vec<Expense>, ApiError> {
+    let mut expenses = filter_expenses(
+        models::get_expenses(conn)?,
+        query.get("from").cloned(),
+        query.get("to").cloned(),
+    )?;
+    expenses.sort_by(|a, b| a.date.cmp(&b.date).then(a.id.cmp(&b.id)));
+    Ok(expenses)
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (decode_component(key), decode_component(value)))
+        .collect()
+}
+
+// Decodes "+" and %XX escapes; malformed escapes are kept as they are
+fn decode_component(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
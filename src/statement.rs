@@ -0,0 +1,664 @@
+use crate::charts::{self, CHART_COLORS};
+use crate::comparison::{self, ComparisonRow, GroupBy};
+use crate::debts;
+use crate::goals;
+use crate::models::{Debt, Expense, Goal, MyError};
+use crate::periods::Period;
+use crate::ui::{calculate_category_totals, calculate_monthly_trends};
+use chrono::NaiveDate;
+use eframe::egui::Color32;
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream};
+use std::f32::consts::TAU;
+use std::path::Path;
+
+// A4 in points, the unit PDF coordinates are in; y counts up from the bottom of the page
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const ROW_HEIGHT: f32 = 14.0;
+const BODY_SIZE: f32 = 9.0;
+const HEADING_SIZE: f32 = 13.0;
+const PIE_RADIUS: f32 = 70.0;
+const BAR_CHART_HEIGHT: f32 = 140.0;
+const MAX_BAR_LABELS: usize = 12;
+const AVERAGE_MONTH_DAYS: f32 = 30.44;
+
+// A savings goal or debt held against what it needed over the period
+#[derive(Clone, Debug, PartialEq)]
+pub struct BudgetLine {
+    pub name: String,
+    pub actual: f32, // saved towards the goal or paid off the debt during the period
+    // The goal's required monthly amount or the debt's minimum payment, for each month of the
+    // period; None for all time, which has no length to scale by
+    pub planned: Option<f32>,
+    pub status: String,
+}
+
+// Everything printed on a statement for one period
+pub struct Statement {
+    pub period: Period,
+    pub baseline: Option<Period>, // the previous period of the same length; None for all time
+    pub generated: NaiveDate,
+    pub transactions: Vec<Expense>,     // oldest first
+    pub categories: Vec<ComparisonRow>, // largest first
+    pub spent: f32,
+    pub income: f32,             // negative amounts, such as refunds
+    pub budget: Vec<BudgetLine>, // goals first, then debts
+}
+
+impl Statement {
+    pub fn new(
+        expenses: &[Expense],
+        period: &Period,
+        goals: &[Goal],
+        debts: &[Debt],
+        generated: NaiveDate,
+    ) -> Self {
+        let mut transactions: Vec<Expense> = expenses
+            .iter()
+            .filter(|expense| period.contains(&expense.date))
+            .cloned()
+            .collect();
+        transactions.sort_by(|a, b| (&a.date, &a.time, a.id).cmp(&(&b.date, &b.time, b.id)));
+
+        let baseline = (*period != Period::AllTime).then(|| period.previous());
+        let mut categories = match &baseline {
+            Some(baseline) => {
+                comparison::compare_periods(expenses, period, baseline, GroupBy::Category)
+            }
+            None => calculate_category_totals(&transactions)
+                .into_iter()
+                .map(|(key, current)| ComparisonRow {
+                    key,
+                    current,
+                    previous: 0.0,
+                })
+                .collect(),
+        };
+        categories.sort_by(|a, b| b.current.total_cmp(&a.current).then(a.key.cmp(&b.key)));
+
+        let spent = transactions
+            .iter()
+            .map(|expense| expense.amount.max(0.0))
+            .sum();
+        let income = transactions
+            .iter()
+            .map(|expense| (-expense.amount).max(0.0))
+            .sum();
+        let budget = budget_lines(expenses, period, goals, debts, generated);
+        Statement {
+            period: period.clone(),
+            baseline,
+            generated,
+            transactions,
+            categories,
+            spent,
+            income,
+            budget,
+        }
+    }
+
+    pub fn net(&self) -> f32 {
+        self.spent - self.income
+    }
+
+    fn total(&self) -> f32 {
+        self.categories.iter().map(|row| row.current).sum()
+    }
+}
+
+// Goals and debts as they stood at the end of the period, or on the day the statement is made if
+// the period hasn't ended yet
+fn budget_lines(
+    expenses: &[Expense],
+    period: &Period,
+    goals: &[Goal],
+    debts: &[Debt],
+    generated: NaiveDate,
+) -> Vec<BudgetLine> {
+    let as_of = period
+        .range()
+        .map_or(generated, |(_, to)| to.min(generated));
+    let as_of_text = as_of.format("%Y-%m-%d").to_string();
+    let until_end: Vec<Expense> = expenses
+        .iter()
+        .filter(|expense| expense.date <= as_of_text)
+        .cloned()
+        .collect();
+    let months = period
+        .range()
+        .map(|(from, to)| ((to - from).num_days() + 1) as f32 / AVERAGE_MONTH_DAYS);
+    let in_period = |expense: &&Expense| period.contains(&expense.date);
+
+    let mut lines = Vec::new();
+    for goal in goals {
+        let progress = goals::goal_progress(goal, &until_end, as_of);
+        let status = if progress.is_reached() {
+            "Reached".to_string()
+        } else if progress.on_track {
+            format!("On track, {:.0}% saved", progress.fraction * 100.0)
+        } else {
+            format!("Behind, {:.0}% saved", progress.fraction * 100.0)
+        };
+        lines.push(BudgetLine {
+            name: format!("Goal: {}", goal.name),
+            actual: expenses
+                .iter()
+                .filter(in_period)
+                .filter(|expense| goals::is_contribution(goal, expense))
+                .map(|expense| expense.amount)
+                .sum(),
+            planned: months.map(|months| progress.required_monthly * months),
+            status,
+        });
+    }
+    for debt in debts {
+        let state = debts::current_state(debt, &until_end);
+        let actual: f32 = expenses
+            .iter()
+            .filter(in_period)
+            .filter(|expense| debts::is_payment(debt, expense))
+            .map(|expense| expense.amount)
+            .sum();
+        let planned = months.map(|months| debt.minimum_payment * months);
+        let status = match planned {
+            _ if state.balance <= 0.0 => "Paid off".to_string(),
+            Some(planned) if actual < planned => {
+                format!("Below minimum, {:.2} owed", state.balance)
+            }
+            _ => format!("{:.2} owed", state.balance),
+        };
+        lines.push(BudgetLine {
+            name: format!("Debt: {}", debt.name),
+            actual,
+            planned,
+            status,
+        });
+    }
+    lines
+}
+
+pub fn write_statement(statement: &Statement, path: &Path) -> Result<(), MyError> {
+    std::fs::write(path, statement_pdf(statement)?)?;
+    Ok(())
+}
+
+pub fn statement_pdf(statement: &Statement) -> Result<Vec<u8>, MyError> {
+    let mut pages = Pages::new();
+    pages.text(MARGIN, 18.0, true, "Expense Statement");
+    pages.advance(24.0);
+    pages.text(
+        MARGIN,
+        BODY_SIZE,
+        false,
+        &format!("Period: {}", statement.period.label()),
+    );
+    pages.advance(ROW_HEIGHT);
+    pages.text(
+        MARGIN,
+        BODY_SIZE,
+        false,
+        &format!("Generated: {}", statement.generated),
+    );
+    pages.advance(ROW_HEIGHT * 2.0);
+
+    write_summary(&mut pages, statement);
+    write_categories(&mut pages, statement);
+    write_budget(&mut pages, statement);
+    write_charts(&mut pages, statement);
+    write_transactions(&mut pages, statement);
+
+    let mut document = pages.into_document(&statement.period.label())?;
+    let mut pdf = Vec::new();
+    document.save_to(&mut pdf)?;
+    Ok(pdf)
+}
+
+fn write_summary(pages: &mut Pages, statement: &Statement) {
+    pages.heading("Summary");
+    let rows = [
+        ("Spent", format!("{:.2}", statement.spent)),
+        ("Income and refunds", format!("{:.2}", statement.income)),
+        ("Net spending", format!("{:.2}", statement.net())),
+        ("Transactions", statement.transactions.len().to_string()),
+    ];
+    for (label, value) in rows {
+        pages.text(MARGIN, BODY_SIZE, false, label);
+        pages.text_right(MARGIN + 220.0, BODY_SIZE, false, &value);
+        pages.advance(ROW_HEIGHT);
+    }
+    pages.advance(ROW_HEIGHT);
+}
+
+fn write_categories(pages: &mut Pages, statement: &Statement) {
+    pages.heading("Spending by Category");
+    if statement.categories.is_empty() {
+        pages.text(MARGIN, BODY_SIZE, false, "No expenses in this period.");
+        pages.advance(ROW_HEIGHT * 2.0);
+        return;
+    }
+    let total = statement.total();
+    let header = |pages: &mut Pages| {
+        pages.text(MARGIN, BODY_SIZE, true, "Category");
+        pages.text_right(MARGIN + 260.0, BODY_SIZE, true, "Amount");
+        pages.text_right(MARGIN + 320.0, BODY_SIZE, true, "Share");
+        if let Some(baseline) = &statement.baseline {
+            pages.text_right(MARGIN + 410.0, BODY_SIZE, true, &fit(&baseline.label(), 18));
+            pages.text_right(PAGE_WIDTH - MARGIN, BODY_SIZE, true, "Change");
+        }
+        pages.advance(ROW_HEIGHT);
+    };
+    header(pages);
+    for row in &statement.categories {
+        if pages.reserve(ROW_HEIGHT) {
+            header(pages);
+        }
+        let share = if total > 0.0 {
+            format!("{:.1}%", row.current / total * 100.0)
+        } else {
+            String::new()
+        };
+        pages.text(MARGIN, BODY_SIZE, false, &fit(&row.key, 40));
+        pages.text_right(
+            MARGIN + 260.0,
+            BODY_SIZE,
+            false,
+            &format!("{:.2}", row.current),
+        );
+        pages.text_right(MARGIN + 320.0, BODY_SIZE, false, &share);
+        if statement.baseline.is_some() {
+            pages.text_right(
+                MARGIN + 410.0,
+                BODY_SIZE,
+                false,
+                &format!("{:.2}", row.previous),
+            );
+            pages.text_right(PAGE_WIDTH - MARGIN, BODY_SIZE, false, &row.percent_label());
+        }
+        pages.advance(ROW_HEIGHT);
+    }
+    pages.line(MARGIN, PAGE_WIDTH - MARGIN);
+    pages.advance(4.0);
+    pages.text(MARGIN, BODY_SIZE, true, "Total");
+    pages.text_right(MARGIN + 260.0, BODY_SIZE, true, &format!("{:.2}", total));
+    pages.advance(ROW_HEIGHT * 2.0);
+}
+
+fn write_budget(pages: &mut Pages, statement: &Statement) {
+    pages.heading("Budget Status");
+    if statement.budget.is_empty() {
+        pages.text(
+            MARGIN,
+            BODY_SIZE,
+            false,
+            "No savings goals or debts set up.",
+        );
+        pages.advance(ROW_HEIGHT * 2.0);
+        return;
+    }
+    let header = |pages: &mut Pages| {
+        pages.text(MARGIN, BODY_SIZE, true, "Goal or debt");
+        pages.text_right(MARGIN + 260.0, BODY_SIZE, true, "This period");
+        pages.text_right(MARGIN + 330.0, BODY_SIZE, true, "Planned");
+        pages.text(MARGIN + 345.0, BODY_SIZE, true, "Status");
+        pages.advance(ROW_HEIGHT);
+    };
+    header(pages);
+    for line in &statement.budget {
+        if pages.reserve(ROW_HEIGHT) {
+            header(pages);
+        }
+        pages.text(MARGIN, BODY_SIZE, false, &fit(&line.name, 40));
+        pages.text_right(
+            MARGIN + 260.0,
+            BODY_SIZE,
+            false,
+            &format!("{:.2}", line.actual),
+        );
+        let planned = line
+            .planned
+            .map_or(String::new(), |planned| format!("{:.2}", planned));
+        pages.text_right(MARGIN + 330.0, BODY_SIZE, false, &planned);
+        pages.text(MARGIN + 345.0, BODY_SIZE, false, &fit(&line.status, 30));
+        pages.advance(ROW_HEIGHT);
+    }
+    pages.advance(ROW_HEIGHT);
+}
+
+// The same slices and columns as the pie and bar charts in the analytics section
+fn write_charts(pages: &mut Pages, statement: &Statement) {
+    let slices = charts::pie_slices(&calculate_category_totals(&statement.transactions));
+    if !slices.is_empty() {
+        let legend_height = slices.len() as f32 * ROW_HEIGHT;
+        pages.reserve(HEADING_SIZE * 2.0 + (PIE_RADIUS * 2.0).max(legend_height));
+        pages.heading("Category Breakdown");
+        let center = (MARGIN + PIE_RADIUS, pages.y - PIE_RADIUS);
+        let mut legend_y = pages.y;
+        for (i, slice) in slices.iter().enumerate() {
+            let color = CHART_COLORS[i % CHART_COLORS.len()];
+            // Radians clockwise from 12 o'clock, as in the on-screen pie
+            let steps = ((slice.end_angle - slice.start_angle) / TAU * 90.0).ceil() as usize;
+            let mut points = vec![center];
+            for step in 0..=steps.max(1) {
+                let angle = slice.start_angle
+                    + (slice.end_angle - slice.start_angle) * step as f32 / steps.max(1) as f32;
+                points.push((
+                    center.0 + PIE_RADIUS * angle.sin(),
+                    center.1 + PIE_RADIUS * angle.cos(),
+                ));
+            }
+            pages.fill_polygon(&points, color);
+
+            let legend_x = MARGIN + PIE_RADIUS * 2.0 + 30.0;
+            pages.fill_rect(legend_x, legend_y - 8.0, 8.0, 8.0, color);
+            pages.text_at(
+                legend_x + 14.0,
+                legend_y - 8.0,
+                BODY_SIZE,
+                false,
+                &format!(
+                    "{:.1}% {} ({:.2})",
+                    slice.share * 100.0,
+                    fit(&slice.label, 40),
+                    slice.amount
+                ),
+            );
+            legend_y -= ROW_HEIGHT;
+        }
+        pages.advance((PIE_RADIUS * 2.0).max(legend_height) + ROW_HEIGHT);
+    }
+
+    let months = charts::sorted_series(&calculate_monthly_trends(&statement.transactions));
+    if months.is_empty() {
+        return;
+    }
+    pages.reserve(HEADING_SIZE * 2.0 + BAR_CHART_HEIGHT + ROW_HEIGHT * 2.0);
+    pages.heading("Monthly Spending");
+    let highest = months
+        .iter()
+        .map(|(_, amount)| *amount)
+        .fold(0.0, f32::max)
+        .max(f32::EPSILON);
+    let left = MARGIN + 40.0;
+    let width = PAGE_WIDTH - MARGIN - left;
+    let bottom = pages.y - BAR_CHART_HEIGHT;
+    let column_width = width / months.len() as f32;
+    let label_every = months.len().div_ceil(MAX_BAR_LABELS);
+    pages.text_right_at(
+        left - 4.0,
+        pages.y - 4.0,
+        7.0,
+        false,
+        &format!("{:.0}", highest),
+    );
+    pages.text_right_at(left - 4.0, bottom, 7.0, false, "0");
+    for (i, (month, amount)) in months.iter().enumerate() {
+        let x = left + column_width * i as f32;
+        let height = BAR_CHART_HEIGHT * amount.max(0.0) / highest;
+        pages.fill_rect(
+            x + column_width * 0.1,
+            bottom,
+            column_width * 0.8,
+            height,
+            Color32::from_rgb(100, 100, 255),
+        );
+        if i % label_every == 0 {
+            pages.text_at(x + column_width * 0.1, bottom - 10.0, 7.0, false, month);
+        }
+    }
+    pages.stroke_line((left, bottom), (left + width, bottom));
+    pages.advance(BAR_CHART_HEIGHT + ROW_HEIGHT * 2.0);
+}
+
+fn write_transactions(pages: &mut Pages, statement: &Statement) {
+    pages.reserve(HEADING_SIZE * 2.0 + ROW_HEIGHT * 2.0);
+    pages.heading("Transactions");
+    let columns = [
+        MARGIN,
+        MARGIN + 58.0,
+        MARGIN + 90.0,
+        MARGIN + 280.0,
+        MARGIN + 380.0,
+    ];
+    let header = |pages: &mut Pages| {
+        for (x, title) in columns
+            .iter()
+            .zip(["Date", "Time", "Description", "Category", "Payment"])
+        {
+            pages.text(*x, BODY_SIZE, true, title);
+        }
+        pages.text_right(PAGE_WIDTH - MARGIN, BODY_SIZE, true, "Amount");
+        pages.advance(ROW_HEIGHT);
+    };
+    header(pages);
+    for expense in &statement.transactions {
+        if pages.reserve(ROW_HEIGHT) {
+            header(pages);
+        }
+        let cells = [
+            expense.date.clone(),
+            expense.time.clone().unwrap_or_default(),
+            fit(&expense.description, 36),
+            fit(&expense.category, 18),
+            fit(&expense.payment_method, 12),
+        ];
+        for (x, cell) in columns.iter().zip(&cells) {
+            pages.text(*x, BODY_SIZE, false, cell);
+        }
+        pages.text_right(
+            PAGE_WIDTH - MARGIN,
+            BODY_SIZE,
+            false,
+            &format!("{:.2}", expense.amount),
+        );
+        pages.advance(ROW_HEIGHT);
+    }
+}
+
+// Cuts long text so it stays inside its table column
+fn fit(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max_chars.saturating_sub(3)).collect();
+    format!("{}...", cut)
+}
+
+// The standard fonts use WinAnsi encoding, which matches Latin-1 for the printable range
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x20..=0x7e | 0xa0..=0xff => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+// Approximate Helvetica advance widths in ems, close enough to right-align numbers
+fn text_width(text: &str, size: f32) -> f32 {
+    let ems: f32 = text
+        .chars()
+        .map(|c| match c {
+            '0'..='9' | '+' => 0.556,
+            '.' | ',' | ' ' => 0.278,
+            '-' => 0.333,
+            '%' => 0.889,
+            'A'..='Z' => 0.667,
+            _ => 0.5,
+        })
+        .sum();
+    ems * size
+}
+
+fn color_operands(color: Color32) -> Vec<Object> {
+    [color.r(), color.g(), color.b()]
+        .iter()
+        .map(|channel| Object::Real(*channel as f32 / 255.0))
+        .collect()
+}
+
+// Content streams for each page, written top to bottom with a cursor
+struct Pages {
+    pages: Vec<Vec<Operation>>,
+    y: f32, // baseline of the next line on the current page
+}
+
+impl Pages {
+    fn new() -> Self {
+        Pages {
+            pages: vec![Vec::new()],
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    fn operations(&mut self) -> &mut Vec<Operation> {
+        self.pages.last_mut().unwrap()
+    }
+
+    fn advance(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    // Starts a new page when `height` doesn't fit above the bottom margin; true if it did
+    fn reserve(&mut self, height: f32) -> bool {
+        if self.y - height >= MARGIN {
+            return false;
+        }
+        self.pages.push(Vec::new());
+        self.y = PAGE_HEIGHT - MARGIN;
+        true
+    }
+
+    fn heading(&mut self, title: &str) {
+        self.reserve(HEADING_SIZE + ROW_HEIGHT * 2.0);
+        self.text(MARGIN, HEADING_SIZE, true, title);
+        self.advance(HEADING_SIZE + 6.0);
+    }
+
+    fn text(&mut self, x: f32, size: f32, bold: bool, text: &str) {
+        let y = self.y - size;
+        self.text_at(x, y, size, bold, text);
+    }
+
+    fn text_right(&mut self, right: f32, size: f32, bold: bool, text: &str) {
+        let y = self.y - size;
+        self.text_right_at(right, y, size, bold, text);
+    }
+
+    fn text_right_at(&mut self, right: f32, y: f32, size: f32, bold: bool, text: &str) {
+        self.text_at(right - text_width(text, size), y, size, bold, text);
+    }
+
+    fn text_at(&mut self, x: f32, y: f32, size: f32, bold: bool, text: &str) {
+        let font = if bold { "F2" } else { "F1" };
+        self.operations().extend([
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec![font.into(), size.into()]),
+            Operation::new("Td", vec![x.into(), y.into()]),
+            Operation::new("Tj", vec![Object::string_literal(encode(text))]),
+            Operation::new("ET", vec![]),
+        ]);
+    }
+
+    // A thin rule under the last row written
+    fn line(&mut self, from_x: f32, to_x: f32) {
+        let y = self.y + 2.0;
+        self.stroke_line((from_x, y), (to_x, y));
+    }
+
+    fn stroke_line(&mut self, from: (f32, f32), to: (f32, f32)) {
+        self.operations().extend([
+            Operation::new("w", vec![0.5.into()]),
+            Operation::new("m", vec![from.0.into(), from.1.into()]),
+            Operation::new("l", vec![to.0.into(), to.1.into()]),
+            Operation::new("S", vec![]),
+        ]);
+    }
+
+    fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color32) {
+        self.operations().extend([
+            Operation::new("rg", color_operands(color)),
+            Operation::new("re", vec![x.into(), y.into(), width.into(), height.into()]),
+            Operation::new("f", vec![]),
+            Operation::new("rg", color_operands(Color32::BLACK)),
+        ]);
+    }
+
+    fn fill_polygon(&mut self, points: &[(f32, f32)], color: Color32) {
+        let Some((first, rest)) = points.split_first() else {
+            return;
+        };
+        let operations = self.operations();
+        operations.push(Operation::new("rg", color_operands(color)));
+        operations.push(Operation::new("m", vec![first.0.into(), first.1.into()]));
+        for point in rest {
+            operations.push(Operation::new("l", vec![point.0.into(), point.1.into()]));
+        }
+        operations.push(Operation::new("f", vec![]));
+        operations.push(Operation::new("rg", color_operands(Color32::BLACK)));
+    }
+
+    // Adds "Page n of m" footers and wraps the pages in a document
+    fn into_document(mut self, footer: &str) -> Result<Document, MyError> {
+        let page_count = self.pages.len();
+        for number in 1..=page_count {
+            let text = format!("{} - Page {} of {}", footer, number, page_count);
+            let operations = &mut self.pages[number - 1];
+            operations.extend([
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 7.0.into()]),
+                Operation::new("Td", vec![MARGIN.into(), (MARGIN / 2.0).into()]),
+                Operation::new("Tj", vec![Object::string_literal(encode(&text))]),
+                Operation::new("ET", vec![]),
+            ]);
+        }
+
+        let mut document = Document::with_version("1.5");
+        let pages_id = document.new_object_id();
+        let font = |name: &'static str| {
+            dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type1",
+                "BaseFont" => name,
+                "Encoding" => "WinAnsiEncoding",
+            }
+        };
+        let regular_id = document.add_object(font("Helvetica"));
+        let bold_id = document.add_object(font("Helvetica-Bold"));
+        let resources_id = document.add_object(dictionary! {
+            "Font" => dictionary! {
+                "F1" => regular_id,
+                "F2" => bold_id,
+            },
+        });
+        let mut kids = Vec::new();
+        for operations in self.pages {
+            let content = Content { operations }.encode()?;
+            let content_id = document.add_object(Stream::new(dictionary! {}, content));
+            let page_id = document.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+            });
+            kids.push(page_id.into());
+        }
+        document.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => page_count as i64,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), PAGE_WIDTH.into(), PAGE_HEIGHT.into()],
+            }),
+        );
+        let catalog_id = document.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        document.trailer.set("Root", catalog_id);
+        document.compress();
+        Ok(document)
+    }
+}
@@ -0,0 +1,829 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::attachments;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::models;
+use crate::models::{
+    Asset, Attachment, CategoryRule, Debt, DueCharges, Expense, Goal, MyError, NetWorthSnapshot,
+    Payee, RecurringSchedule, ReviewStatus, User,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// What the app loads besides expenses; the per-user lists stay empty until someone logs in
+#[derive(Default)]
+pub struct UserData {
+    pub payees: Vec<Payee>,
+    pub anomaly_reviews: HashMap<i32, ReviewStatus>,
+    pub schedules: Vec<RecurringSchedule>,
+    pub rules: Vec<CategoryRule>,
+    pub goals: Vec<Goal>,
+    pub debts: Vec<Debt>,
+    pub assets: Vec<Asset>,
+    pub net_worth_snapshots: Vec<NetWorthSnapshot>,
+}
+
+// Where MyApp keeps users' expenses: the local SQLite file, or a server started with `serve`.
+// Stores are Send so the background worker can own them.
+pub trait ExpenseStore: Send {
+    fn authenticate(&mut self, username: &str, password: &str) -> Result<Option<User>, MyError>;
+    fn is_username_unique(&self, username: &str) -> Result<bool, MyError>;
+    fn add_user(&mut self, user: &User, password: &str) -> Result<(), MyError>;
+    fn get_expenses(&self) -> Result<Vec<Expense>, MyError>;
+    // Both ends are inclusive YYYY-MM-DD dates, which compare correctly as strings
+    fn get_expenses_between(&self, from: &str, to: &str) -> Result<Vec<Expense>, MyError> {
+        let mut expenses = self.get_expenses()?;
+        expenses.retain(|expense| expense.date.as_str() >= from && expense.date.as_str() <= to);
+        Ok(expenses)
+    }
+    // Returns the id of the new expense
+    fn add_expense(&mut self, expense: &Expense) -> Result<i32, MyError>;
+    // Returns the new ids in order. Stores that can't save a batch at once say in the error how
+    // many were saved before it.
+    fn add_expenses(&mut self, expenses: &[Expense]) -> Result<Vec<i32>, MyError> {
+        let mut ids = Vec::with_capacity(expenses.len());
+        for expense in expenses {
+            match self.add_expense(expense) {
+                Ok(id) => ids.push(id),
+                Err(e) => {
+                    return Err(MyError::InvalidInput(format!(
+                        "{} ({} of {} expenses were saved before this)",
+                        e,
+                        ids.len(),
+                        expenses.len()
+                    )))
+                }
+            }
+        }
+        Ok(ids)
+    }
+    fn update_expense(&mut self, expense: &Expense) -> Result<(), MyError>;
+    // Also removes the expense's receipts
+    fn delete_expense(&mut self, expense_id: i32) -> Result<(), MyError>;
+    fn logout(&mut self) {}
+    // Rules, payees, reviews, schedules, goals, debts, net worth and receipts only live in the
+    // local database, so the app turns those features off for a remote store
+    fn is_remote(&self) -> bool {
+        false
+    }
+    fn load_user_data(&self, user_id: Option<i32>) -> Result<UserData, MyError> {
+        let mut data = UserData {
+            payees: self.get_payees()?,
+            anomaly_reviews: self.get_anomaly_reviews()?,
+            schedules: self.get_recurring_schedules()?,
+            ..UserData::default()
+        };
+        if let Some(user_id) = user_id {
+            data.rules = self.get_rules(user_id)?;
+            data.goals = self.get_goals(user_id)?;
+            data.debts = self.get_debts(user_id)?;
+            data.assets = self.get_assets(user_id)?;
+            data.net_worth_snapshots = self.get_net_worth_snapshots(user_id)?;
+        }
+        Ok(data)
+    }
+    // Copies a receipt file in and links it to the expense
+    fn attach_receipt(&mut self, _expense_id: i32, _path: &Path) -> Result<(), MyError> {
+        Err(MyError::InvalidInput(
+            "receipts can only be attached to expenses in the local database".to_string(),
+        ))
+    }
+    // Where receipt files are kept; None without local receipts
+    fn attachments_dir(&self) -> Option<PathBuf> {
+        None
+    }
+    fn get_attachments(&self, _expense_id: i32) -> Result<Vec<Attachment>, MyError> {
+        Ok(Vec::new())
+    }
+    fn remove_attachment(&mut self, _attachment: &Attachment) -> Result<(), MyError> {
+        Err(MyError::InvalidInput(
+            "receipts can only be removed from expenses in the local database".to_string(),
+        ))
+    }
+
+    // The other local-only lists read as empty and refuse changes
+    fn get_anomaly_reviews(&self) -> Result<HashMap<i32, ReviewStatus>, MyError> {
+        Ok(HashMap::new())
+    }
+    fn set_anomaly_review(
+        &mut self,
+        _expense_id: i32,
+        _status: ReviewStatus,
+    ) -> Result<(), MyError> {
+        Err(local_only("reviews"))
+    }
+    fn get_recurring_schedules(&self) -> Result<Vec<RecurringSchedule>, MyError> {
+        Ok(Vec::new())
+    }
+    fn add_recurring_schedule(&mut self, _schedule: &RecurringSchedule) -> Result<(), MyError> {
+        Err(local_only("schedules"))
+    }
+    // Returns the ids of the new expenses, in order
+    fn record_due_charges(&mut self, _charges: &[DueCharges]) -> Result<Vec<i32>, MyError> {
+        Err(local_only("schedules"))
+    }
+    fn delete_recurring_schedule(&mut self, _schedule_id: i32) -> Result<(), MyError> {
+        Err(local_only("schedules"))
+    }
+    fn get_payees(&self) -> Result<Vec<Payee>, MyError> {
+        Ok(Vec::new())
+    }
+    // Returns the id of the new payee
+    fn add_payee(&mut self, _name: &str) -> Result<i32, MyError> {
+        Err(local_only("payees"))
+    }
+    fn add_payee_alias(&mut self, _payee_id: i32, _pattern: &str) -> Result<(), MyError> {
+        Err(local_only("payees"))
+    }
+    fn delete_payee_alias(&mut self, _alias_id: i32) -> Result<(), MyError> {
+        Err(local_only("payees"))
+    }
+    fn merge_payees(&mut self, _source_id: i32, _target_id: i32) -> Result<(), MyError> {
+        Err(local_only("payees"))
+    }
+    fn get_rules(&self, _user_id: i32) -> Result<Vec<CategoryRule>, MyError> {
+        Ok(Vec::new())
+    }
+    fn add_rule(&mut self, _rule: &CategoryRule) -> Result<(), MyError> {
+        Err(local_only("rules"))
+    }
+    fn delete_rule(&mut self, _rule_id: i32) -> Result<(), MyError> {
+        Err(local_only("rules"))
+    }
+    fn swap_rule_positions(
+        &mut self,
+        _first: &CategoryRule,
+        _second: &CategoryRule,
+    ) -> Result<(), MyError> {
+        Err(local_only("rules"))
+    }
+    fn get_goals(&self, _user_id: i32) -> Result<Vec<Goal>, MyError> {
+        Ok(Vec::new())
+    }
+    fn add_goal(&mut self, _goal: &Goal) -> Result<(), MyError> {
+        Err(local_only("goals"))
+    }
+    fn delete_goal(&mut self, _goal_id: i32) -> Result<(), MyError> {
+        Err(local_only("goals"))
+    }
+    fn get_debts(&self, _user_id: i32) -> Result<Vec<Debt>, MyError> {
+        Ok(Vec::new())
+    }
+    fn add_debt(&mut self, _debt: &Debt) -> Result<(), MyError> {
+        Err(local_only("debts"))
+    }
+    fn delete_debt(&mut self, _debt_id: i32) -> Result<(), MyError> {
+        Err(local_only("debts"))
+    }
+    fn get_assets(&self, _user_id: i32) -> Result<Vec<Asset>, MyError> {
+        Ok(Vec::new())
+    }
+    // An asset with an id of 0 is added, otherwise its value is updated
+    fn save_asset(&mut self, _asset: &Asset) -> Result<(), MyError> {
+        Err(local_only("assets"))
+    }
+    fn delete_asset(&mut self, _asset_id: i32) -> Result<(), MyError> {
+        Err(local_only("assets"))
+    }
+    fn get_net_worth_snapshots(&self, _user_id: i32) -> Result<Vec<NetWorthSnapshot>, MyError> {
+        Ok(Vec::new())
+    }
+    fn save_net_worth_snapshot(
+        &mut self,
+        _user_id: i32,
+        _snapshot: &NetWorthSnapshot,
+    ) -> Result<(), MyError> {
+        Err(local_only("net worth snapshots"))
+    }
+}
+
+fn local_only(what: &str) -> MyError {
+    MyError::InvalidInput(format!("{} are only kept in the local database", what))
+}
+
+// Owns its connection, so whichever thread holds the store (the worker, the CLI or the server)
+// reads and writes the file it was opened on
+#[cfg(not(target_arch = "wasm32"))]
+pub struct SqliteStore {
+    conn: Connection,
+    attachments_dir: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SqliteStore {
+    // Creates the file and any missing tables
+    pub fn open(path: &str) -> Result<Self, MyError> {
+        let conn = models::open_database(path)?;
+        models::create_expense_table(&conn)?;
+        Ok(SqliteStore {
+            conn,
+            attachments_dir: attachments::attachments_dir(Path::new(path)),
+        })
+    }
+
+    // For the CLI and server, which query the database beyond what the trait covers
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ExpenseStore for SqliteStore {
+    fn authenticate(&mut self, username: &str, password: &str) -> Result<Option<User>, MyError> {
+        Ok(models::authenticate_user(&self.conn, username, password)?)
+    }
+
+    fn is_username_unique(&self, username: &str) -> Result<bool, MyError> {
+        models::is_username_unique(&self.conn, username)
+    }
+
+    fn add_user(&mut self, user: &User, password: &str) -> Result<(), MyError> {
+        models::add_user(&self.conn, user, password)
+    }
+
+    fn get_expenses(&self) -> Result<Vec<Expense>, MyError> {
+        Ok(models::get_expenses(&self.conn)?)
+    }
+
+    fn add_expense(&mut self, expense: &Expense) -> Result<i32, MyError> {
+        Ok(models::add_expense(&self.conn, expense)?)
+    }
+
+    // In one transaction, so nothing is saved if any expense fails
+    fn add_expenses(&mut self, expenses: &[Expense]) -> Result<Vec<i32>, MyError> {
+        Ok(models::add_expenses(&self.conn, expenses)?)
+    }
+
+    fn update_expense(&mut self, expense: &Expense) -> Result<(), MyError> {
+        Ok(models::update_expense(&self.conn, expense)?)
+    }
+
+    fn delete_expense(&mut self, expense_id: i32) -> Result<(), MyError> {
+        models::delete_expense(&self.conn, expense_id)?;
+        attachments::remove_attachments_for_expense(&self.conn, &self.attachments_dir, expense_id)
+    }
+
+    fn attach_receipt(&mut self, expense_id: i32, path: &Path) -> Result<(), MyError> {
+        attachments::attach_file(&self.conn, &self.attachments_dir, expense_id, path).map(|_| ())
+    }
+
+    fn attachments_dir(&self) -> Option<PathBuf> {
+        Some(self.attachments_dir.clone())
+    }
+
+    fn get_attachments(&self, expense_id: i32) -> Result<Vec<Attachment>, MyError> {
+        Ok(models::get_attachments(&self.conn, expense_id)?)
+    }
+
+    fn remove_attachment(&mut self, attachment: &Attachment) -> Result<(), MyError> {
+        attachments::remove_attachment(&self.conn, &self.attachments_dir, attachment)
+    }
+
+    fn get_anomaly_reviews(&self) -> Result<HashMap<i32, ReviewStatus>, MyError> {
+        Ok(models::get_anomaly_reviews(&self.conn)?)
+    }
+
+    fn set_anomaly_review(&mut self, expense_id: i32, status: ReviewStatus) -> Result<(), MyError> {
+        Ok(models::set_anomaly_review(&self.conn, expense_id, status)?)
+    }
+
+    fn get_recurring_schedules(&self) -> Result<Vec<RecurringSchedule>, MyError> {
+        Ok(models::get_recurring_schedules(&self.conn)?)
+    }
+
+    fn add_recurring_schedule(&mut self, schedule: &RecurringSchedule) -> Result<(), MyError> {
+        models::add_recurring_schedule(&self.conn, schedule)?;
+        Ok(())
+    }
+
+    fn record_due_charges(&mut self, charges: &[DueCharges]) -> Result<Vec<i32>, MyError> {
+        Ok(models::record_due_charges(&self.conn, charges)?)
+    }
+
+    fn delete_recurring_schedule(&mut self, schedule_id: i32) -> Result<(), MyError> {
+        Ok(models::delete_recurring_schedule(&self.conn, schedule_id)?)
+    }
+
+    fn get_payees(&self) -> Result<Vec<Payee>, MyError> {
+        Ok(models::get_payees(&self.conn)?)
+    }
+
+    fn add_payee(&mut self, name: &str) -> Result<i32, MyError> {
+        Ok(models::add_payee(&self.conn, name)?)
+    }
+
+    fn add_payee_alias(&mut self, payee_id: i32, pattern: &str) -> Result<(), MyError> {
+        models::add_payee_alias(&self.conn, payee_id, pattern)?;
+        Ok(())
+    }
+
+    fn delete_payee_alias(&mut self, alias_id: i32) -> Result<(), MyError> {
+        Ok(models::delete_payee_alias(&self.conn, alias_id)?)
+    }
+
+    fn merge_payees(&mut self, source_id: i32, target_id: i32) -> Result<(), MyError> {
+        Ok(models::merge_payees(&self.conn, source_id, target_id)?)
+    }
+
+    fn get_rules(&self, user_id: i32) -> Result<Vec<CategoryRule>, MyError> {
+        Ok(models::get_rules(&self.conn, user_id)?)
+    }
+
+    fn add_rule(&mut self, rule: &CategoryRule) -> Result<(), MyError> {
+        models::add_rule(&self.conn, rule)?;
+        Ok(())
+    }
+
+    fn delete_rule(&mut self, rule_id: i32) -> Result<(), MyError> {
+        Ok(models::delete_rule(&self.conn, rule_id)?)
+    }
+
+    fn swap_rule_positions(
+        &mut self,
+        first: &CategoryRule,
+        second: &CategoryRule,
+    ) -> Result<(), MyError> {
+        Ok(models::swap_rule_positions(&self.conn, first, second)?)
+    }
+
+    fn get_goals(&self, user_id: i32) -> Result<Vec<Goal>, MyError> {
+        Ok(models::get_goals(&self.conn, user_id)?)
+    }
+
+    fn add_goal(&mut self, goal: &Goal) -> Result<(), MyError> {
+        models::add_goal(&self.conn, goal)?;
+        Ok(())
+    }
+
+    fn delete_goal(&mut self, goal_id: i32) -> Result<(), MyError> {
+        Ok(models::delete_goal(&self.conn, goal_id)?)
+    }
+
+    fn get_debts(&self, user_id: i32) -> Result<Vec<Debt>, MyError> {
+        Ok(models::get_debts(&self.conn, user_id)?)
+    }
+
+    fn add_debt(&mut self, debt: &Debt) -> Result<(), MyError> {
+        models::add_debt(&self.conn, debt)?;
+        Ok(())
+    }
+
+    fn delete_debt(&mut self, debt_id: i32) -> Result<(), MyError> {
+        Ok(models::delete_debt(&self.conn, debt_id)?)
+    }
+
+    fn get_assets(&self, user_id: i32) -> Result<Vec<Asset>, MyError> {
+        Ok(models::get_assets(&self.conn, user_id)?)
+    }
+
+    fn save_asset(&mut self, asset: &Asset) -> Result<(), MyError> {
+        if asset.id == 0 {
+            models::add_asset(&self.conn, asset)?;
+        } else {
+            models::set_asset_value(&self.conn, asset.id, asset.value)?;
+        }
+        Ok(())
+    }
+
+    fn delete_asset(&mut self, asset_id: i32) -> Result<(), MyError> {
+        Ok(models::delete_asset(&self.conn, asset_id)?)
+    }
+
+    fn get_net_worth_snapshots(&self, user_id: i32) -> Result<Vec<NetWorthSnapshot>, MyError> {
+        Ok(models::get_net_worth_snapshots(&self.conn, user_id)?)
+    }
+
+    fn save_net_worth_snapshot(
+        &mut self,
+        user_id: i32,
+        snapshot: &NetWorthSnapshot,
+    ) -> Result<(), MyError> {
+        Ok(models::save_net_worth_snapshot(
+            &self.conn, user_id, snapshot,
+        )?)
+    }
+}
+
+// Talks to the JSON API of `expense_tracker serve`. Natively requests block until the server
+// answers; in the browser they go through fetch and the answer arrives in a callback.
+// Clones share the login token.
+#[derive(Clone)]
+pub struct HttpStore {
+    base_url: String,
+    token: Arc<Mutex<Option<String>>>,
+}
+
+impl HttpStore {
+    pub fn new(base_url: &str) -> Self {
+        HttpStore {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn token(&self) -> Option<String> {
+        self.token.lock().unwrap().clone()
+    }
+
+    fn set_token(&self, token: Option<String>) {
+        *self.token.lock().unwrap() = token;
+    }
+}
+
+// The server explains rejected requests in an "error" field
+fn rejection(status: u16, body: &[u8]) -> MyError {
+    let message = serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|body| body["error"].as_str().map(str::to_string))
+        .unwrap_or_else(|| format!("server returned status {}", status));
+    MyError::HttpError(message)
+}
+
+// From the body of a successful login
+fn logged_in_user(username: &str, body: &Value) -> User {
+    User {
+        id: body["user_id"].as_i64().unwrap_or_default() as i32,
+        username: username.to_string(),
+        password_hash: String::new(), // Stays on the server
+    }
+}
+
+fn parse_expenses(body: Value) -> Result<Vec<Expense>, MyError> {
+    serde_json::from_value(body).map_err(|e| MyError::HttpError(e.to_string()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl HttpStore {
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        let request = ureq::request(method, &self.url(path));
+        match self.token() {
+            Some(token) => request.set("Authorization", &format!("Bearer {}", token)),
+            None => request,
+        }
+    }
+
+    fn send(&self, method: &str, path: &str, body: Option<Value>) -> Result<Value, MyError> {
+        let request = self.request(method, path);
+        let result = match body {
+            Some(body) => request.send_json(body),
+            None => request.call(),
+        };
+        match result {
+            Ok(response) => response.into_json().map_err(MyError::from),
+            Err(ureq::Error::Status(status, response)) => Err(rejection(
+                status,
+                response.into_string().unwrap_or_default().as_bytes(),
+            )),
+            Err(e) => Err(MyError::HttpError(e.to_string())),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ExpenseStore for HttpStore {
+    fn authenticate(&mut self, username: &str, password: &str) -> Result<Option<User>, MyError> {
+        let result = self
+            .request("POST", "/api/login")
+            .send_json(json!({ "username": username, "password": password }));
+        let body: Value = match result {
+            Ok(response) => response.into_json()?,
+            Err(ureq::Error::Status(401, _)) => return Ok(None),
+            Err(e) => return Err(MyError::HttpError(e.to_string())),
+        };
+        self.set_token(body["token"].as_str().map(str::to_string));
+        Ok(Some(logged_in_user(username, &body)))
+    }
+
+    // The server checks this itself when the account is created
+    fn is_username_unique(&self, _username: &str) -> Result<bool, MyError> {
+        Ok(true)
+    }
+
+    fn add_user(&mut self, user: &User, password: &str) -> Result<(), MyError> {
+        let body = json!({ "username": user.username, "password": password });
+        self.send("POST", "/api/users", Some(body)).map(|_| ())
+    }
+
+    fn get_expenses(&self) -> Result<Vec<Expense>, MyError> {
+        // Nothing is visible before logging in
+        if self.token().is_none() {
+            return Ok(Vec::new());
+        }
+        parse_expenses(self.send("GET", "/api/expenses", None)?)
+    }
+
+    fn add_expense(&mut self, expense: &Expense) -> Result<i32, MyError> {
+        let created = self.send("POST", "/api/expenses", Some(json!(expense)))?;
+        Ok(created["id"].as_i64().unwrap_or_default() as i32)
+    }
+
+    fn update_expense(&mut self, expense: &Expense) -> Result<(), MyError> {
+        let path = format!("/api/expenses/{}", expense.id);
+        self.send("PUT", &path, Some(json!(expense))).map(|_| ())
+    }
+
+    fn delete_expense(&mut self, expense_id: i32) -> Result<(), MyError> {
+        let path = format!("/api/expenses/{}", expense_id);
+        self.send("DELETE", &path, None).map(|_| ())
+    }
+
+    fn logout(&mut self) {
+        if self.token().is_some() {
+            if let Err(e) = self.send("POST", "/api/logout", None) {
+                eprintln!("Failed to end server session: {}", e);
+            }
+            self.set_token(None);
+        }
+    }
+
+    fn is_remote(&self) -> bool {
+        true
+    }
+}
+
+// A browser tab can't wait for a request, so there the worker calls these instead of the
+// ExpenseStore methods; on_done runs once fetch has the server's answer
+#[cfg(target_arch = "wasm32")]
+impl HttpStore {
+    fn fetch(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<Value>,
+        on_done: impl FnOnce(Result<ehttp::Response, MyError>) + Send + 'static,
+    ) {
+        let mut request = ehttp::Request::get(self.url(path));
+        request.method = method.to_string();
+        if let Some(body) = body {
+            request.body = body.to_string().into_bytes();
+            request.headers.insert("Content-Type", "application/json");
+        }
+        if let Some(token) = self.token() {
+            request
+                .headers
+                .insert("Authorization", format!("Bearer {}", token));
+        }
+        ehttp::fetch(request, move |result| {
+            on_done(result.map_err(MyError::HttpError))
+        });
+    }
+
+    fn fetch_json(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<Value>,
+        on_done: impl FnOnce(Result<Value, MyError>) + Send + 'static,
+    ) {
+        self.fetch(method, path, body, move |result| {
+            on_done(result.and_then(|response| response_json(&response)))
+        });
+    }
+
+    pub fn authenticate_then(
+        &self,
+        username: &str,
+        password: &str,
+        on_done: impl FnOnce(Result<Option<User>, MyError>) + Send + 'static,
+    ) {
+        let store = self.clone();
+        let username = username.to_string();
+        let body = json!({ "username": username, "password": password });
+        self.fetch("POST", "/api/login", Some(body), move |result| {
+            on_done(result.and_then(|response| {
+                if response.status == 401 {
+                    return Ok(None);
+                }
+                let body = response_json(&response)?;
+                store.set_token(body["token"].as_str().map(str::to_string));
+                Ok(Some(logged_in_user(&username, &body)))
+            }))
+        });
+    }
+
+    // The server checks that the username is free
+    pub fn add_user_then(
+        &self,
+        user: &User,
+        password: &str,
+        on_done: impl FnOnce(Result<(), MyError>) + Send + 'static,
+    ) {
+        let body = json!({ "username": user.username, "password": password });
+        self.fetch_json("POST", "/api/users", Some(body), move |result| {
+            on_done(result.map(|_| ()))
+        });
+    }
+
+    pub fn get_expenses_then(
+        &self,
+        on_done: impl FnOnce(Result<Vec<Expense>, MyError>) + Send + 'static,
+    ) {
+        // Nothing is visible before logging in
+        if self.token().is_none() {
+            return on_done(Ok(Vec::new()));
+        }
+        self.fetch_json("GET", "/api/expenses", None, move |result| {
+            on_done(result.and_then(parse_expenses))
+        });
+    }
+
+    // Answers with the id of the new expense
+    pub fn add_expense_then(
+        &self,
+        expense: &Expense,
+        on_done: impl FnOnce(Result<i32, MyError>) + Send + 'static,
+    ) {
+        self.fetch_json(
+            "POST",
+            "/api/expenses",
+            Some(json!(expense)),
+            move |result| {
+                on_done(result.map(|created| created["id"].as_i64().unwrap_or_default() as i32))
+            },
+        );
+    }
+
+    // One PUT after another, stopping at the first the server rejects
+    pub fn update_expenses_then(
+        &self,
+        expenses: Vec<Expense>,
+        on_done: impl FnOnce(Result<Vec<Expense>, MyError>) + Send + 'static,
+    ) {
+        self.update_expenses_from(expenses, 0, on_done);
+    }
+
+    fn update_expenses_from<F>(&self, expenses: Vec<Expense>, next: usize, on_done: F)
+    where
+        F: FnOnce(Result<Vec<Expense>, MyError>) + Send + 'static,
+    {
+        let Some(expense) = expenses.get(next) else {
+            return on_done(Ok(expenses));
+        };
+        let path = format!("/api/expenses/{}", expense.id);
+        let body = json!(expense);
+        let store = self.clone();
+        self.fetch_json("PUT", &path, Some(body), move |result| match result {
+            Ok(_) => store.update_expenses_from(expenses, next + 1, on_done),
+            Err(e) => on_done(Err(e)),
+        });
+    }
+
+    pub fn delete_expense_then(
+        &self,
+        expense_id: i32,
+        on_done: impl FnOnce(Result<(), MyError>) + Send + 'static,
+    ) {
+        let path = format!("/api/expenses/{}", expense_id);
+        self.fetch_json("DELETE", &path, None, move |result| {
+            on_done(result.map(|_| ()))
+        });
+    }
+
+    // The token is forgotten straight away, whether or not the server hears about it
+    pub fn logout_then(&self, on_done: impl FnOnce() + Send + 'static) {
+        if self.token().is_none() {
+            return on_done();
+        }
+        self.fetch_json("POST", "/api/logout", None, move |result| {
+            if let Err(e) = result {
+                eprintln!("Failed to end server session: {}", e);
+            }
+            on_done();
+        });
+        self.set_token(None);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn response_json(response: &ehttp::Response) -> Result<Value, MyError> {
+    if !response.ok {
+        return Err(rejection(response.status, &response.bytes));
+    }
+    serde_json::from_slice(&response.bytes).map_err(|e| MyError::HttpError(e.to_string()))
+}
+
+// Only the local-only defaults are used in the browser; requests go through the methods above
+#[cfg(target_arch = "wasm32")]
+impl ExpenseStore for HttpStore {
+    fn authenticate(&mut self, _username: &str, _password: &str) -> Result<Option<User>, MyError> {
+        Err(sent_with_fetch())
+    }
+
+    fn is_username_unique(&self, _username: &str) -> Result<bool, MyError> {
+        Ok(true)
+    }
+
+    fn add_user(&mut self, _user: &User, _password: &str) -> Result<(), MyError> {
+        Err(sent_with_fetch())
+    }
+
+    fn get_expenses(&self) -> Result<Vec<Expense>, MyError> {
+        Err(sent_with_fetch())
+    }
+
+    fn add_expense(&mut self, _expense: &Expense) -> Result<i32, MyError> {
+        Err(sent_with_fetch())
+    }
+
+    fn update_expense(&mut self, _expense: &Expense) -> Result<(), MyError> {
+        Err(sent_with_fetch())
+    }
+
+    fn delete_expense(&mut self, _expense_id: i32) -> Result<(), MyError> {
+        Err(sent_with_fetch())
+    }
+
+    fn is_remote(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn sent_with_fetch() -> MyError {
+    MyError::HttpError("the browser can only send this request with fetch".to_string())
+}
+
+// Lowest cost bcrypt accepts; these hashes never leave the process
+const MEMORY_HASH_COST: u32 = 4;
+
+// Keeps users and expenses in memory only, so tests can run the app without a database file
+#[derive(Default)]
+pub struct MemoryStore {
+    users: Vec<User>,
+    expenses: Vec<Expense>,
+    last_id: i32,
+}
+
+impl MemoryStore {
+    fn next_id(&mut self) -> i32 {
+        self.last_id += 1;
+        self.last_id
+    }
+}
+
+impl ExpenseStore for MemoryStore {
+    fn authenticate(&mut self, username: &str, password: &str) -> Result<Option<User>, MyError> {
+        match self.users.iter().find(|user| user.username == username) {
+            Some(user) if bcrypt::verify(password, &user.password_hash)? => Ok(Some(user.clone())),
+            _ => Ok(None),
+        }
+    }
+
+    fn is_username_unique(&self, username: &str) -> Result<bool, MyError> {
+        Ok(!self.users.iter().any(|user| user.username == username))
+    }
+
+    fn add_user(&mut self, user: &User, password: &str) -> Result<(), MyError> {
+        let password_hash = bcrypt::hash(password, MEMORY_HASH_COST)?;
+        let id = self.next_id();
+        self.users.push(User {
+            id,
+            username: user.username.clone(),
+            password_hash,
+        });
+        Ok(())
+    }
+
+    fn get_expenses(&self) -> Result<Vec<Expense>, MyError> {
+        Ok(self.expenses.clone())
+    }
+
+    fn add_expense(&mut self, expense: &Expense) -> Result<i32, MyError> {
+        let id = self.next_id();
+        self.expenses.push(Expense {
+            id,
+            ..expense.clone()
+        });
+        Ok(id)
+    }
+
+    fn update_expense(&mut self, expense: &Expense) -> Result<(), MyError> {
+        match self
+            .expenses
+            .iter_mut()
+            .find(|stored| stored.id == expense.id)
+        {
+            Some(stored) => {
+                *stored = expense.clone();
+                Ok(())
+            }
+            None => Err(MyError::InvalidInput(format!(
+                "no expense with id {}",
+                expense.id
+            ))),
+        }
+    }
+
+    // Like SQLite, deleting a missing expense is not an error
+    fn delete_expense(&mut self, expense_id: i32) -> Result<(), MyError> {
+        self.expenses.retain(|expense| expense.id != expense_id);
+        Ok(())
+    }
+}
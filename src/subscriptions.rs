@@ -0,0 +1,258 @@
+use crate::models::{Cadence, DueCharges, Expense, Payee, RecurringSchedule};
+use crate::payees;
+use chrono::{Duration, Months, NaiveDate};
+use std::collections::HashMap;
+
+// A service needs to have charged this many times before it counts as a subscription
+const MIN_CHARGES: usize = 3;
+// Charges further than this from the group's usual amount belong to something else
+const AMOUNT_TOLERANCE: f32 = 0.2;
+// Share of the gaps between charges that must match the cadence
+const REGULAR_SHARE: f32 = 0.75;
+// A gap this many times the cadence still matches, e.g. a month without a charge
+const MAX_PERIODS_PER_GAP: i64 = 2;
+const CADENCES: [Cadence; 4] = [
+    Cadence::Weekly,
+    Cadence::Monthly,
+    Cadence::Quarterly,
+    Cadence::Yearly,
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subscription {
+    pub name: String, // payee name, or the latest description without one
+    pub description: String,
+    pub category: String,
+    pub payment_method: String,
+    pub payee_id: Option<i32>,
+    pub amount: f32, // the latest charge
+    pub cadence: Cadence,
+    pub charges: usize,
+    pub last_date: NaiveDate,
+    pub next_date: NaiveDate,
+}
+
+impl Subscription {
+    pub fn annual_cost(&self) -> f32 {
+        self.amount * self.cadence.per_year()
+    }
+
+    // A schedule whose first charge is the next one expected, counted from the last charge so a
+    // service billing on the 31st keeps doing so after shorter months
+    pub fn to_schedule(&self) -> RecurringSchedule {
+        RecurringSchedule {
+            id: 0,
+            description: self.description.clone(),
+            amount: self.amount,
+            category: self.category.clone(),
+            payment_method: self.payment_method.clone(),
+            payee_id: self.payee_id,
+            cadence: self.cadence,
+            next_date: self.next_date.format("%Y-%m-%d").to_string(),
+            anchor_date: self.last_date.format("%Y-%m-%d").to_string(),
+        }
+    }
+
+    pub fn is_scheduled(&self, schedules: &[RecurringSchedule]) -> bool {
+        schedules.iter().any(|schedule| match self.payee_id {
+            Some(payee_id) => schedule.payee_id == Some(payee_id),
+            None => {
+                schedule.payee_id.is_none()
+                    && normalize(&schedule.description) == normalize(&self.description)
+            }
+        })
+    }
+}
+
+#[derive(Hash, PartialEq, Eq)]
+enum GroupKey {
+    Payee(i32),
+    Description(String),
+}
+
+// Finds services that keep charging a similar amount at a regular interval. Subscriptions that
+// have missed two charges in a row by `today` are taken to be cancelled and left out.
+// The most expensive per year come first.
+pub fn detect_subscriptions(
+    expenses: &[Expense],
+    payees: &[Payee],
+    today: NaiveDate,
+) -> Vec<Subscription> {
+    let mut groups: HashMap<GroupKey, Vec<(NaiveDate, &Expense)>> = HashMap::new();
+    for expense in expenses {
+        let Ok(date) = NaiveDate::parse_from_str(&expense.date, "%Y-%m-%d") else {
+            continue;
+        };
+        let key = match expense.payee_id {
+            Some(payee_id) => GroupKey::Payee(payee_id),
+            None => GroupKey::Description(normalize(&expense.description)),
+        };
+        groups.entry(key).or_default().push((date, expense));
+    }
+
+    let mut subscriptions: Vec<Subscription> = groups
+        .into_values()
+        .filter_map(|charges| detect_in_group(charges, payees))
+        .filter(|subscription| nth_date(subscription.last_date, subscription.cadence, 2) >= today)
+        .collect();
+    subscriptions.sort_by(|a, b| {
+        b.annual_cost()
+            .total_cmp(&a.annual_cost())
+            .then(a.name.cmp(&b.name))
+    });
+    subscriptions
+}
+
+fn detect_in_group(
+    mut charges: Vec<(NaiveDate, &Expense)>,
+    payees: &[Payee],
+) -> Option<Subscription> {
+    let typical = median(charges.iter().map(|(_, expense)| expense.amount).collect());
+    charges.retain(|(_, expense)| (expense.amount - typical).abs() <= typical * AMOUNT_TOLERANCE);
+    if charges.len() < MIN_CHARGES {
+        return None;
+    }
+    charges.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.id.cmp(&b.1.id)));
+
+    let gaps: Vec<i64> = charges
+        .windows(2)
+        .map(|pair| (pair[1].0 - pair[0].0).num_days())
+        .collect();
+    let usual_gap = median(gaps.iter().map(|gap| *gap as f32).collect()) as i64;
+    let cadence = CADENCES
+        .into_iter()
+        .find(|cadence| gap_range(*cadence).contains(&usual_gap))?;
+    let regular = gaps
+        .iter()
+        .filter(|gap| fits_cadence(**gap, cadence))
+        .count();
+    if (regular as f32) < gaps.len() as f32 * REGULAR_SHARE {
+        return None;
+    }
+
+    let (last_date, latest) = *charges.last()?;
+    let name = payees::payee_name(payees, latest.payee_id).unwrap_or(&latest.description);
+    Some(Subscription {
+        name: name.to_string(),
+        description: latest.description.clone(),
+        category: latest.category.clone(),
+        payment_method: latest.payment_method.clone(),
+        payee_id: latest.payee_id,
+        amount: latest.amount,
+        cadence,
+        charges: charges.len(),
+        last_date,
+        next_date: nth_date(last_date, cadence, 1),
+    })
+}
+
+// Days between two charges that still fit the cadence
+fn gap_range(cadence: Cadence) -> std::ops::RangeInclusive<i64> {
+    match cadence {
+        Cadence::Weekly => 6..=8,
+        Cadence::Monthly => 26..=35,
+        Cadence::Quarterly => 85..=97,
+        Cadence::Yearly => 350..=380,
+    }
+}
+
+fn fits_cadence(gap: i64, cadence: Cadence) -> bool {
+    let range = gap_range(cadence);
+    (1..=MAX_PERIODS_PER_GAP)
+        .any(|periods| (range.start() * periods..=range.end() * periods).contains(&gap))
+}
+
+// The nth charge after `anchor`. Month-based cadences stop at the end of shorter months but are
+// always counted from the anchor, so Jan 31 goes on to Feb 29 and then Mar 31, not Mar 29.
+pub fn nth_date(anchor: NaiveDate, cadence: Cadence, n: u32) -> NaiveDate {
+    let months = match cadence {
+        Cadence::Weekly => {
+            return anchor
+                .checked_add_signed(Duration::weeks(n as i64))
+                .unwrap_or(NaiveDate::MAX)
+        }
+        Cadence::Monthly => n,
+        Cadence::Quarterly => 3 * n,
+        Cadence::Yearly => 12 * n,
+    };
+    anchor
+        .checked_add_months(Months::new(months))
+        .unwrap_or(NaiveDate::MAX)
+}
+
+// Dates the schedule should have charged on up to and including `today`, and the next date
+// after them. None when a stored date doesn't parse.
+pub fn due_dates(
+    schedule: &RecurringSchedule,
+    today: NaiveDate,
+) -> Option<(Vec<NaiveDate>, NaiveDate)> {
+    let anchor = NaiveDate::parse_from_str(&schedule.anchor_date, "%Y-%m-%d").ok()?;
+    let next = NaiveDate::parse_from_str(&schedule.next_date, "%Y-%m-%d").ok()?;
+    // Skip the charges that were already recorded
+    let mut n = 0;
+    while nth_date(anchor, schedule.cadence, n) < next {
+        n += 1;
+    }
+    let mut due = Vec::new();
+    while nth_date(anchor, schedule.cadence, n) <= today {
+        due.push(nth_date(anchor, schedule.cadence, n));
+        n += 1;
+    }
+    Some((due, nth_date(anchor, schedule.cadence, n)))
+}
+
+// An expense for every scheduled charge up to and including `today`, grouped by schedule
+pub fn due_charges(schedules: &[RecurringSchedule], today: NaiveDate) -> Vec<DueCharges> {
+    schedules
+        .iter()
+        .filter_map(|schedule| {
+            let (due, next) = due_dates(schedule, today)?;
+            if due.is_empty() {
+                return None;
+            }
+            let expenses = due
+                .into_iter()
+                .map(|date| Expense {
+                    id: 0,
+                    date: date.to_string(),
+                    amount: schedule.amount,
+                    category: schedule.category.clone(),
+                    description: schedule.description.clone(),
+                    payment_method: schedule.payment_method.clone(),
+                    tags: String::new(),
+                    payee_id: schedule.payee_id,
+                    time: None,
+                })
+                .collect();
+            Some(DueCharges {
+                schedule_id: schedule.id,
+                expenses,
+                next_date: next.to_string(),
+            })
+        })
+        .collect()
+}
+
+// Bank descriptions often carry reference numbers that change with every charge
+fn normalize(description: &str) -> String {
+    description
+        .to_lowercase()
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty() && !word.chars().any(|c| c.is_ascii_digit()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn median(mut values: Vec<f32>) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let middle = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    }
+}
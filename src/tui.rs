@@ -0,0 +1,509 @@
+use crate::app::MyApp;
+use crate::ui::{EXPENSE_CATEGORIES, PAYMENT_METHODS};
+use crossterm::cursor::Show;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table, TableState, Tabs};
+use ratatui::{Frame, Terminal};
+use std::collections::HashMap;
+use std::io;
+use std::panic;
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Screen {
+    Login,
+    Signup,
+    Expenses,
+    AddExpense,
+    Totals,
+}
+
+const ADD_FORM_FIELDS: usize = 5;
+
+// Terminal frontend that drives the same MyApp state and methods as the egui window
+pub struct TuiState {
+    pub app: MyApp,
+    pub screen: Screen,
+    pub focused_field: usize,
+    pub selected_expense: usize,
+    pub quit: bool,
+}
+
+impl TuiState {
+    pub fn new(app: MyApp) -> Self {
+        TuiState {
+            screen: if app.is_logged_in {
+                Screen::Expenses
+            } else {
+                Screen::Login
+            },
+            app,
+            focused_field: 0,
+            selected_expense: 0,
+            quit: false,
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        if key.code == KeyCode::Esc
+            || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+        {
+            self.quit = true;
+            return;
+        }
+        match self.screen {
+            Screen::Login | Screen::Signup => self.handle_account_key(key),
+            _ => self.handle_tracker_key(key),
+        }
+    }
+
+    // Applies finished background jobs and follows a login that has just gone through
+    pub fn tick(&mut self) {
+        self.app.poll_worker();
+        if self.app.is_logged_in && self.screen == Screen::Login {
+            self.switch_to(Screen::Expenses);
+        }
+        self.selected_expense = self
+            .selected_expense
+            .min(self.app.expenses.len().saturating_sub(1));
+    }
+
+    fn switch_to(&mut self, screen: Screen) {
+        self.screen = screen;
+        self.focused_field = 0;
+        if screen == Screen::AddExpense && self.app.expense_date.is_empty() {
+            self.app.expense_date = chrono::Local::now().format("%Y-%m-%d").to_string();
+        }
+    }
+
+    fn handle_account_key(&mut self, key: KeyEvent) {
+        let signup = self.screen == Screen::Signup;
+        match key.code {
+            KeyCode::F(2) => {
+                self.app.warning_message = None;
+                self.switch_to(if signup {
+                    Screen::Login
+                } else {
+                    Screen::Signup
+                });
+            }
+            KeyCode::Tab | KeyCode::Down | KeyCode::BackTab | KeyCode::Up => {
+                self.focused_field = 1 - self.focused_field;
+            }
+            KeyCode::Enter if signup => self.app.process_signup(),
+            // The expense list opens from tick once the login has been checked
+            KeyCode::Enter => {
+                let username = self.app.username.clone();
+                let password = self.app.password.clone();
+                self.app.process_login(&username, &password);
+            }
+            _ => {
+                let field = match (signup, self.focused_field) {
+                    (false, 0) => &mut self.app.username,
+                    (false, _) => &mut self.app.password,
+                    (true, 0) => &mut self.app.new_username,
+                    (true, _) => &mut self.app.new_password,
+                };
+                edit_text(field, key);
+            }
+        }
+    }
+
+    fn handle_tracker_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::F(1) => return self.switch_to(Screen::Expenses),
+            KeyCode::F(2) => return self.switch_to(Screen::AddExpense),
+            KeyCode::F(3) => return self.switch_to(Screen::Totals),
+            KeyCode::F(5) => {
+                self.app.logout();
+                return self.switch_to(Screen::Login);
+            }
+            _ => {}
+        }
+        match self.screen {
+            Screen::Expenses => self.handle_expenses_key(key),
+            Screen::AddExpense => self.handle_add_form_key(key),
+            _ => {}
+        }
+    }
+
+    fn handle_expenses_key(&mut self, key: KeyEvent) {
+        let count = self.app.expenses.len();
+        match key.code {
+            KeyCode::Up => self.selected_expense = self.selected_expense.saturating_sub(1),
+            KeyCode::Down if self.selected_expense + 1 < count => self.selected_expense += 1,
+            KeyCode::Delete | KeyCode::Char('d') => {
+                if let Some(expense) = self.app.expenses.get(self.selected_expense) {
+                    let expense_id = expense.id;
+                    self.app.delete_expense_from_db(expense_id);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_add_form_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Tab | KeyCode::Down => {
+                self.focused_field = (self.focused_field + 1) % ADD_FORM_FIELDS;
+            }
+            KeyCode::BackTab | KeyCode::Up => {
+                self.focused_field = (self.focused_field + ADD_FORM_FIELDS - 1) % ADD_FORM_FIELDS;
+            }
+            KeyCode::Enter => {
+                self.app.submit_expense_form();
+                if self.app.warning_message.is_none() {
+                    self.switch_to(Screen::Expenses);
+                    // Clamped to the new last row by tick once the expense is saved
+                    self.selected_expense = usize::MAX;
+                }
+            }
+            KeyCode::Left | KeyCode::Right => {
+                let forward = key.code == KeyCode::Right;
+                match self.focused_field {
+                    3 => {
+                        // The empty entry leaves the category to rules, as in the GUI
+                        let mut options = vec![""];
+                        options.extend_from_slice(EXPENSE_CATEGORIES);
+                        cycle(&mut self.app.category, &options, forward);
+                        self.app.category_suggested = false;
+                    }
+                    4 => {
+                        cycle(&mut self.app.payment_method, &PAYMENT_METHODS, forward);
+                        self.app.suggest_category();
+                    }
+                    _ => {}
+                }
+            }
+            _ => {
+                let field = match self.focused_field {
+                    0 => &mut self.app.expense_name,
+                    1 => &mut self.app.expense_amount,
+                    2 => &mut self.app.expense_date,
+                    _ => return,
+                };
+                if edit_text(field, key) && self.focused_field == 0 {
+                    self.app.suggest_category();
+                }
+            }
+        }
+    }
+}
+
+// Returns whether the text changed
+fn edit_text(text: &mut String, key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            text.push(c);
+            true
+        }
+        KeyCode::Backspace => text.pop().is_some(),
+        _ => false,
+    }
+}
+
+fn cycle(value: &mut String, options: &[&str], forward: bool) {
+    let index = options.iter().position(|option| option == value);
+    let next = match (index, forward) {
+        (None, _) => 0,
+        (Some(index), true) => (index + 1) % options.len(),
+        (Some(index), false) => (index + options.len() - 1) % options.len(),
+    };
+    *value = options[next].to_string();
+}
+
+// Renders totals as rows of "label ████ amount (percent)", largest first
+pub fn text_bar_chart(totals: &HashMap<String, f32>, width: usize) -> Vec<String> {
+    let mut sorted: Vec<(&String, &f32)> = totals.iter().collect();
+    sorted.sort_by(|a, b| b.1.total_cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    let grand_total: f32 = totals.values().sum();
+    let max_value = sorted.first().map(|(_, &value)| value).unwrap_or(0.0);
+    let label_width = sorted
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    // Room for the label, two spaces and " 1234567.89 (100.0%)"
+    let bar_width = width.saturating_sub(label_width + 2 + 20).max(1);
+
+    sorted
+        .into_iter()
+        .map(|(label, &value)| {
+            let filled = if max_value > 0.0 {
+                ((value / max_value) * bar_width as f32).round() as usize
+            } else {
+                0
+            };
+            let percent = if grand_total > 0.0 {
+                value / grand_total * 100.0
+            } else {
+                0.0
+            };
+            format!(
+                "{:<label_width$}  {}{} {:>10.2} ({:>5.1}%)",
+                label,
+                "█".repeat(filled),
+                " ".repeat(bar_width - filled.min(bar_width)),
+                value,
+                percent,
+            )
+        })
+        .collect()
+}
+
+pub fn run(app: MyApp) -> io::Result<()> {
+    install_panic_hook();
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let mut state = TuiState::new(app);
+    let result = event_loop(&mut terminal, &mut state);
+
+    // Always restore the terminal, even if drawing failed
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+// A panic while drawing would otherwise leave the shell in raw mode on the alternate screen, with
+// the panic message hidden; restore it before the previous hook prints the message
+fn install_panic_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+        previous(info);
+    }));
+}
+
+fn event_loop(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    state: &mut TuiState,
+) -> io::Result<()> {
+    while !state.quit {
+        state.tick();
+        terminal.draw(|frame| draw(frame, state))?;
+        // Wake up regularly so background results show without waiting for a key press
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key) = event::read()? {
+                // Windows also reports key releases
+                if key.kind == KeyEventKind::Press {
+                    state.handle_key(key);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn draw(frame: &mut Frame, state: &TuiState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(frame.size());
+
+    let tab_titles = if state.app.is_logged_in {
+        vec!["F1 Expenses", "F2 Add Expense", "F3 Totals"]
+    } else {
+        vec!["Login", "Sign Up (F2)"]
+    };
+    let selected_tab = match state.screen {
+        Screen::Login | Screen::Expenses => 0,
+        Screen::Signup | Screen::AddExpense => 1,
+        Screen::Totals => 2,
+    };
+    frame.render_widget(
+        Tabs::new(tab_titles)
+            .select(selected_tab)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Personal Expense Tracker"),
+            )
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        chunks[0],
+    );
+
+    match state.screen {
+        Screen::Login | Screen::Signup => draw_account_form(frame, state, chunks[1]),
+        Screen::Expenses => draw_expenses(frame, state, chunks[1]),
+        Screen::AddExpense => draw_add_form(frame, state, chunks[1]),
+        Screen::Totals => draw_totals(frame, state, chunks[1]),
+    }
+
+    if let Some(warning) = &state.app.warning_message {
+        frame.render_widget(
+            Paragraph::new(warning.as_str()).style(Style::default().fg(Color::Red)),
+            chunks[2],
+        );
+    } else if state.app.worker.is_busy() {
+        frame.render_widget(
+            Paragraph::new("Working...").style(Style::default().fg(Color::Yellow)),
+            chunks[2],
+        );
+    }
+    let help = match state.screen {
+        Screen::Login | Screen::Signup => {
+            "Tab: next field  Enter: submit  F2: login/sign up  Esc: quit"
+        }
+        Screen::Expenses => "Up/Down: select  d/Del: delete  F5: logout  Esc: quit",
+        Screen::AddExpense => "Tab: next field  Left/Right: choose option  Enter: add  Esc: quit",
+        Screen::Totals => "F1/F2: other views  F5: logout  Esc: quit",
+    };
+    frame.render_widget(
+        Paragraph::new(help).style(Style::default().fg(Color::DarkGray)),
+        chunks[3],
+    );
+}
+
+fn form_line<'a>(label: &'a str, value: String, focused: bool) -> Line<'a> {
+    let style = if focused {
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+    Line::from(vec![
+        Span::styled(if focused { "> " } else { "  " }, style),
+        Span::styled(format!("{:<16}", label), style),
+        Span::raw(value),
+    ])
+}
+
+fn draw_account_form(frame: &mut Frame, state: &TuiState, area: Rect) {
+    let app = &state.app;
+    let (title, username, password) = if state.screen == Screen::Signup {
+        (
+            "Sign Up for Expense Tracker",
+            &app.new_username,
+            &app.new_password,
+        )
+    } else {
+        ("Login to Expense Tracker", &app.username, &app.password)
+    };
+    let lines = vec![
+        form_line("Username:", username.clone(), state.focused_field == 0),
+        form_line(
+            "Password:",
+            "*".repeat(password.chars().count()),
+            state.focused_field == 1,
+        ),
+    ];
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)),
+        area,
+    );
+}
+
+fn draw_expenses(frame: &mut Frame, state: &TuiState, area: Rect) {
+    let total: f32 = state
+        .app
+        .expenses
+        .iter()
+        .map(|expense| expense.amount)
+        .sum();
+    let rows = state.app.expenses.iter().map(|expense| {
+        Row::new(vec![
+            expense.date.clone(),
+            expense.description.clone(),
+            format!("{:.2}", expense.amount),
+            expense.category.clone(),
+            expense.payment_method.clone(),
+        ])
+    });
+    let widths = [
+        Constraint::Length(10),
+        Constraint::Percentage(35),
+        Constraint::Length(10),
+        Constraint::Percentage(30),
+        Constraint::Length(8),
+    ];
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec!["Date", "Name", "Amount", "Category", "Payment"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Expenses List (Total Expenses: ${:.2})", total)),
+        )
+        .highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
+    let mut table_state = TableState::default();
+    if !state.app.expenses.is_empty() {
+        table_state.select(Some(state.selected_expense));
+    }
+    frame.render_stateful_widget(table, area, &mut table_state);
+}
+
+fn draw_add_form(frame: &mut Frame, state: &TuiState, area: Rect) {
+    let app = &state.app;
+    let category = if app.category.is_empty() {
+        "< Auto (rules) >".to_string()
+    } else if app.category_suggested {
+        format!("< {} > (suggested from your history)", app.category)
+    } else {
+        format!("< {} >", app.category)
+    };
+    let lines = vec![
+        form_line("Name:", app.expense_name.clone(), state.focused_field == 0),
+        form_line(
+            "Amount:",
+            app.expense_amount.clone(),
+            state.focused_field == 1,
+        ),
+        form_line("Date:", app.expense_date.clone(), state.focused_field == 2),
+        form_line("Expense Type:", category, state.focused_field == 3),
+        form_line(
+            "Payment Method:",
+            format!("< {} >", app.payment_method),
+            state.focused_field == 4,
+        ),
+    ];
+    frame.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Add New Expense"),
+        ),
+        area,
+    );
+}
+
+fn draw_totals(frame: &mut Frame, state: &TuiState, area: Rect) {
+    let totals = state.app.calculate_category_totals();
+    let width = area.width.saturating_sub(2) as usize;
+    let lines: Vec<Line> = text_bar_chart(&totals, width)
+        .into_iter()
+        .map(Line::from)
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Expense Amounts by Category"),
+        ),
+        area,
+    );
+}
@@ -1,18 +1,22 @@
-use crate::app::load_image_to_memory;
 pub use crate::app::MyApp;
-use crate::models::Expense;
-use chrono::NaiveDate;
+use crate::app::{DrillFilter, LOCAL_ONLY_MESSAGE};
+use crate::attachments;
+use crate::calendar::{self, CalendarDay};
+use crate::charts;
+use crate::comparison::{Baseline, GroupBy};
+use crate::debts::{DebtState, PayoffPlan, Strategy};
+use crate::forecast::MonthForecast;
+use crate::models::{Expense, Payee, ReviewStatus};
+use crate::networth;
+use crate::patterns::{self, PatternView};
+use crate::payees;
+use crate::periods::Period;
+use crate::receipts::ReceiptField;
+use crate::subscriptions::Subscription;
+use chrono::{Datelike, NaiveDate};
 use eframe::egui;
 use eframe::egui::{Color32, TextureOptions};
-use plotters::prelude::*;
 use std::collections::HashMap;
-use std::error::Error;
-
-const ORANGE: RGBColor = RGBColor(255, 165, 0);
-const PURPLE: RGBColor = RGBColor(128, 0, 128);
-const PINK: RGBColor = RGBColor(255, 192, 203);
-const LIME_GREEN: RGBColor = RGBColor(50, 205, 50);
-const INDIGO: RGBColor = RGBColor(75, 0, 130);
 
 pub fn load_texture_from_memory(
     egui_ctx: &egui::Context,
@@ -25,7 +29,7 @@ pub fn load_texture_from_memory(
     egui_ctx.load_texture(texture_id, image_data, TextureOptions::default())
 }
 
-const EXPENSE_CATEGORIES: &[&str] = &[
+pub const EXPENSE_CATEGORIES: &[&str] = &[
     "Housing and Utilities",
     "Food",
     "Transportation",
@@ -39,73 +43,7 @@ const EXPENSE_CATEGORIES: &[&str] = &[
     "Miscellaneous",
 ];
 
-pub fn create_monthly_spending_chart(data: &HashMap<String, f32>) -> Result<(), Box<dyn Error>> {
-    let root_area = BitMapBackend::new("chart.png", (640, 480)).into_drawing_area();
-    root_area.fill(&WHITE)?;
-
-    let title = "Expense Amounts by Category";
-
-    // Specify the font style as bold and increase the font size if necessary
-    let title_font = FontDesc::new(
-        FontFamily::SansSerif, // You can change this to Arial or any other font if you have it
-        20.0,
-        FontStyle::Bold,
-    )
-    .color(&RGBColor(0, 123, 255)); // Example of a bold, blue color
-
-    let title_pos = (320, 20); // Adjust as needed for your layout
-
-    // Draw the title
-    root_area.draw_text(title, &title_font, title_pos)?;
-
-    let total_expenses: f32 = data.values().sum();
-    let mut sizes = Vec::new();
-    let mut empty_labels = Vec::new();
-    let mut custom_labels = Vec::new();
-
-    let colors = vec![
-        RED, BLUE, GREEN, YELLOW, CYAN, MAGENTA, ORANGE, PINK, PURPLE, LIME_GREEN, INDIGO,
-    ];
-    let color_iter = colors.into_iter().cycle();
-
-    let center = (320, 240);
-    let radius = 150.0;
-    let label_font = FontDesc::new(FontFamily::SansSerif, 16.0, FontStyle::Normal).color(&BLACK);
-
-    for (category, &amount) in data.iter() {
-        let proportion = (amount / total_expenses) as f64;
-        sizes.push(proportion * 100.0); // Proportion in percentage
-        empty_labels.push(""); // Empty label for Pie::new
-        let label = format!("{:.2}% {}", proportion * 100.0, category);
-        custom_labels.push(label);
-    }
-
-    let color_slice: Vec<_> = color_iter.take(data.len()).collect();
-
-    root_area.draw(&Pie::new(
-        &center,
-        &radius,
-        &sizes,
-        &color_slice,
-        &empty_labels,
-    ))?;
-
-    // Draw custom labels
-    for (i, label) in custom_labels.iter().enumerate() {
-        // Calculate the position for each custom label
-        let angle = std::f64::consts::PI * 2.0 * sizes[i] / 100.0 / 2.0
-            + sizes.iter().take(i).sum::<f64>() / 100.0 * std::f64::consts::PI * 2.0;
-        let label_x = center.0 as f64 + angle.cos() * radius * 0.8;
-        let label_y = center.1 as f64 + angle.sin() * radius * 0.8;
-
-        // Draw the custom label
-        root_area.draw_text(label, &label_font, (label_x as i32, label_y as i32))?;
-    }
-
-    root_area.present()?;
-
-    Ok(())
-}
+pub const PAYMENT_METHODS: [&str; 2] = ["Cash", "Card"];
 
 pub fn render_login_ui(ui: &mut egui::Ui, app: &mut MyApp) {
     let username = app.username.clone();
@@ -122,6 +60,9 @@ pub fn render_login_ui(ui: &mut egui::Ui, app: &mut MyApp) {
             // Implement login logic in MyApp
             app.process_login(&username, &password);
         }
+        if app.worker.is_busy() {
+            ui.spinner();
+        }
     });
     display_warning_message(ui, app);
 }
@@ -144,6 +85,9 @@ pub fn render_signup_ui(ui: &mut egui::Ui, app: &mut MyApp) {
         if ui.button("Sign Up").clicked() {
             app.process_signup();
         }
+        if app.worker.is_busy() {
+            ui.spinner();
+        }
     });
     display_warning_message(ui, app);
 }
@@ -191,6 +135,8 @@ pub fn render_expense_tracker_ui(_ui: &mut egui::Ui, app: &mut MyApp, ctx: &egui
 
     ctx.set_style(style);
     let mut expenses_to_delete: Vec<i32> = Vec::new();
+    let mut receipts_to_open: Option<i32> = None;
+    let mut rule_from_expense: Option<i32> = None;
 
     egui::CentralPanel::default().show(ctx, |ui| {
         ui.heading("Personal Expense Tracker");
@@ -208,9 +154,24 @@ pub fn render_expense_tracker_ui(_ui: &mut egui::Ui, app: &mut MyApp, ctx: &egui
         }
         ui.vertical(|ui| {
             ui.heading("Add New Expense");
+            if !app.server_mode {
+                ui.horizontal(|ui| {
+                    ui.label("Receipt:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut app.scan_receipt_path)
+                            .hint_text("/path/to/receipt.pdf (optional)"),
+                    );
+                    if ui.button("Scan").clicked() {
+                        app.scan_receipt();
+                    }
+                });
+                render_receipt_suggestion(ui, app);
+            }
             ui.horizontal(|ui| {
                 ui.label("New Expense Name:");
-                ui.text_edit_singleline(&mut app.expense_name);
+                if ui.text_edit_singleline(&mut app.expense_name).changed() {
+                    app.suggest_category();
+                }
             });
             ui.horizontal(|ui| {
                 ui.label("New Expense Amount:");
@@ -220,12 +181,25 @@ pub fn render_expense_tracker_ui(_ui: &mut egui::Ui, app: &mut MyApp, ctx: &egui
                 ui.label("Date:");
                 ui.add(egui::TextEdit::singleline(&mut app.expense_date).hint_text("YYYY-MM-DD"));
             });
+            ui.horizontal(|ui| {
+                ui.label("Time:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut app.expense_time).hint_text("HH:MM (optional)"),
+                );
+            });
             ui.horizontal(|ui| {
                 ui.label("Expense Type:");
+                let category_before = app.category.clone();
                 ui.push_id("expense_type", |ui| {
+                    let selected_text = if app.category.is_empty() {
+                        "Auto (rules)".to_string()
+                    } else {
+                        app.category.clone()
+                    };
                     egui::ComboBox::from_label("")
-                        .selected_text(app.category.clone())
+                        .selected_text(selected_text)
                         .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut app.category, String::new(), "Auto (rules)");
                             for category in EXPENSE_CATEGORIES.iter() {
                                 ui.selectable_value(
                                     &mut app.category,
@@ -235,10 +209,16 @@ pub fn render_expense_tracker_ui(_ui: &mut egui::Ui, app: &mut MyApp, ctx: &egui
                             }
                         });
                 });
+                if app.category != category_before {
+                    app.category_suggested = false; // Picked by hand
+                } else if app.category_suggested {
+                    ui.weak("(suggested from your history)");
+                }
             });
             ui.horizontal(|ui| {
                 ui.label("Payment Method:");
-                let payment_methods = ["Cash", "Card"];
+                let payment_method_before = app.payment_method.clone();
+                let payment_methods = PAYMENT_METHODS;
                 egui::ComboBox::from_label("")
                     .selected_text(app.payment_method.clone())
                     .show_ui(ui, |ui| {
@@ -250,24 +230,25 @@ pub fn render_expense_tracker_ui(_ui: &mut egui::Ui, app: &mut MyApp, ctx: &egui
                             );
                         }
                     });
+                if app.payment_method != payment_method_before {
+                    app.suggest_category();
+                }
             });
 
             let add_button = ui.add(egui::Button::new("Add"));
 
             if add_button.clicked() {
-                if !app.expense_name.is_empty()
-                    && !app.expense_amount.is_empty()
-                    && !app.expense_date.is_empty()
-                    && !app.category.is_empty()
-                    && !app.payment_method.is_empty()
-                {
-                    app.add_expense_to_db(ctx);
-                    app.warning_message = None; // Clear any previous warning
-                } else {
-                    app.warning_message = Some("Please fill in all fields".to_string());
-                }
+                app.submit_expense_form();
             }
 
+            ui.horizontal(|ui| {
+                ui.label("Import CSV:");
+                ui.text_edit_singleline(&mut app.import_path);
+                if ui.button("Import").clicked() {
+                    app.import_csv();
+                }
+            });
+
             if let Some(warning) = &app.warning_message {
                 ui.colored_label(egui::Color32::RED, warning);
             }
@@ -276,29 +257,65 @@ pub fn render_expense_tracker_ui(_ui: &mut egui::Ui, app: &mut MyApp, ctx: &egui
         ui.separator();
 
         ui.vertical(|ui| {
-            ui.heading("Expenses List");
+            ui.horizontal(|ui| {
+                ui.heading("Expenses List");
+                // Saving, loading or importing is still running in the background
+                if app.worker.is_busy() {
+                    ui.spinner();
+                }
+            });
+            render_drill_down_breadcrumbs(ui, app);
 
             // Improved table of expenses
             egui::Grid::new("expenses_table")
-                .num_columns(6) // Adjust the number of columns to include all properties
+                .num_columns(10) // Adjust the number of columns to include all properties
                 .striped(true)
                 .show(ui, |ui| {
                     // Header row
                     ui.label("Date");
                     ui.label("Name");
+                    ui.label("Payee");
                     ui.label("Amount");
                     ui.label("Category");
+                    ui.label("Tags");
                     ui.label("Payment Method");
+                    ui.label(""); // Placeholder for the rule button column
+                    ui.label(""); // Placeholder for the receipts button column
                     ui.label(""); // Placeholder for the delete button column
                     ui.end_row();
 
                     // Rows for each expense
                     for (index, expense) in app.expenses.iter().enumerate() {
+                        // The list only narrows down once a chart has been clicked
+                        if !app.drill_down.is_empty() && !app.is_drilled(expense) {
+                            continue;
+                        }
                         ui.label(&expense.date);
                         ui.label(&expense.description);
-                        ui.label(&format!("{:.2}", expense.amount));
+                        ui.label(payees::payee_name(&app.payees, expense.payee_id).unwrap_or("-"));
+                        ui.label(format!("{:.2}", expense.amount));
                         ui.label(&expense.category);
+                        ui.label(&expense.tags);
                         ui.label(&expense.payment_method);
+                        if app.server_mode {
+                            ui.label("");
+                            ui.label("");
+                        } else {
+                            ui.push_id(index, |ui| {
+                                if ui
+                                    .button("Rule")
+                                    .on_hover_text("Create a rule from this expense")
+                                    .clicked()
+                                {
+                                    rule_from_expense = Some(expense.id);
+                                }
+                            });
+                            ui.push_id(index, |ui| {
+                                if ui.button("Receipts").clicked() {
+                                    receipts_to_open = Some(expense.id);
+                                }
+                            });
+                        }
                         ui.push_id(index, |ui| {
                             if ui.button("Delete").clicked() {
                                 expenses_to_delete.push(expense.id);
@@ -309,184 +326,1387 @@ pub fn render_expense_tracker_ui(_ui: &mut egui::Ui, app: &mut MyApp, ctx: &egui
                 });
         });
 
+        if app.server_mode {
+            ui.weak(LOCAL_ONLY_MESSAGE);
+        } else {
+            egui::CollapsingHeader::new("Categorization Rules")
+                .open(rule_from_expense.map(|_| true))
+                .show(ui, |ui| {
+                    render_rules_ui(ui, app);
+                });
+
+            egui::CollapsingHeader::new("Payees").show(ui, |ui| {
+                render_payees_ui(ui, app);
+            });
+
+            let flagged = app.needs_review().len();
+            egui::CollapsingHeader::new(format!("Needs Review ({})", flagged))
+                .id_source("needs_review")
+                .show(ui, |ui| {
+                    render_needs_review(ui, app);
+                });
+
+            egui::CollapsingHeader::new("Subscriptions").show(ui, |ui| {
+                render_subscriptions(ui, app);
+            });
+
+            egui::CollapsingHeader::new("Savings Goals").show(ui, |ui| {
+                render_goals(ui, app);
+            });
+
+            egui::CollapsingHeader::new("Debts").show(ui, |ui| {
+                render_debts(ui, app);
+            });
+
+            egui::CollapsingHeader::new("Net Worth").show(ui, |ui| {
+                render_net_worth(ui, app);
+            });
+        }
+
+        egui::CollapsingHeader::new("Cash-Flow Calendar").show(ui, |ui| {
+            render_calendar(ui, app);
+        });
+
         ui.vertical(|ui| {
             ui.heading("Expense Analytics");
+            render_period_selector(ui, app);
 
-            // Example: Monthly Trends
-            if ui.button("Show Monthly Trends").clicked() {
-                app.show_monthly_trends = true;
-                app.show_yearly_comparison = false;
-                app.show_monthly_spending = false;
-                let monthly_data = calculate_monthly_trends(&app.expenses); // Implement this
-                let _ = create_bar_chart("monthly_trends.png", &monthly_data); // Implement this
-                load_and_display_chart(ui, "monthly_trends.png"); // Implement this
+            ui.horizontal(|ui| {
+                if ui.button("Show Monthly Trends").clicked() {
+                    app.show_monthly_trends = true;
+                    app.show_yearly_comparison = false;
+                    app.show_monthly_spending = false;
+                    app.show_period_comparison = false;
+                    app.show_spending_patterns = false;
+                }
+                if ui.button("Show Yearly Comparison").clicked() {
+                    app.show_yearly_comparison = true;
+                    app.show_monthly_trends = false;
+                    app.show_monthly_spending = false;
+                    app.show_period_comparison = false;
+                    app.show_spending_patterns = false;
+                }
+                if ui.button("Show Monthly Spending").clicked() {
+                    app.show_monthly_spending = true;
+                    app.show_monthly_trends = false;
+                    app.show_yearly_comparison = false;
+                    app.show_period_comparison = false;
+                    app.show_spending_patterns = false;
+                }
+                if ui.button("Show Period Comparison").clicked() {
+                    app.show_period_comparison = true;
+                    app.show_monthly_trends = false;
+                    app.show_yearly_comparison = false;
+                    app.show_monthly_spending = false;
+                    app.show_spending_patterns = false;
+                }
+                if ui.button("Show Spending Patterns").clicked() {
+                    app.show_spending_patterns = true;
+                    app.show_monthly_trends = false;
+                    app.show_yearly_comparison = false;
+                    app.show_monthly_spending = false;
+                    app.show_period_comparison = false;
+                }
+            });
+
+            // Drawn from the drilled-down expenses every frame; hovering shows exact amounts and
+            // clicking a slice or bar drills into that category or month
+            render_drill_down_breadcrumbs(ui, app);
+            if app.show_period_comparison {
+                render_period_comparison(ui, app);
+                return;
+            }
+            if app.show_spending_patterns {
+                render_spending_patterns(ui, app);
+                return;
             }
+            let expenses = app.drilled_expenses();
+            let period = app.period.label();
+            let clicked = if app.show_monthly_trends {
+                let monthly_data = calculate_monthly_trends(&expenses);
+                let title = format!("Monthly Spending ({})", period);
+                let today = chrono::Local::now().date_naive();
+                let forecast = app.month_forecast(today);
+                let projection: Vec<charts::Projection> = app
+                    .projected_months(today)
+                    .into_iter()
+                    .map(|month| charts::Projection {
+                        label: month.month,
+                        expected: month.expected,
+                        low: month.low,
+                        high: month.high,
+                    })
+                    .collect();
+                let clicked = charts::bar_chart(ui, &title, &monthly_data, &projection);
+                if let Some(forecast) = &forecast {
+                    render_forecast_table(ui, forecast);
+                }
+                clicked.map(DrillFilter::Month)
+            } else if app.show_yearly_comparison {
+                let yearly_data = calculate_yearly_comparison(&expenses);
+                let title = format!("Yearly Spending Comparison ({})", period);
+                charts::line_chart(ui, &title, &yearly_data);
+                // Snapshots aren't expenses, so the period and drill-down don't apply
+                let net_worth = networth::monthly_history(&app.net_worth_snapshots);
+                if !net_worth.is_empty() {
+                    charts::line_chart(ui, "Net Worth", &net_worth);
+                }
+                None
+            } else {
+                let category_totals = calculate_category_totals(&expenses);
+                let title = format!("Expense Amounts by Category ({})", period);
+                charts::pie_chart(ui, &title, &category_totals).map(DrillFilter::Category)
+            };
+            drop(expenses);
+            if let Some(filter) = clicked {
+                app.drill_into(filter);
+            }
+        });
+    });
+
+    if let Some(id) = rule_from_expense {
+        app.rule_form_from_expense(id);
+    }
+    if let Some(id) = receipts_to_open {
+        app.open_receipts(id);
+    }
+    render_receipts_window(app, ctx);
+
+    // Process deletions after UI rendering
+    for id in expenses_to_delete {
+        app.delete_expense_from_db(id);
+    }
+}
 
-            if ui.button("Show Yearly Comparison").clicked() {
-                app.show_yearly_comparison = true;
-                app.show_monthly_trends = false;
-                app.show_monthly_spending = false;
-                let yearly_data = calculate_yearly_comparison(&app.expenses); // Implement this
-                let _ = create_line_graph("yearly_comparison.png", &yearly_data); // Implement this
-                load_and_display_chart(ui, "yearly_comparison.png"); // Implement this
+pub fn render_rules_ui(ui: &mut egui::Ui, app: &mut MyApp) {
+    let mut rule_to_delete: Option<i32> = None;
+    let mut rule_to_move: Option<(i32, bool)> = None;
+
+    ui.label(
+        "Rules run top to bottom and the first match assigns the category, tags and description.",
+    );
+    egui::Grid::new("rules_table").striped(true).show(ui, |ui| {
+        ui.label("Description");
+        ui.label("Amount");
+        ui.label("Payment Method");
+        ui.label("Category");
+        ui.label("Tags");
+        ui.label("Rename To");
+        ui.label("");
+        ui.end_row();
+
+        for (index, rule) in app.rules.iter().enumerate() {
+            let pattern = match (rule.description_pattern.is_empty(), rule.is_regex) {
+                (true, _) => "any".to_string(),
+                (false, true) => format!("matches /{}/", rule.description_pattern),
+                (false, false) => format!("contains \"{}\"", rule.description_pattern),
+            };
+            let amount = match (rule.min_amount, rule.max_amount) {
+                (Some(min), Some(max)) => format!("{:.2} - {:.2}", min, max),
+                (Some(min), None) => format!(">= {:.2}", min),
+                (None, Some(max)) => format!("<= {:.2}", max),
+                (None, None) => "any".to_string(),
+            };
+            ui.label(pattern);
+            ui.label(amount);
+            ui.label(if rule.payment_method.is_empty() {
+                "any"
+            } else {
+                &rule.payment_method
+            });
+            ui.label(&rule.category);
+            ui.label(&rule.tags);
+            ui.label(&rule.rename_to);
+            ui.push_id(("rule", index), |ui| {
+                ui.horizontal(|ui| {
+                    if ui.small_button("Up").clicked() {
+                        rule_to_move = Some((rule.id, true));
+                    }
+                    if ui.small_button("Down").clicked() {
+                        rule_to_move = Some((rule.id, false));
+                    }
+                    if ui.small_button("Delete").clicked() {
+                        rule_to_delete = Some(rule.id);
+                    }
+                });
+            });
+            ui.end_row();
+        }
+    });
+
+    ui.separator();
+    ui.label("New Rule");
+    let form = &mut app.rule_form;
+    egui::Grid::new("rule_form").show(ui, |ui| {
+        ui.label("Description:");
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut form.description_pattern);
+            ui.checkbox(&mut form.is_regex, "Regex");
+        });
+        ui.end_row();
+        ui.label("Amount range:");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut form.min_amount)
+                    .hint_text("min")
+                    .desired_width(60.0),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut form.max_amount)
+                    .hint_text("max")
+                    .desired_width(60.0),
+            );
+        });
+        ui.end_row();
+        ui.label("Payment Method:");
+        egui::ComboBox::from_id_source("rule_payment_method")
+            .selected_text(if form.payment_method.is_empty() {
+                "Any"
+            } else {
+                &form.payment_method
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut form.payment_method, String::new(), "Any");
+                for payment_method in PAYMENT_METHODS {
+                    ui.selectable_value(
+                        &mut form.payment_method,
+                        payment_method.to_string(),
+                        payment_method,
+                    );
+                }
+            });
+        ui.end_row();
+        ui.label("Set Category:");
+        egui::ComboBox::from_id_source("rule_category")
+            .selected_text(form.category.clone())
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut form.category, String::new(), "Keep");
+                for category in EXPENSE_CATEGORIES.iter() {
+                    ui.selectable_value(&mut form.category, category.to_string(), *category);
+                }
+            });
+        ui.end_row();
+        ui.label("Add Tags:");
+        ui.add(egui::TextEdit::singleline(&mut form.tags).hint_text("comma separated"));
+        ui.end_row();
+        ui.label("Rename To:");
+        ui.text_edit_singleline(&mut form.rename_to);
+        ui.end_row();
+    });
+
+    ui.horizontal(|ui| {
+        if ui.button("Save Rule").clicked() {
+            app.save_rule_form();
+        }
+        if ui.button("Dry Run").clicked() {
+            app.preview_rules();
+        }
+    });
+
+    let mut apply_clicked = false;
+    if let Some(changes) = &app.rule_changes {
+        if changes.is_empty() {
+            ui.label("No existing expenses would change.");
+        } else {
+            ui.label(format!("{} expenses would change:", changes.len()));
+            egui::Grid::new("rule_changes")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Description");
+                    ui.label("Category");
+                    ui.label("Tags");
+                    ui.end_row();
+                    for change in changes {
+                        ui.label(change_text(
+                            &change.old_description,
+                            &change.new_description,
+                        ));
+                        ui.label(change_text(&change.old_category, &change.new_category));
+                        ui.label(change_text(&change.old_tags, &change.new_tags));
+                        ui.end_row();
+                    }
+                });
+            apply_clicked = ui.button("Apply Changes").clicked();
+        }
+    }
+
+    if apply_clicked {
+        app.apply_rule_changes();
+    }
+    if let Some(id) = rule_to_delete {
+        app.delete_rule(id);
+    }
+    if let Some((id, up)) = rule_to_move {
+        app.move_rule(id, up);
+    }
+}
+
+fn payee_combo(ui: &mut egui::Ui, id: &str, payees: &[Payee], selected: &mut Option<i32>) {
+    egui::ComboBox::from_id_source(id)
+        .selected_text(payees::payee_name(payees, *selected).unwrap_or("Choose payee"))
+        .show_ui(ui, |ui| {
+            for payee in payees {
+                ui.selectable_value(selected, Some(payee.id), &payee.name);
             }
+        });
+}
 
-            if ui.button("Show Monthly Spending").clicked() {
-                app.show_monthly_spending = true;
-                app.show_monthly_trends = false;
-                app.show_yearly_comparison = false;
+// Charges that look unusual next to earlier ones from the same payee or category
+fn render_needs_review(ui: &mut egui::Ui, app: &mut MyApp) {
+    let mut review: Option<(i32, ReviewStatus)> = None;
+    let flagged = app.needs_review();
+    if flagged.is_empty() {
+        ui.label("Nothing unusual");
+        return;
+    }
+    egui::Grid::new("needs_review_table")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("Date");
+            ui.label("Description");
+            ui.label("Amount");
+            ui.label("Why");
+            ui.end_row();
+            for (anomaly, expense) in flagged {
+                ui.label(&expense.date);
+                ui.label(&expense.description);
+                ui.label(format!("{:.2}", expense.amount));
+                ui.label(anomaly.reason());
+                ui.push_id(("review", expense.id), |ui| {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .button("Looks Fine")
+                            .on_hover_text("Keep it as part of the usual spending")
+                            .clicked()
+                        {
+                            review = Some((expense.id, ReviewStatus::Dismissed));
+                        }
+                        if ui
+                            .button("Confirm")
+                            .on_hover_text("Mark it unusual so it doesn't raise the baseline")
+                            .clicked()
+                        {
+                            review = Some((expense.id, ReviewStatus::Confirmed));
+                        }
+                    });
+                });
+                ui.end_row();
             }
-            if app.show_monthly_trends {
-                // Display the chart here
-                // Load and display the chart
-                load_and_display_chart(ui, "monthly_trends.png");
-            } else if app.show_yearly_comparison {
-                // Display yearly comparison chart
-                load_and_display_chart(ui, "yearly_comparison.png");
-            } else if app.show_monthly_spending {
-                // Display monthly spending chart
-                load_and_display_chart(ui, "monthly_spending.png"); // Assume this is the correct file path
+        });
+
+    if let Some((expense_id, status)) = review {
+        app.review_anomaly(expense_id, status);
+    }
+}
+
+// Services detected from the expense history, and the schedules made from them
+fn render_subscriptions(ui: &mut egui::Ui, app: &mut MyApp) {
+    let today = chrono::Local::now().date_naive();
+    let detected = app.subscriptions(today);
+    let mut to_schedule: Option<Subscription> = None;
+    let mut schedule_to_delete: Option<i32> = None;
+
+    if detected.is_empty() {
+        ui.label("No regular charges found yet");
+    } else {
+        egui::Grid::new("subscriptions_table")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Service");
+                ui.label("Cadence");
+                ui.label("Amount");
+                ui.label("Next Expected");
+                ui.label("Per Year");
+                ui.end_row();
+                for subscription in &detected {
+                    ui.label(&subscription.name);
+                    ui.label(subscription.cadence.label());
+                    ui.label(format!("{:.2}", subscription.amount));
+                    ui.label(subscription.next_date.to_string());
+                    ui.label(format!("{:.2}", subscription.annual_cost()));
+                    ui.push_id(("subscription", &subscription.name), |ui| {
+                        if subscription.is_scheduled(&app.schedules) {
+                            ui.label("Scheduled");
+                        } else if ui
+                            .button("Schedule")
+                            .on_hover_text("Record this charge automatically from now on")
+                            .clicked()
+                        {
+                            to_schedule = Some(subscription.clone());
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        let annual: f32 = detected.iter().map(|s| s.annual_cost()).sum();
+        ui.label(format!("Total per year: {:.2}", annual));
+    }
+
+    ui.separator();
+    ui.label("Schedules");
+    egui::Grid::new("schedules_table")
+        .striped(true)
+        .show(ui, |ui| {
+            for schedule in &app.schedules {
+                ui.label(&schedule.description);
+                ui.label(schedule.cadence.label());
+                ui.label(format!("{:.2}", schedule.amount));
+                ui.label(&schedule.next_date);
+                ui.push_id(("schedule", schedule.id), |ui| {
+                    if ui.button("Remove").clicked() {
+                        schedule_to_delete = Some(schedule.id);
+                    }
+                });
+                ui.end_row();
+            }
+        });
+    let due = app.due_schedule_count(today);
+    if ui
+        .add_enabled(
+            due > 0,
+            egui::Button::new(format!("Record Due Charges ({})", due)),
+        )
+        .clicked()
+    {
+        app.record_due_schedules(today);
+    }
+
+    if let Some(subscription) = to_schedule {
+        app.schedule_subscription(&subscription);
+    }
+    if let Some(schedule_id) = schedule_to_delete {
+        app.delete_schedule(schedule_id);
+    }
+}
+
+fn render_goals(ui: &mut egui::Ui, app: &mut MyApp) {
+    let today = chrono::Local::now().date_naive();
+    let mut goal_to_delete: Option<i32> = None;
+
+    for (goal, progress) in app.goal_progress(today) {
+        ui.push_id(("goal", goal.id), |ui| {
+            ui.horizontal(|ui| {
+                ui.strong(&goal.name);
+                ui.label(format!("by {}", goal.target_date));
+                if ui.small_button("Delete").clicked() {
+                    goal_to_delete = Some(goal.id);
+                }
+            });
+            ui.add(egui::ProgressBar::new(progress.fraction).text(format!(
+                "{:.2} of {:.2}",
+                progress.saved, goal.target_amount
+            )));
+            let source = if goal.tag.is_empty() {
+                goal.category.clone()
+            } else {
+                format!("{} tagged \"{}\"", goal.category, goal.tag)
+            };
+            ui.label(format!("Counting {} since {}", source, goal.start_date));
+            if progress.is_reached() {
+                ui.colored_label(Color32::DARK_GREEN, "Reached");
+                return;
+            }
+            ui.label(format!(
+                "Needs {:.2} a month; saving {:.2} a month so far",
+                progress.required_monthly, progress.monthly_pace
+            ));
+            let projection = match progress.projected_date {
+                Some(date) => format!(" (reached around {})", date),
+                None => String::new(),
+            };
+            if progress.on_track {
+                ui.colored_label(Color32::DARK_GREEN, format!("On track{}", projection));
+            } else {
+                ui.colored_label(Color32::RED, format!("Behind{}", projection));
             }
         });
+        ui.separator();
+    }
+
+    ui.label("New Goal");
+    let form = &mut app.goal_form;
+    egui::Grid::new("goal_form").show(ui, |ui| {
+        ui.label("Name:");
+        ui.add(egui::TextEdit::singleline(&mut form.name).hint_text("e.g. New laptop"));
+        ui.end_row();
+        ui.label("Target Amount:");
+        ui.text_edit_singleline(&mut form.target_amount);
+        ui.end_row();
+        ui.label("Target Date:");
+        ui.add(egui::TextEdit::singleline(&mut form.target_date).hint_text("YYYY-MM-DD"));
+        ui.end_row();
+        ui.label("Category:");
+        egui::ComboBox::from_id_source("goal_category")
+            .selected_text(form.category.clone())
+            .show_ui(ui, |ui| {
+                for category in EXPENSE_CATEGORIES.iter() {
+                    ui.selectable_value(&mut form.category, category.to_string(), *category);
+                }
+            });
+        ui.end_row();
+        ui.label("Tag:");
+        ui.add(egui::TextEdit::singleline(&mut form.tag).hint_text("optional, e.g. laptop"));
+        ui.end_row();
+    });
+    if ui.button("Add Goal").clicked() {
+        app.add_goal_from_form(today);
+    }
+
+    if let Some(goal_id) = goal_to_delete {
+        app.delete_goal(goal_id);
+    }
+}
 
-        if let Some(texture) = &app.image_texture {
-            // Display the image using the texture
-            ui.image(texture);
-            // Forcefully request a repaint
-            ctx.request_repaint();
+fn render_debts(ui: &mut egui::Ui, app: &mut MyApp) {
+    let today = chrono::Local::now().date_naive();
+    let mut debt_to_delete: Option<i32> = None;
+
+    ui.label(
+        "Payments are \"Debt Payments\" expenses tagged with the debt's tag, counted from the day the debt is added.",
+    );
+    let states = app.debt_states();
+    egui::Grid::new("debts_table").striped(true).show(ui, |ui| {
+        ui.label("Debt");
+        ui.label("Balance");
+        ui.label("APR");
+        ui.label("Minimum");
+        ui.label("Tag");
+        ui.end_row();
+        for (debt, state) in app.debts.iter().zip(&states) {
+            ui.label(&debt.name);
+            ui.label(format!("{:.2}", state.balance));
+            ui.label(format!("{:.2}%", debt.apr));
+            ui.label(format!("{:.2}", debt.minimum_payment));
+            ui.label(&debt.tag);
+            ui.push_id(("debt", debt.id), |ui| {
+                if ui.button("Delete").clicked() {
+                    debt_to_delete = Some(debt.id);
+                }
+            });
+            ui.end_row();
         }
     });
 
-    // Process deletions after UI rendering
-    for id in expenses_to_delete {
-        app.delete_expense_from_db(id, ctx);
+    ui.separator();
+    ui.label("New Debt");
+    let form = &mut app.debt_form;
+    egui::Grid::new("debt_form").show(ui, |ui| {
+        ui.label("Name:");
+        ui.add(egui::TextEdit::singleline(&mut form.name).hint_text("e.g. Credit card"));
+        ui.end_row();
+        ui.label("Balance:");
+        ui.text_edit_singleline(&mut form.balance);
+        ui.end_row();
+        ui.label("APR (%):");
+        ui.text_edit_singleline(&mut form.apr);
+        ui.end_row();
+        ui.label("Minimum Payment:");
+        ui.text_edit_singleline(&mut form.minimum_payment);
+        ui.end_row();
+        ui.label("Tag:");
+        ui.add(egui::TextEdit::singleline(&mut form.tag).hint_text("defaults to the name"));
+        ui.end_row();
+    });
+    if ui.button("Add Debt").clicked() {
+        app.add_debt_from_form(today);
+    }
+
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.label("Monthly budget for debts:");
+        ui.add(
+            egui::TextEdit::singleline(&mut app.debt_form.monthly_budget)
+                .hint_text("minimums only")
+                .desired_width(80.0),
+        );
+    });
+    if let Some(plans) = app.payoff_plans(today) {
+        render_payoff_plans(ui, app, &states, &plans);
+    }
+
+    if let Some(debt_id) = debt_to_delete {
+        app.delete_debt(debt_id);
     }
 }
 
-// Function to calculate monthly trends (implement the logic based on your data structure)
-fn calculate_monthly_trends(expenses: &[Expense]) -> HashMap<String, f32> {
-    let mut monthly_totals = HashMap::new();
-    for expense in expenses {
-        let month = expense.date[0..7].to_string(); // Extract YYYY-MM
-        *monthly_totals.entry(month).or_insert(0.0) += expense.amount;
+const CALENDAR_CELL: egui::Vec2 = egui::vec2(86.0, 64.0);
+
+// A month grid coloured by how much was spent each day; clicking a day lists its expenses
+fn render_calendar(ui: &mut egui::Ui, app: &mut MyApp) {
+    let today = chrono::Local::now().date_naive();
+    let month = app.calendar(today);
+    ui.horizontal(|ui| {
+        if ui.button("<").clicked() {
+            app.step_calendar(false, today);
+        }
+        ui.strong(month.first_day.format("%B %Y").to_string());
+        if ui.button(">").clicked() {
+            app.step_calendar(true, today);
+        }
+        if ui.button("Today").clicked() {
+            app.calendar_month = None;
+            app.calendar_day = Some(today);
+        }
+    });
+    ui.label(format!(
+        "Spent {:.2}, refunds/credits {:.2}, still scheduled {:.2}",
+        month.total_spent(),
+        month.total_credits(),
+        month.total_scheduled()
+    ));
+
+    let busiest_day = month.busiest_day();
+    let mut clicked_day = None;
+    egui::Grid::new("calendar_grid")
+        .spacing(egui::vec2(2.0, 2.0))
+        .show(ui, |ui| {
+            for weekday in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"] {
+                ui.label(weekday);
+            }
+            ui.end_row();
+            for _ in 0..month.leading_blanks {
+                ui.label("");
+            }
+            for (index, day) in month.days.iter().enumerate() {
+                let selected = app.calendar_day == Some(day.date);
+                if calendar_cell(ui, day, busiest_day, selected).clicked() {
+                    clicked_day = Some(day.date);
+                }
+                if (month.leading_blanks + index) % 7 == 6 {
+                    ui.end_row();
+                }
+            }
+        });
+    if let Some(day) = clicked_day {
+        app.calendar_day = Some(day);
     }
-    monthly_totals
+
+    let Some(day) = app.calendar_day else {
+        return;
+    };
+    ui.separator();
+    let expenses = app.calendar_day_expenses();
+    if expenses.is_empty() {
+        ui.label(format!("No expenses on {}", day));
+        return;
+    }
+    ui.label(format!("Expenses on {}", day));
+    egui::Grid::new("calendar_day_expenses")
+        .striped(true)
+        .show(ui, |ui| {
+            for expense in expenses {
+                ui.label(&expense.description);
+                ui.label(&expense.category);
+                ui.label(&expense.payment_method);
+                ui.label(format!("{:.2}", expense.amount));
+                ui.end_row();
+            }
+        });
 }
 
-// Function to create a bar chart (you'll need to define this based on your needs)
-fn create_bar_chart(file_path: &str, data: &HashMap<String, f32>) -> Result<(), Box<dyn Error>> {
-    let root = BitMapBackend::new(file_path, (640, 480)).into_drawing_area();
-    root.fill(&WHITE)?;
+fn calendar_cell(
+    ui: &mut egui::Ui,
+    day: &CalendarDay,
+    busiest_day: f32,
+    selected: bool,
+) -> egui::Response {
+    let (rect, response) = ui.allocate_exact_size(CALENDAR_CELL, egui::Sense::click());
+    let painter = ui.painter_at(rect);
+    let intensity = calendar::intensity(day.spent, busiest_day);
+    painter.rect_filled(rect, 2.0, ui.visuals().faint_bg_color);
+    painter.rect_filled(rect, 2.0, charts::heat_color(intensity));
+    let outline = if selected {
+        egui::Stroke::new(2.0, Color32::BLUE)
+    } else {
+        egui::Stroke::new(1.0, ui.visuals().weak_text_color())
+    };
+    painter.rect_stroke(rect, 2.0, outline);
+
+    let font = egui::FontId::proportional(11.0);
+    let mut pos = rect.left_top() + egui::vec2(4.0, 2.0);
+    let mut line = |text: String, color: Color32| {
+        painter.text(pos, egui::Align2::LEFT_TOP, text, font.clone(), color);
+        pos.y += 14.0;
+    };
+    let text_color = ui.visuals().text_color();
+    line(day.date.day().to_string(), text_color);
+    if day.spent > 0.0 {
+        line(format!("-{:.2}", day.spent), text_color);
+    }
+    if day.credits > 0.0 {
+        line(format!("+{:.2}", day.credits), Color32::DARK_GREEN);
+    }
+    if day.scheduled > 0.0 {
+        line(format!("due {:.2}", day.scheduled), Color32::BLUE);
+    }
 
-    let mut sorted_data: Vec<(&String, &f32)> = data.iter().collect();
-    sorted_data.sort_by_key(|&(month, _)| month);
+    response.on_hover_text(format!(
+        "{}\nSpent: {:.2}\nRefunds/credits: {:.2}\nScheduled: {:.2}",
+        day.date.format("%A %-d %B"),
+        day.spent,
+        day.credits,
+        day.scheduled
+    ))
+}
 
-    let categories: Vec<String> = sorted_data
+fn render_net_worth(ui: &mut egui::Ui, app: &mut MyApp) {
+    let today = chrono::Local::now().date_naive();
+    let mut asset_to_delete: Option<i32> = None;
+
+    let current = app.net_worth(today);
+    ui.strong(format!("Net worth: {:.2}", current.net_worth()));
+    ui.label(format!(
+        "Assets {:.2} less debts {:.2}",
+        current.assets, current.liabilities
+    ));
+
+    egui::Grid::new("assets_table")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("Asset");
+            ui.label("Value");
+            ui.end_row();
+            for asset in &app.assets {
+                ui.label(&asset.name);
+                ui.label(format!("{:.2}", asset.value));
+                ui.push_id(("asset", asset.id), |ui| {
+                    if ui.button("Delete").clicked() {
+                        asset_to_delete = Some(asset.id);
+                    }
+                });
+                ui.end_row();
+            }
+        });
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut app.asset_form.name).hint_text("e.g. Checking account"),
+        );
+        ui.add(
+            egui::TextEdit::singleline(&mut app.asset_form.value)
+                .hint_text("value")
+                .desired_width(80.0),
+        );
+        if ui
+            .button("Save Asset")
+            .on_hover_text("An existing name updates that asset's value")
+            .clicked()
+        {
+            app.save_asset_from_form();
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "{} snapshots saved; one is taken each month.",
+            app.net_worth_snapshots.len()
+        ));
+        if ui.button("Record Snapshot").clicked() {
+            app.record_net_worth(today);
+        }
+    });
+    if let Some(asset_id) = asset_to_delete {
+        app.delete_asset(asset_id);
+    }
+}
+
+// The two strategies side by side, then the month-by-month schedule of the selected one
+fn render_payoff_plans(
+    ui: &mut egui::Ui,
+    app: &mut MyApp,
+    states: &[DebtState],
+    plans: &[PayoffPlan; 2],
+) {
+    egui::Grid::new("payoff_comparison")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("Strategy");
+            ui.label("Debt Free");
+            ui.label("Total Interest");
+            ui.label("Total Paid");
+            for state in states {
+                ui.label(format!("{} Paid Off", state.name));
+            }
+            ui.end_row();
+            for plan in plans {
+                ui.label(plan.strategy.label());
+                ui.label(plan.debt_free_month().unwrap_or("Not within 50 years"));
+                ui.label(format!("{:.2}", plan.total_interest));
+                ui.label(format!("{:.2}", plan.total_paid));
+                for payoff in &plan.payoff_months {
+                    ui.label(payoff.as_deref().unwrap_or("-"));
+                }
+                ui.end_row();
+            }
+        });
+
+    // The shorter plan stays at zero once it is done
+    let longer = plans.iter().max_by_key(|plan| plan.months.len()).unwrap();
+    let remaining = |plan: &PayoffPlan, i: usize| {
+        plan.months
+            .get(i)
+            .map(|month| month.remaining())
+            .unwrap_or(0.0)
+    };
+    let points: Vec<(String, [f32; 2])> = longer
+        .months
         .iter()
-        .map(|(month, _)| (*month).clone())
+        .enumerate()
+        .map(|(i, month)| {
+            (
+                month.month.clone(),
+                [remaining(&plans[0], i), remaining(&plans[1], i)],
+            )
+        })
         .collect();
-    let values: Vec<f32> = sorted_data.iter().map(|(_, &value)| value).collect();
-
-    let max_value = values.iter().fold(f32::MIN, |a, &b| a.max(b));
-
-    let mut chart = ChartBuilder::on(&root)
-        .caption("Monthly Spending", ("sans-serif", 40).into_font())
-        .x_label_area_size(50)
-        .y_label_area_size(50)
-        .build_cartesian_2d(0..categories.len(), 0.0..max_value)?;
-
-    chart
-        .configure_mesh()
-        .x_labels(categories.len())
-        .x_label_formatter(&|x| {
-            if *x < categories.len() {
-                categories[*x].clone()
-            } else {
-                "".to_string()
+    charts::paired_line_chart(
+        ui,
+        "Balance Remaining",
+        [Strategy::Avalanche.label(), Strategy::Snowball.label()],
+        &points,
+    );
+
+    ui.horizontal(|ui| {
+        ui.label("Schedule:");
+        for strategy in [Strategy::Avalanche, Strategy::Snowball] {
+            ui.selectable_value(&mut app.debt_strategy, strategy, strategy.label());
+        }
+    });
+    let plan = if app.debt_strategy == Strategy::Avalanche {
+        &plans[0]
+    } else {
+        &plans[1]
+    };
+    egui::ScrollArea::vertical()
+        .id_source("payoff_schedule")
+        .max_height(300.0)
+        .show(ui, |ui| {
+            egui::Grid::new("payoff_schedule_table")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Month");
+                    ui.label("Payment");
+                    ui.label("Interest");
+                    for state in states {
+                        ui.label(&state.name);
+                    }
+                    ui.label("Remaining");
+                    ui.end_row();
+                    for month in &plan.months {
+                        ui.label(&month.month);
+                        ui.label(format!("{:.2}", month.payment));
+                        ui.label(format!("{:.2}", month.interest));
+                        for balance in &month.balances {
+                            ui.label(format!("{:.2}", balance));
+                        }
+                        ui.label(format!("{:.2}", month.remaining()));
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+pub fn render_payees_ui(ui: &mut egui::Ui, app: &mut MyApp) {
+    let mut alias_to_delete: Option<i32> = None;
+
+    egui::Grid::new("payees_table")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("Payee");
+            ui.label("Aliases");
+            ui.end_row();
+            for payee in &app.payees {
+                ui.label(&payee.name);
+                ui.horizontal_wrapped(|ui| {
+                    for alias in &payee.aliases {
+                        ui.push_id(("alias", alias.id), |ui| {
+                            ui.label(&alias.pattern);
+                            if ui.small_button("x").on_hover_text("Remove alias").clicked() {
+                                alias_to_delete = Some(alias.id);
+                            }
+                        });
+                    }
+                });
+                ui.end_row();
             }
-        })
-        .draw()?;
+        });
 
-    chart.draw_series(
-        values
-            .iter()
-            .enumerate()
-            .map(|(idx, &value)| Rectangle::new([(idx, 0.0), (idx + 1, value)], BLUE.filled())),
-    )?;
+    ui.separator();
+    let mut add_payee_clicked = false;
+    let mut add_alias_clicked = false;
+    let mut merge_clicked = false;
+    ui.horizontal(|ui| {
+        ui.label("New payee:");
+        ui.text_edit_singleline(&mut app.payee_form.new_name);
+        add_payee_clicked = ui.button("Add Payee").clicked();
+    });
+    ui.horizontal(|ui| {
+        ui.label("Alias:");
+        ui.add(
+            egui::TextEdit::singleline(&mut app.payee_form.new_alias).hint_text("e.g. AMZN MKTP"),
+        );
+        ui.label("for");
+        payee_combo(
+            ui,
+            "alias_payee",
+            &app.payees,
+            &mut app.payee_form.alias_payee_id,
+        );
+        add_alias_clicked = ui.button("Add Alias").clicked();
+    });
+    ui.horizontal(|ui| {
+        ui.label("Merge");
+        payee_combo(
+            ui,
+            "merge_source",
+            &app.payees,
+            &mut app.payee_form.merge_source_id,
+        );
+        ui.label("into");
+        payee_combo(
+            ui,
+            "merge_target",
+            &app.payees,
+            &mut app.payee_form.merge_target_id,
+        );
+        merge_clicked = ui.button("Merge").clicked();
+    });
+    let remap_clicked = ui
+        .button("Match Existing Expenses")
+        .on_hover_text("Assign payees to existing expenses using the current aliases")
+        .clicked();
+
+    ui.separator();
+    ui.label("Spending by payee");
+    let mut payee_totals: Vec<(String, f32)> = app.calculate_payee_totals().into_iter().collect();
+    payee_totals.sort_by(|a, b| b.1.total_cmp(&a.1));
+    egui::Grid::new("payee_totals")
+        .striped(true)
+        .show(ui, |ui| {
+            for (name, total) in payee_totals {
+                ui.label(name);
+                ui.label(format!("${:.2}", total));
+                ui.end_row();
+            }
+        });
 
-    root.present()?;
-    Ok(())
+    if add_payee_clicked {
+        app.add_payee_from_form();
+    }
+    if add_alias_clicked {
+        app.add_alias_from_form();
+    }
+    if merge_clicked {
+        app.merge_payees_from_form();
+    }
+    if remap_clicked {
+        app.remap_payees();
+    }
+    if let Some(id) = alias_to_delete {
+        app.delete_payee_alias(id);
+    }
 }
 
-fn calculate_yearly_comparison(expenses: &[Expense]) -> HashMap<String, f32> {
-    let mut yearly_totals = HashMap::new();
-    for expense in expenses {
-        let date = NaiveDate::parse_from_str(&expense.date, "%Y-%m-%d").unwrap();
-        let year = date.format("%Y").to_string(); // Extract only the year as a string
-        *yearly_totals.entry(year).or_insert(0.0) += expense.amount;
+fn change_text(old: &str, new: &str) -> String {
+    if old == new {
+        old.to_string()
+    } else {
+        format!("{} -> {}", old, new)
     }
-    yearly_totals
 }
 
-// Function to create a line graph (you'll need to define this based on your needs)
-fn create_line_graph(file_path: &str, data: &HashMap<String, f32>) -> Result<(), Box<dyn Error>> {
-    let root = BitMapBackend::new(file_path, (640, 480)).into_drawing_area();
-    root.fill(&WHITE)?;
+fn render_receipt_suggestion(ui: &mut egui::Ui, app: &mut MyApp) {
+    let Some(suggestion) = &app.receipt_suggestion else {
+        return;
+    };
+    let mut use_clicked = false;
+    let mut dismiss_clicked = false;
+
+    egui::Frame::group(ui.style()).show(ui, |ui| {
+        ui.label("Suggested from receipt:");
+        egui::Grid::new("receipt_suggestion").show(ui, |ui| {
+            suggestion_row(ui, "Name", &suggestion.merchant);
+            let total = suggestion.total.as_ref().map(|total| ReceiptField {
+                value: format!("{:.2}", total.value),
+                confidence: total.confidence,
+            });
+            suggestion_row(ui, "Amount", &total);
+            suggestion_row(ui, "Date", &suggestion.date);
+        });
+        ui.horizontal(|ui| {
+            use_clicked = ui.button("Use suggestion").clicked();
+            dismiss_clicked = ui.button("Dismiss").clicked();
+        });
+    });
 
-    // Sort the data by year
-    let mut sorted_data: Vec<(&String, &f32)> = data.iter().collect();
-    sorted_data.sort_by_key(|&(year, _)| year);
+    if use_clicked {
+        app.apply_receipt_suggestion();
+    } else if dismiss_clicked {
+        app.receipt_suggestion = None;
+    }
+}
 
-    let years: Vec<String> = sorted_data
+fn suggestion_row(ui: &mut egui::Ui, label: &str, field: &Option<ReceiptField<String>>) {
+    ui.label(label);
+    match field {
+        Some(field) => {
+            ui.label(&field.value);
+            let color = if field.confidence >= 0.75 {
+                Color32::DARK_GREEN
+            } else if field.confidence >= 0.5 {
+                Color32::from_rgb(200, 120, 0)
+            } else {
+                Color32::RED
+            };
+            ui.colored_label(color, format!("{:.0}% confident", field.confidence * 100.0));
+        }
+        None => {
+            ui.label("-");
+            ui.label("not found");
+        }
+    }
+    ui.end_row();
+}
+
+pub fn render_receipts_window(app: &mut MyApp, ctx: &egui::Context) {
+    let Some(expense_id) = app.receipts_expense_id else {
+        return;
+    };
+    let title = app
+        .expenses
         .iter()
-        .map(|(year, _)| (*year).clone())
+        .find(|expense| expense.id == expense_id)
+        .map(|expense| format!("Receipts for {}", expense.description))
+        .unwrap_or_else(|| "Receipts".to_string());
+
+    let mut open = true;
+    let mut attach_clicked = false;
+    let mut receipt_to_preview: Option<i32> = None;
+    let mut receipt_to_remove: Option<i32> = None;
+
+    egui::Window::new(title)
+        .id(egui::Id::new("receipts_window"))
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("File path:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut app.receipt_path)
+                        .hint_text("/path/to/receipt.jpg or .pdf"),
+                );
+                if ui.button("Attach").clicked() {
+                    attach_clicked = true;
+                }
+            });
+
+            if app.receipts.is_empty() {
+                ui.label("No receipts attached yet.");
+            }
+
+            ui.horizontal_wrapped(|ui| {
+                for (attachment, thumbnail) in &app.receipts {
+                    ui.vertical(|ui| {
+                        match thumbnail {
+                            Some(texture) => {
+                                ui.image(texture);
+                            }
+                            None => {
+                                ui.label(format!("[{}]", attachment.mime_type));
+                            }
+                        }
+                        ui.label(&attachment.file_name);
+                        ui.push_id(attachment.id, |ui| {
+                            ui.horizontal(|ui| {
+                                if attachments::is_image(attachment) {
+                                    if ui.button("View").clicked() {
+                                        receipt_to_preview = Some(attachment.id);
+                                    }
+                                } else if let Some(dir) = &app.attachments_dir {
+                                    ui.label(
+                                        attachments::stored_path(dir, attachment)
+                                            .display()
+                                            .to_string(),
+                                    );
+                                }
+                                if ui.button("Remove").clicked() {
+                                    receipt_to_remove = Some(attachment.id);
+                                }
+                            });
+                        });
+                    });
+                }
+            });
+
+            if let Some(texture) = &app.receipt_preview {
+                ui.separator();
+                egui::ScrollArea::both().max_height(400.0).show(ui, |ui| {
+                    ui.image(texture);
+                });
+            }
+        });
+
+    if attach_clicked {
+        app.attach_receipt();
+    }
+    if let Some(id) = receipt_to_preview {
+        app.preview_receipt(id);
+    }
+    if let Some(id) = receipt_to_remove {
+        app.remove_receipt(id);
+    }
+    if !open {
+        app.close_receipts();
+    }
+}
+
+// Drives every chart in the analytics section
+fn render_period_selector(ui: &mut egui::Ui, app: &mut MyApp) {
+    let today = chrono::Local::now().date_naive();
+    ui.horizontal(|ui| {
+        ui.label("Period:");
+        let bounded = app.period != Period::AllTime;
+        if ui.add_enabled(bounded, egui::Button::new("<")).clicked() {
+            app.period = app.period.previous();
+        }
+        egui::ComboBox::from_id_source("analytics_period")
+            .selected_text(app.period.label())
+            .show_ui(ui, |ui| {
+                let presets = [
+                    ("All time", Period::AllTime),
+                    ("This month", Period::this_month(today)),
+                    ("Last month", Period::last_month(today)),
+                    ("This quarter", Period::this_quarter(today)),
+                    ("Year to date", Period::year_to_date(today)),
+                ];
+                for (name, period) in presets {
+                    ui.selectable_value(&mut app.period, period, name);
+                }
+            });
+        if ui.add_enabled(bounded, egui::Button::new(">")).clicked() {
+            app.period = app.period.next();
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("From:");
+        ui.add(egui::TextEdit::singleline(&mut app.period_form.from).desired_width(90.0));
+        ui.label("To:");
+        ui.add(egui::TextEdit::singleline(&mut app.period_form.to).desired_width(90.0));
+        if ui.button("Apply Range").clicked() {
+            app.apply_custom_period();
+        }
+        ui.separator();
+        ui.label("Last");
+        ui.add(egui::TextEdit::singleline(&mut app.period_form.rolling_days).desired_width(40.0));
+        ui.label("days");
+        if ui.button("Apply").clicked() {
+            app.apply_rolling_period(today);
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Statement PDF:");
+        ui.add(egui::TextEdit::singleline(&mut app.statement_path).hint_text("statement.pdf"));
+        if ui.button("Save Statement").clicked() {
+            app.save_statement(today);
+        }
+    });
+}
+
+// Selected period next to its baseline, as a table sorted by the largest change and a grouped chart
+fn render_period_comparison(ui: &mut egui::Ui, app: &mut MyApp) {
+    ui.horizontal(|ui| {
+        ui.label("Compare with:");
+        for baseline in [Baseline::PreviousPeriod, Baseline::SamePeriodLastYear] {
+            ui.selectable_value(&mut app.comparison.baseline, baseline, baseline.label());
+        }
+        ui.separator();
+        ui.label("Group by:");
+        for group_by in [GroupBy::Category, GroupBy::PaymentMethod] {
+            ui.selectable_value(&mut app.comparison.group_by, group_by, group_by.label());
+        }
+    });
+
+    let Some((baseline, rows)) = app.comparison_rows() else {
+        ui.label("Pick a period above to compare it with an earlier one.");
+        return;
+    };
+    let current_label = app.period.label();
+    let baseline_label = baseline.label();
+
+    egui::Grid::new("period_comparison")
+        .num_columns(5)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label(app.comparison.group_by.label());
+            ui.label(&baseline_label);
+            ui.label(&current_label);
+            ui.label("Change");
+            ui.label("Change %");
+            ui.end_row();
+            for row in &rows {
+                ui.label(&row.key);
+                ui.label(format!("{:.2}", row.previous));
+                ui.label(format!("{:.2}", row.current));
+                ui.label(format!("{:+.2}", row.change()));
+                ui.label(row.percent_label());
+                ui.end_row();
+            }
+        });
+
+    let groups: Vec<(String, [f32; 2])> = rows
+        .iter()
+        .map(|row| (row.key.clone(), [row.previous, row.current]))
         .collect();
-    let values: Vec<f32> = sorted_data.iter().map(|(_, &value)| value).collect();
-
-    let max_value = values.iter().fold(f32::MIN, |a, &b| a.max(b));
-
-    let mut chart = ChartBuilder::on(&root)
-        .caption("Yearly Spending Comparison", ("sans-serif", 40).into_font())
-        .x_label_area_size(30)
-        .y_label_area_size(30)
-        .build_cartesian_2d(0..years.len(), 0.0..max_value)?;
-
-    chart
-        .configure_mesh()
-        .x_labels(years.len())
-        .x_label_formatter(&|x| {
-            if *x < years.len() {
-                years[*x].clone()
-            } else {
-                "".to_string()
+    let title = format!("{} vs {}", current_label, baseline_label);
+    charts::grouped_bar_chart(ui, &title, [&baseline_label, &current_label], &groups);
+}
+
+fn render_spending_patterns(ui: &mut egui::Ui, app: &mut MyApp) {
+    ui.horizontal(|ui| {
+        ui.label("View:");
+        for view in [PatternView::HourOfDay, PatternView::WeekOfYear] {
+            ui.selectable_value(&mut app.pattern_view, view, view.label());
+        }
+    });
+
+    let expenses = app.drilled_expenses();
+    let period = app.period.label();
+    let summary = patterns::summarize(&expenses);
+    let weekdays: Vec<String> = patterns::WEEKDAYS
+        .iter()
+        .map(|weekday| weekday.to_string())
+        .collect();
+    let row_labels: Vec<&str> = weekdays.iter().map(String::as_str).collect();
+    let heatmap = match app.pattern_view {
+        PatternView::HourOfDay => patterns::hour_heatmap(&expenses),
+        PatternView::WeekOfYear => patterns::week_heatmap(&expenses),
+    };
+    let title = format!("Spending by {} ({})", app.pattern_view.label(), period);
+    if app.pattern_view == PatternView::HourOfDay && summary.timed_expenses == 0 {
+        ui.label(egui::RichText::new(&title).strong());
+        ui.label("No expenses have a time yet. Add one with the Time field, HH:MM.");
+    } else {
+        charts::heatmap(ui, &title, &row_labels, &heatmap.columns, &heatmap.cells);
+    }
+
+    egui::Grid::new("weekday_averages")
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("Weekday");
+            ui.label("Average per Day");
+            ui.label("Total");
+            ui.end_row();
+            for (weekday, average) in weekdays.iter().zip(&summary.weekday_averages) {
+                ui.label(weekday);
+                ui.label(format!("{:.2}", average.average));
+                ui.label(format!("{:.2}", average.total));
+                ui.end_row();
             }
-        })
-        .draw()?;
+        });
+    ui.label(format!(
+        "Weekend share of spending: {:.1}%",
+        summary.weekend_share * 100.0
+    ));
+    match summary.late_night_share {
+        Some(share) => ui.label(format!(
+            "Late-night ({:02}:00-{:02}:00) share of timed spending: {:.1}% ({} of {} expenses have a time)",
+            patterns::LATE_NIGHT_START,
+            patterns::LATE_NIGHT_END,
+            share * 100.0,
+            summary.timed_expenses,
+            summary.expenses
+        )),
+        None => ui.label("Late-night spending needs expenses with a time."),
+    };
+}
 
-    // Draw line
-    chart.draw_series(LineSeries::new(
-        values.iter().enumerate().map(|(idx, &value)| (idx, value)),
-        &RED,
-    ))?;
+fn render_forecast_table(ui: &mut egui::Ui, forecast: &MonthForecast) {
+    ui.label(egui::RichText::new(format!("Month-end projection for {}", forecast.month)).strong());
+    egui::Grid::new("month_forecast")
+        .num_columns(5)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("Category");
+            ui.label("Spent");
+            ui.label("Recurring Due");
+            ui.label("Projected");
+            ui.label("Likely Range");
+            ui.end_row();
+            for category in &forecast.categories {
+                ui.label(&category.category);
+                ui.label(format!("{:.2}", category.spent));
+                ui.label(format!("{:.2}", category.upcoming_recurring));
+                ui.label(format!("{:.2}", category.projected));
+                ui.label(format!("{:.2} - {:.2}", category.low, category.high));
+                ui.end_row();
+            }
+            ui.strong("Total");
+            ui.strong(format!("{:.2}", forecast.spent));
+            ui.label("");
+            ui.strong(format!("{:.2}", forecast.projected));
+            ui.strong(format!("{:.2} - {:.2}", forecast.low, forecast.high));
+            ui.end_row();
+        });
+}
 
-    root.present()?;
-    Ok(())
+// "All expenses > Food > 2024-03"; every level but the last can be clicked to go back up
+fn render_drill_down_breadcrumbs(ui: &mut egui::Ui, app: &mut MyApp) {
+    if app.drill_down.is_empty() {
+        return;
+    }
+    let mut go_to_depth = None;
+    let root = match app.period {
+        Period::AllTime => "All expenses".to_string(),
+        _ => app.period.label(),
+    };
+    ui.horizontal(|ui| {
+        if ui.link(root).clicked() {
+            go_to_depth = Some(0);
+        }
+        let last = app.drill_down.len() - 1;
+        for (depth, level) in app.drill_down.iter().enumerate() {
+            ui.label(">");
+            if depth == last {
+                ui.strong(level.label());
+            } else if ui.link(level.label()).clicked() {
+                go_to_depth = Some(depth + 1);
+            }
+        }
+    });
+    if let Some(depth) = go_to_depth {
+        app.drill_up_to(depth);
+    }
 }
 
-// Function to load and display a chart in the UI
-pub fn load_and_display_chart(ui: &mut egui::Ui, file_path: &str) {
-    if let Ok((image_data, image_size)) = load_image_to_memory(file_path) {
-        let texture = load_texture_from_memory(
-            ui.ctx(),
-            &image_data,
-            [image_size[0] as usize, image_size[1] as usize],
-            file_path.to_owned(),
-        );
+pub fn calculate_category_totals(expenses: &[Expense]) -> HashMap<String, f32> {
+    let mut category_totals = HashMap::new();
+    for expense in expenses {
+        *category_totals
+            .entry(expense.category.clone())
+            .or_insert(0.0) += expense.amount;
+    }
+    category_totals
+}
 
-        // Create an Image widget with the texture ID and size
-        let image_widget = egui::Image::new(&texture);
+// Rows whose date is too short to hold a YYYY-MM prefix are skipped instead of panicking
+pub fn calculate_monthly_trends(expenses: &[Expense]) -> HashMap<String, f32> {
+    let mut monthly_totals = HashMap::new();
+    for expense in expenses {
+        let Some(month) = expense.date.get(..7) else {
+            continue;
+        };
+        *monthly_totals.entry(month.to_string()).or_insert(0.0) += expense.amount;
+    }
+    monthly_totals
+}
 
-        // Add the image widget to the UI
-        ui.add(image_widget);
+fn calculate_yearly_comparison(expenses: &[Expense]) -> HashMap<String, f32> {
+    let mut yearly_totals = HashMap::new();
+    for expense in expenses {
+        let Ok(date) = NaiveDate::parse_from_str(&expense.date, "%Y-%m-%d") else {
+            continue;
+        };
+        let year = date.format("%Y").to_string(); // Extract only the year as a string
+        *yearly_totals.entry(year).or_insert(0.0) += expense.amount;
     }
+    yearly_totals
 }
@@ -0,0 +1,567 @@
+use crate::app::load_image_to_memory;
+use crate::attachments;
+use crate::classifier::CategoryClassifier;
+use crate::import_export;
+use crate::models::{
+    Asset, Attachment, CategoryRule, Debt, DueCharges, Expense, Goal, MyError, NetWorthSnapshot,
+    Payee, RecurringSchedule, ReviewStatus, User,
+};
+use crate::receipts::{self, ReceiptParser, ReceiptSuggestion};
+use crate::statement::{self, Statement};
+#[cfg(target_arch = "wasm32")]
+use crate::storage::HttpStore;
+use crate::storage::{ExpenseStore, UserData};
+use crate::ui::load_texture_from_memory;
+use eframe::egui;
+#[cfg(target_arch = "wasm32")]
+use std::collections::VecDeque;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+// Everything that may touch the disk, the network or bcrypt goes through the worker
+pub enum Job {
+    Authenticate {
+        username: String,
+        password: String,
+    },
+    Register {
+        username: String,
+        password: String,
+    },
+    LoadExpenses,
+    // Payees, reviews and schedules, plus the user's rules, goals, debts and net worth
+    LoadUserData(Option<i32>),
+    AddExpense {
+        expense: Expense,
+        receipt_path: Option<PathBuf>,
+    },
+    DeleteExpense(i32),
+    UpdateExpenses(Vec<Expense>),
+    Import {
+        path: PathBuf,
+        rules: Vec<CategoryRule>,
+        payees: Vec<Payee>,
+    },
+    ScanReceipt(PathBuf),
+    SaveStatement {
+        statement: Statement,
+        path: PathBuf,
+    },
+    // Receipts of one expense, with their thumbnails ready to draw
+    LoadReceipts(i32),
+    AttachReceipt {
+        expense_id: i32,
+        path: PathBuf,
+    },
+    RemoveReceipt(Attachment),
+    PreviewReceipt(Attachment),
+    // Each change below answers with the list it changed, read back from the store
+    SetAnomalyReview {
+        expense_id: i32,
+        status: ReviewStatus,
+    },
+    AddSchedule(RecurringSchedule),
+    DeleteSchedule(i32),
+    RecordDueCharges(Vec<DueCharges>),
+    AddPayee(String),
+    AddPayeeAlias {
+        payee_id: i32,
+        pattern: String,
+    },
+    DeletePayeeAlias(i32),
+    MergePayees {
+        source_id: i32,
+        target_id: i32,
+    },
+    AddRule(CategoryRule),
+    DeleteRule {
+        rule_id: i32,
+        user_id: i32,
+    },
+    SwapRules(CategoryRule, CategoryRule),
+    AddGoal(Goal),
+    DeleteGoal {
+        goal_id: i32,
+        user_id: i32,
+    },
+    AddDebt(Debt),
+    DeleteDebt {
+        debt_id: i32,
+        user_id: i32,
+    },
+    SaveAsset(Asset),
+    DeleteAsset {
+        asset_id: i32,
+        user_id: i32,
+    },
+    RecordNetWorth {
+        user_id: i32,
+        snapshot: NetWorthSnapshot,
+    },
+    Logout,
+}
+
+pub enum JobResult {
+    Authenticated(Result<Option<User>, MyError>),
+    Registered(Result<(), MyError>),
+    ExpensesLoaded(Result<Vec<Expense>, MyError>),
+    UserDataLoaded(Result<UserData, MyError>),
+    ExpenseAdded(Result<Expense, MyError>),
+    ExpenseDeleted(Result<i32, MyError>),
+    ExpensesUpdated(Result<Vec<Expense>, MyError>),
+    Imported(Result<Vec<Expense>, MyError>),
+    ReceiptScanned(Result<ReceiptSuggestion, MyError>),
+    StatementSaved {
+        label: String,
+        path: PathBuf,
+        result: Result<(), MyError>,
+    },
+    ReceiptsLoaded {
+        expense_id: i32,
+        receipts: Result<Vec<(Attachment, Option<egui::TextureHandle>)>, MyError>,
+    },
+    ReceiptAttached(Result<i32, MyError>), // the expense id
+    ReceiptRemoved(Result<i32, MyError>),  // the expense id
+    ReceiptPreviewed {
+        attachment_id: i32,
+        texture: Result<egui::TextureHandle, MyError>,
+    },
+    AnomalyReviewed {
+        expense_id: i32,
+        status: ReviewStatus,
+        result: Result<(), MyError>,
+    },
+    ScheduleAdded(Result<Vec<RecurringSchedule>, MyError>),
+    ScheduleDeleted(Result<Vec<RecurringSchedule>, MyError>),
+    // The new expenses and the schedules moved on past them
+    DueChargesRecorded(Result<(Vec<Expense>, Vec<RecurringSchedule>), MyError>),
+    PayeeAdded(Result<(i32, Vec<Payee>), MyError>), // the new payee's id
+    PayeeAliasAdded(Result<Vec<Payee>, MyError>),
+    PayeeAliasDeleted(Result<Vec<Payee>, MyError>),
+    PayeesMerged(Result<Vec<Payee>, MyError>),
+    RuleAdded(Result<Vec<CategoryRule>, MyError>),
+    RuleDeleted(Result<Vec<CategoryRule>, MyError>),
+    RulesSwapped(Result<Vec<CategoryRule>, MyError>),
+    GoalAdded(Result<Vec<Goal>, MyError>),
+    GoalDeleted(Result<Vec<Goal>, MyError>),
+    DebtAdded(Result<Vec<Debt>, MyError>),
+    DebtDeleted(Result<Vec<Debt>, MyError>),
+    AssetSaved(Result<Vec<Asset>, MyError>),
+    AssetDeleted(Result<Vec<Asset>, MyError>),
+    NetWorthRecorded(Result<Vec<NetWorthSnapshot>, MyError>),
+    LoggedOut,
+}
+
+// Runs jobs one at a time on a background thread so eframe::App::update never waits on them.
+// Results come back in the order the jobs were submitted.
+pub struct Worker {
+    #[cfg(not(target_arch = "wasm32"))]
+    jobs: Sender<Job>,
+    #[cfg(target_arch = "wasm32")]
+    web: WebJobs,
+    results: Receiver<JobResult>,
+    pending: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Worker {
+    // The worker owns the store; egui_ctx is woken up whenever a result is ready
+    pub fn spawn(mut store: Box<dyn ExpenseStore>, egui_ctx: egui::Context) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (result_sender, results) = mpsc::channel();
+        thread::spawn(move || {
+            // Parsers don't need to be Send when they are created on the worker thread
+            let parsers = receipts::default_parsers();
+            for job in job_receiver {
+                let result = run_job(store.as_mut(), &parsers, &egui_ctx, job);
+                if result_sender.send(result).is_err() {
+                    break; // The app has gone away
+                }
+                egui_ctx.request_repaint();
+            }
+        });
+        Worker {
+            jobs,
+            results,
+            pending: 0,
+        }
+    }
+
+    pub fn submit(&mut self, job: Job) {
+        if self.jobs.send(job).is_ok() {
+            self.pending += 1;
+        }
+    }
+
+    // Blocks until the next result arrives, or returns None if nothing is pending
+    pub fn wait(&mut self) -> Option<JobResult> {
+        if self.pending == 0 {
+            return None;
+        }
+        let result = self.results.recv().ok()?;
+        self.pending -= 1;
+        Some(result)
+    }
+}
+
+// A browser tab has no threads to spare, so requests go out with fetch and everything else runs
+// on the page between frames. Jobs still wait for the one before them to finish.
+#[cfg(target_arch = "wasm32")]
+impl Worker {
+    pub fn in_browser(store: HttpStore, egui_ctx: egui::Context) -> Self {
+        let (result_sender, results) = mpsc::channel();
+        Worker {
+            web: WebJobs {
+                store,
+                parsers: receipts::default_parsers(),
+                egui_ctx,
+                results: result_sender,
+                queue: VecDeque::new(),
+                running: false,
+            },
+            results,
+            pending: 0,
+        }
+    }
+
+    pub fn submit(&mut self, job: Job) {
+        self.web.queue.push_back(job);
+        self.pending += 1;
+        self.web.start_next();
+    }
+}
+
+impl Worker {
+    pub fn is_busy(&self) -> bool {
+        self.pending > 0
+    }
+
+    // Never blocks; returns whatever has finished so far
+    pub fn poll(&mut self) -> Vec<JobResult> {
+        let finished: Vec<JobResult> = self.results.try_iter().collect();
+        self.pending -= finished.len();
+        #[cfg(target_arch = "wasm32")]
+        if !finished.is_empty() {
+            self.web.running = false;
+            self.web.start_next();
+        }
+        finished
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+struct WebJobs {
+    store: HttpStore,
+    parsers: Vec<Box<dyn ReceiptParser>>,
+    egui_ctx: egui::Context,
+    results: Sender<JobResult>,
+    queue: VecDeque<Job>,
+    running: bool, // until poll picks up its result
+}
+
+#[cfg(target_arch = "wasm32")]
+impl WebJobs {
+    fn start_next(&mut self) {
+        if self.running {
+            return;
+        }
+        let Some(job) = self.queue.pop_front() else {
+            return;
+        };
+        self.running = true;
+        let results = self.results.clone();
+        let egui_ctx = self.egui_ctx.clone();
+        let finish = move |result: JobResult| {
+            let _ = results.send(result);
+            egui_ctx.request_repaint();
+        };
+        let store = &self.store;
+        match job {
+            Job::Authenticate { username, password } => {
+                store.authenticate_then(&username, &password, move |result| {
+                    finish(JobResult::Authenticated(result))
+                })
+            }
+            Job::Register { username, password } => {
+                let user = User {
+                    id: 0,
+                    username,
+                    password_hash: String::new(), // The server hashes the password
+                };
+                store.add_user_then(&user, &password, move |result| {
+                    finish(JobResult::Registered(result))
+                })
+            }
+            Job::LoadExpenses => {
+                store.get_expenses_then(move |result| finish(JobResult::ExpensesLoaded(result)))
+            }
+            // A server keeps no receipts, so only the expense is sent
+            Job::AddExpense { mut expense, .. } => {
+                store.add_expense_then(&expense.clone(), move |result| {
+                    finish(JobResult::ExpenseAdded(result.map(|expense_id| {
+                        expense.id = expense_id;
+                        expense
+                    })))
+                })
+            }
+            Job::DeleteExpense(expense_id) => store
+                .delete_expense_then(expense_id, move |result| {
+                    finish(JobResult::ExpenseDeleted(result.map(|_| expense_id)))
+                }),
+            Job::UpdateExpenses(expenses) => store.update_expenses_then(expenses, move |result| {
+                finish(JobResult::ExpensesUpdated(result))
+            }),
+            Job::Logout => store.logout_then(move || finish(JobResult::LoggedOut)),
+            job => finish(run_job(&mut self.store, &self.parsers, &self.egui_ctx, job)),
+        }
+    }
+}
+
+fn run_job(
+    store: &mut dyn ExpenseStore,
+    parsers: &[Box<dyn ReceiptParser>],
+    egui_ctx: &egui::Context,
+    job: Job,
+) -> JobResult {
+    match job {
+        Job::Authenticate { username, password } => {
+            JobResult::Authenticated(store.authenticate(&username, &password))
+        }
+        Job::Register { username, password } => {
+            JobResult::Registered(register(store, &username, &password))
+        }
+        Job::LoadExpenses => JobResult::ExpensesLoaded(store.get_expenses()),
+        Job::LoadUserData(user_id) => JobResult::UserDataLoaded(store.load_user_data(user_id)),
+        Job::AddExpense {
+            mut expense,
+            receipt_path,
+        } => JobResult::ExpenseAdded(store.add_expense(&expense).map(|expense_id| {
+            expense.id = expense_id;
+            if let Some(path) = receipt_path {
+                if let Err(e) = store.attach_receipt(expense_id, &path) {
+                    eprintln!("Failed to attach scanned receipt: {:?}", e);
+                }
+            }
+            expense
+        })),
+        Job::DeleteExpense(expense_id) => {
+            JobResult::ExpenseDeleted(store.delete_expense(expense_id).map(|_| expense_id))
+        }
+        Job::UpdateExpenses(expenses) => JobResult::ExpensesUpdated(
+            expenses
+                .iter()
+                .try_for_each(|expense| store.update_expense(expense))
+                .map(|_| expenses),
+        ),
+        Job::Import {
+            path,
+            rules,
+            payees,
+        } => JobResult::Imported(import(store, &path, &rules, &payees)),
+        Job::ScanReceipt(path) => {
+            JobResult::ReceiptScanned(receipts::parse_receipt(parsers, &path))
+        }
+        Job::SaveStatement { statement, path } => JobResult::StatementSaved {
+            label: statement.period.label(),
+            result: statement::write_statement(&statement, &path),
+            path,
+        },
+        Job::LoadReceipts(expense_id) => JobResult::ReceiptsLoaded {
+            expense_id,
+            receipts: load_receipts(store, egui_ctx, expense_id),
+        },
+        Job::AttachReceipt { expense_id, path } => {
+            JobResult::ReceiptAttached(store.attach_receipt(expense_id, &path).map(|_| expense_id))
+        }
+        Job::RemoveReceipt(attachment) => JobResult::ReceiptRemoved(
+            store
+                .remove_attachment(&attachment)
+                .map(|_| attachment.expense_id),
+        ),
+        Job::PreviewReceipt(attachment) => JobResult::ReceiptPreviewed {
+            attachment_id: attachment.id,
+            texture: preview_receipt(store, egui_ctx, &attachment),
+        },
+        Job::SetAnomalyReview { expense_id, status } => JobResult::AnomalyReviewed {
+            expense_id,
+            status,
+            result: store.set_anomaly_review(expense_id, status),
+        },
+        Job::AddSchedule(schedule) => JobResult::ScheduleAdded(
+            store
+                .add_recurring_schedule(&schedule)
+                .and_then(|_| store.get_recurring_schedules()),
+        ),
+        Job::DeleteSchedule(schedule_id) => JobResult::ScheduleDeleted(
+            store
+                .delete_recurring_schedule(schedule_id)
+                .and_then(|_| store.get_recurring_schedules()),
+        ),
+        Job::RecordDueCharges(charges) => {
+            JobResult::DueChargesRecorded(store.record_due_charges(&charges).and_then(|ids| {
+                let expenses = charges
+                    .into_iter()
+                    .flat_map(|charge| charge.expenses)
+                    .zip(ids)
+                    .map(|(expense, id)| Expense { id, ..expense })
+                    .collect();
+                Ok((expenses, store.get_recurring_schedules()?))
+            }))
+        }
+        Job::AddPayee(name) => JobResult::PayeeAdded(
+            store
+                .add_payee(&name)
+                .and_then(|payee_id| Ok((payee_id, store.get_payees()?))),
+        ),
+        Job::AddPayeeAlias { payee_id, pattern } => JobResult::PayeeAliasAdded(
+            store
+                .add_payee_alias(payee_id, &pattern)
+                .and_then(|_| store.get_payees()),
+        ),
+        Job::DeletePayeeAlias(alias_id) => JobResult::PayeeAliasDeleted(
+            store
+                .delete_payee_alias(alias_id)
+                .and_then(|_| store.get_payees()),
+        ),
+        Job::MergePayees {
+            source_id,
+            target_id,
+        } => JobResult::PayeesMerged(
+            store
+                .merge_payees(source_id, target_id)
+                .and_then(|_| store.get_payees()),
+        ),
+        Job::AddRule(rule) => JobResult::RuleAdded(
+            store
+                .add_rule(&rule)
+                .and_then(|_| store.get_rules(rule.user_id)),
+        ),
+        Job::DeleteRule { rule_id, user_id } => JobResult::RuleDeleted(
+            store
+                .delete_rule(rule_id)
+                .and_then(|_| store.get_rules(user_id)),
+        ),
+        Job::SwapRules(first, second) => JobResult::RulesSwapped(
+            store
+                .swap_rule_positions(&first, &second)
+                .and_then(|_| store.get_rules(first.user_id)),
+        ),
+        Job::AddGoal(goal) => JobResult::GoalAdded(
+            store
+                .add_goal(&goal)
+                .and_then(|_| store.get_goals(goal.user_id)),
+        ),
+        Job::DeleteGoal { goal_id, user_id } => JobResult::GoalDeleted(
+            store
+                .delete_goal(goal_id)
+                .and_then(|_| store.get_goals(user_id)),
+        ),
+        Job::AddDebt(debt) => JobResult::DebtAdded(
+            store
+                .add_debt(&debt)
+                .and_then(|_| store.get_debts(debt.user_id)),
+        ),
+        Job::DeleteDebt { debt_id, user_id } => JobResult::DebtDeleted(
+            store
+                .delete_debt(debt_id)
+                .and_then(|_| store.get_debts(user_id)),
+        ),
+        Job::SaveAsset(asset) => JobResult::AssetSaved(
+            store
+                .save_asset(&asset)
+                .and_then(|_| store.get_assets(asset.user_id)),
+        ),
+        Job::DeleteAsset { asset_id, user_id } => JobResult::AssetDeleted(
+            store
+                .delete_asset(asset_id)
+                .and_then(|_| store.get_assets(user_id)),
+        ),
+        Job::RecordNetWorth { user_id, snapshot } => JobResult::NetWorthRecorded(
+            store
+                .save_net_worth_snapshot(user_id, &snapshot)
+                .and_then(|_| store.get_net_worth_snapshots(user_id)),
+        ),
+        Job::Logout => {
+            store.logout();
+            JobResult::LoggedOut
+        }
+    }
+}
+
+fn register(store: &mut dyn ExpenseStore, username: &str, password: &str) -> Result<(), MyError> {
+    if !store.is_username_unique(username)? {
+        return Err(MyError::InvalidInput("Username already exists".to_string()));
+    }
+    let user = User {
+        id: 0,
+        username: username.to_string(),
+        password_hash: String::new(), // This will be set in add_user
+    };
+    store.add_user(&user, password)
+}
+
+// Categorizes like the CLI import, against the store's own history
+fn import(
+    store: &mut dyn ExpenseStore,
+    path: &Path,
+    rules: &[CategoryRule],
+    payees: &[Payee],
+) -> Result<Vec<Expense>, MyError> {
+    let mut expenses = import_export::read_expenses_csv(File::open(path)?)?;
+    let classifier = CategoryClassifier::train(&store.get_expenses()?);
+    import_export::categorize_imported(&mut expenses, rules, payees, &classifier);
+    let ids = store.add_expenses(&expenses)?;
+    for (expense, id) in expenses.iter_mut().zip(ids) {
+        expense.id = id;
+    }
+    Ok(expenses)
+}
+
+// Decoding thumbnails is the slow part, so the textures are made here rather than in a frame
+fn load_receipts(
+    store: &dyn ExpenseStore,
+    egui_ctx: &egui::Context,
+    expense_id: i32,
+) -> Result<Vec<(Attachment, Option<egui::TextureHandle>)>, MyError> {
+    let receipts = store
+        .get_attachments(expense_id)?
+        .into_iter()
+        .map(|attachment| {
+            let texture = attachment
+                .thumbnail
+                .as_deref()
+                .and_then(|png_bytes| attachments::decode_thumbnail(png_bytes).ok())
+                .map(|(image_data, size)| {
+                    load_texture_from_memory(
+                        egui_ctx,
+                        &image_data,
+                        size,
+                        format!("receipt_thumbnail_{}", attachment.id),
+                    )
+                });
+            (attachment, texture)
+        })
+        .collect();
+    Ok(receipts)
+}
+
+fn preview_receipt(
+    store: &dyn ExpenseStore,
+    egui_ctx: &egui::Context,
+    attachment: &Attachment,
+) -> Result<egui::TextureHandle, MyError> {
+    let dir = store.attachments_dir().ok_or_else(|| {
+        MyError::InvalidInput("receipts are only kept in the local database".to_string())
+    })?;
+    let path = attachments::stored_path(&dir, attachment);
+    let (image_data, image_size) = load_image_to_memory(&path.to_string_lossy())?;
+    Ok(load_texture_from_memory(
+        egui_ctx,
+        &image_data,
+        [image_size[0] as usize, image_size[1] as usize],
+        format!("receipt_preview_{}", attachment.id),
+    ))
+}
@@ -0,0 +1,151 @@
+use expense_tracker::anomalies::{self, AnomalyBasis};
+use expense_tracker::models::{self, Expense, Payee, ReviewStatus};
+use std::collections::HashMap;
+
+fn create_expense(
+    id: i32,
+    date: &str,
+    amount: f32,
+    category: &str,
+    payee_id: Option<i32>,
+) -> Expense {
+    Expense {
+        id,
+        date: date.to_string(),
+        amount,
+        category: category.to_string(),
+        description: format!("Charge {}", id),
+        payment_method: "Card".to_string(),
+        tags: String::new(),
+        payee_id,
+        time: None,
+    }
+}
+
+fn create_payee(id: i32, name: &str) -> Payee {
+    Payee {
+        id,
+        name: name.to_string(),
+        aliases: Vec::new(),
+    }
+}
+
+// A few months of meals out between 20 and 30, then one large bill
+fn restaurant_history() -> Vec<Expense> {
+    let amounts = [22.0, 25.0, 28.0, 24.0, 30.0, 26.0, 135.0];
+    amounts
+        .iter()
+        .enumerate()
+        .map(|(index, amount)| {
+            let date = format!("2024-0{}-10", index + 1);
+            create_expense(index as i32 + 1, &date, *amount, "Dining", None)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_jump_on_a_flat_subscription_is_flagged_against_the_payee() {
+        let payees = vec![create_payee(7, "Netflix")];
+        let mut expenses: Vec<Expense> = (1..=4)
+            .map(|month| {
+                let date = format!("2024-0{}-03", month);
+                create_expense(month, &date, 15.0, "Entertainment", Some(7))
+            })
+            .collect();
+        expenses.push(create_expense(
+            5,
+            "2024-05-03",
+            30.0,
+            "Entertainment",
+            Some(7),
+        ));
+        // Cheaper than usual never counts as unusual
+        expenses.push(create_expense(
+            6,
+            "2024-06-03",
+            5.0,
+            "Entertainment",
+            Some(7),
+        ));
+
+        let anomalies = anomalies::detect_anomalies(&expenses, &payees, &HashMap::new());
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].expense_id, 5);
+        assert_eq!(
+            anomalies[0].basis,
+            AnomalyBasis::Payee("Netflix".to_string())
+        );
+        assert_eq!(anomalies[0].reason(), "2.0x the usual 15.00 at Netflix");
+    }
+
+    #[test]
+    fn test_category_history_is_used_without_a_payee() {
+        let expenses = restaurant_history();
+        let anomalies = anomalies::detect_anomalies(&expenses, &[], &HashMap::new());
+
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].expense_id, 7);
+        assert_eq!(
+            anomalies[0].basis,
+            AnomalyBasis::Category("Dining".to_string())
+        );
+        assert_eq!(anomalies[0].typical, 25.5);
+
+        // Too little history to judge anything
+        let anomalies = anomalies::detect_anomalies(&expenses[4..], &[], &HashMap::new());
+        assert!(anomalies.is_empty());
+    }
+
+    #[test]
+    fn test_confirmed_charges_stay_out_of_the_baseline() {
+        let payees = vec![create_payee(3, "Corner Cafe")];
+        let expenses: Vec<Expense> = [10.0, 12.0, 14.0, 40.0, 23.0]
+            .iter()
+            .enumerate()
+            .map(|(index, amount)| {
+                let date = format!("2024-0{}-10", index + 1);
+                create_expense(index as i32 + 1, &date, *amount, "Dining", Some(3))
+            })
+            .collect();
+        let flagged = |reviews: &HashMap<i32, ReviewStatus>| -> Vec<i32> {
+            anomalies::detect_anomalies(&expenses, &payees, reviews)
+                .iter()
+                .map(|anomaly| anomaly.expense_id)
+                .collect()
+        };
+
+        // A dismissed charge widens what counts as usual; a confirmed one doesn't
+        assert_eq!(
+            flagged(&HashMap::from([(4, ReviewStatus::Dismissed)])),
+            vec![4]
+        );
+        assert_eq!(
+            flagged(&HashMap::from([(4, ReviewStatus::Confirmed)])),
+            vec![4, 5]
+        );
+    }
+
+    #[test]
+    fn test_reviews_are_stored_and_removed_with_the_expense() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn =
+            models::open_database(dir.path().join("anomalies.db").to_str().unwrap()).unwrap();
+        models::create_expense_table(&conn).unwrap();
+
+        let expense = create_expense(0, "2024-01-10", 135.0, "Dining", None);
+        let expense_id = models::add_expense(&conn, &expense).unwrap();
+        models::set_anomaly_review(&conn, expense_id, ReviewStatus::Dismissed).unwrap();
+        models::set_anomaly_review(&conn, expense_id, ReviewStatus::Confirmed).unwrap();
+        assert_eq!(
+            models::get_anomaly_reviews(&conn).unwrap(),
+            HashMap::from([(expense_id, ReviewStatus::Confirmed)])
+        );
+
+        models::delete_expense(&conn, expense_id).unwrap();
+        assert!(models::get_anomaly_reviews(&conn).unwrap().is_empty());
+    }
+}
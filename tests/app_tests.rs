@@ -1,34 +1,35 @@
-use expense_tracker::models;
-use expense_tracker::models::Expense;
+mod common;
+
+use chrono::NaiveDate;
+use eframe::egui;
+use expense_tracker::app::{DrillFilter, LOCAL_ONLY_MESSAGE};
+use expense_tracker::classifier::CategoryClassifier;
+use expense_tracker::models::{self, Cadence, Expense, Payee, PayeeAlias, RecurringSchedule, User};
+use expense_tracker::periods::Period;
+use expense_tracker::receipts;
+use expense_tracker::storage::SqliteStore;
 use expense_tracker::ui::MyApp;
 
-fn create_test_app() -> MyApp {
-    MyApp {
-        expense_name: String::new(),
-        expense_amount: String::new(),
-        payment_method: String::new(),
-        category: String::new(),
-        expenses: Vec::new(),
-        expense_date: String::new(),
-        warning_message: None,
-        current_user_id: None,
-        username: String::new(),
-        password: String::new(),
-        is_logged_in: false,
-        current_user: None,
-        new_username: String::new(),
-        new_password: String::new(),
-        showing_signup: false,
-        image_texture: None,
-        show_monthly_trends: false,
-        show_yearly_comparison: false,
-        show_monthly_spending: false,
-    }
+// Re-reads the store so tests can check what was actually saved
+fn reload(app: &mut MyApp) -> usize {
+    app.expenses.clear();
+    app.load_expenses();
+    app.wait_for_worker();
+    app.expenses.len()
+}
+
+fn fill_expense_form(app: &mut MyApp, name: &str, amount: &str, category: &str) {
+    app.expense_name = name.to_string();
+    app.expense_amount = amount.to_string();
+    app.expense_date = "2023-01-01".to_string();
+    app.category = category.to_string();
+    app.payment_method = "Cash".to_string();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use common::create_test_app;
 
     #[test]
     fn test_new_app_has_no_expenses() {
@@ -39,24 +40,52 @@ mod tests {
     #[test]
     fn test_adding_expense_increases_count() {
         let mut app = create_test_app();
-        app.expense_name = "Test Expense".to_string();
-        app.expense_amount = "100.0".to_string();
-        app.expense_date = "2023-01-01".to_string();
-        app.category = "Food".to_string();
-        app.payment_method = "Cash".to_string();
+        fill_expense_form(&mut app, "Test Expense", "100.0", "Food");
 
-        // Mock the functionality that adds the expense to the database
-        // For this example, we'll just add it to the expenses vector
-        app.expenses.push(Expense {
-            id: 1,
-            date: app.expense_date.clone(),
-            amount: app.expense_amount.parse::<f32>().unwrap(),
-            category: app.category.clone(),
-            description: app.expense_name.clone(),
-            payment_method: app.payment_method.clone(),
-        });
+        app.add_expense_to_db();
+        assert!(app.expense_name.is_empty());
+        assert!(app.worker.is_busy());
+        app.wait_for_worker();
 
         assert_eq!(app.expenses.len(), 1);
+        assert_eq!(app.expenses[0].amount, 100.0);
+        assert_eq!(reload(&mut app), 1);
+    }
+
+    #[test]
+    fn test_expense_without_category_is_not_added() {
+        let mut app = create_test_app();
+        fill_expense_form(&mut app, "Mystery", "5", "");
+
+        app.submit_expense_form();
+
+        assert!(app.warning_message.is_some());
+        assert_eq!(reload(&mut app), 0);
+    }
+
+    #[test]
+    fn test_expense_time_is_optional_but_must_be_valid() {
+        let mut app = create_test_app();
+        fill_expense_form(&mut app, "Late snack", "8.5", "Food");
+        app.expense_time = "25:10".to_string();
+        app.submit_expense_form();
+        assert_eq!(
+            app.warning_message.as_deref(),
+            Some("Enter the time as HH:MM")
+        );
+        assert_eq!(reload(&mut app), 0);
+
+        app.expense_time = "23:40".to_string();
+        app.submit_expense_form();
+        app.wait_for_worker();
+        assert!(app.expense_time.is_empty());
+        assert_eq!(app.expenses[0].time.as_deref(), Some("23:40"));
+
+        fill_expense_form(&mut app, "Groceries", "40", "Food");
+        app.submit_expense_form();
+        app.wait_for_worker();
+        assert_eq!(reload(&mut app), 2);
+        assert!(app.expenses.iter().any(|expense| expense.time.is_none()));
     }
 
     #[test]
@@ -70,6 +99,9 @@ mod tests {
                 category: "Food".to_string(),
                 description: "Groceries".to_string(),
                 payment_method: "Cash".to_string(),
+                tags: String::new(),
+                payee_id: None,
+                time: None,
             },
             Expense {
                 id: 2,
@@ -78,6 +110,9 @@ mod tests {
                 category: "Food".to_string(),
                 description: "Restaurant".to_string(),
                 payment_method: "Card".to_string(),
+                tags: String::new(),
+                payee_id: None,
+                time: None,
             },
         ];
 
@@ -93,6 +128,7 @@ mod tests {
         app.new_username = "newuser2".to_string();
         app.new_password = "newpass123!".to_string(); // Assuming this meets your password criteria
         app.process_signup();
+        app.wait_for_worker();
 
         // Check the warning message for successful registration
         assert_eq!(
@@ -100,11 +136,18 @@ mod tests {
             Some("User successfully registered!".to_string())
         );
 
-        // Cleanup: Remove the test user from the database
-        // This assumes you have a function to delete a user and it returns a Result type
-        if let Err(e) = models::delete_user("newuser2") {
-            eprintln!("Failed to clean up test user: {}", e);
-        }
+        app.process_signup();
+        app.wait_for_worker();
+        assert_eq!(
+            app.warning_message,
+            Some("Username already exists".to_string())
+        );
+
+        app.process_login("newuser2", "newpass123!");
+        assert!(!app.is_logged_in);
+        app.wait_for_worker();
+        assert!(app.is_logged_in);
+        assert_eq!(app.current_user.as_ref().unwrap().username, "newuser2");
     }
 
     #[test]
@@ -129,32 +172,374 @@ mod tests {
     #[test]
     fn test_delete_expense() {
         let mut app = create_test_app();
-        app.expenses = vec![
-            Expense {
-                id: 1,
-                date: "2023-01-01".to_string(),
-                amount: 100.0,
-                category: "Food".to_string(),
-                description: "Groceries".to_string(),
-                payment_method: "Cash".to_string(),
-            },
-            Expense {
-                id: 2,
-                date: "2023-01-02".to_string(),
-                amount: 150.0,
-                category: "Food".to_string(),
-                description: "Restaurant".to_string(),
-                payment_method: "Card".to_string(),
-            },
-        ];
+        fill_expense_form(&mut app, "Groceries", "100", "Food");
+        app.add_expense_to_db();
+        fill_expense_form(&mut app, "Restaurant", "150", "Food");
+        app.add_expense_to_db();
+        app.wait_for_worker();
+        let expense_id_to_delete = app.expenses[0].id;
 
-        // Simulate deleting an expense
-        let expense_id_to_delete = 1;
-        app.expenses.retain(|e| e.id != expense_id_to_delete);
+        app.delete_expense_from_db(expense_id_to_delete);
+        app.wait_for_worker();
 
         assert_eq!(app.expenses.len(), 1);
         assert!(app.expenses.iter().all(|e| e.id != expense_id_to_delete));
+        assert_eq!(reload(&mut app), 1);
     }
 
-    // Add more tests to cover other functionalities like login, signup, delete_expense_from_db, etc.
+    #[test]
+    fn test_import_csv_in_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv_path = dir.path().join("import.csv");
+        std::fs::write(
+            &csv_path,
+            "date,amount,category,description\n2023-02-01,9.99,Entertainment,Streaming\n2023-02-03,30,,Unknown shop\n",
+        )
+        .unwrap();
+        let mut app = create_test_app();
+        app.import_path = csv_path.to_str().unwrap().to_string();
+
+        app.import_csv();
+        app.wait_for_worker();
+
+        assert_eq!(app.warning_message, Some("Imported 2 expenses".to_string()));
+        assert!(app.import_path.is_empty());
+        assert_eq!(app.expenses[1].category, "Miscellaneous");
+        assert_eq!(reload(&mut app), 2);
+    }
+
+    #[test]
+    fn test_drill_down_from_category_to_month_and_back() {
+        let mut app = create_test_app();
+        for (name, amount, category, date) in [
+            ("Groceries", "40", "Food", "2024-03-02"),
+            ("Bakery", "5", "Food", "2024-04-10"),
+            ("Bus", "3", "Transportation", "2024-03-05"),
+        ] {
+            fill_expense_form(&mut app, name, amount, category);
+            app.expense_date = date.to_string();
+            app.add_expense_to_db();
+        }
+        app.wait_for_worker();
+
+        app.drill_into(DrillFilter::Category("Food".to_string()));
+        assert_eq!(app.drilled_expenses().len(), 2);
+        app.drill_into(DrillFilter::Month("2024-03".to_string()));
+        let drilled = app.drilled_expenses();
+        assert_eq!(drilled.len(), 1);
+        assert_eq!(drilled[0].description, "Groceries");
+
+        // Picking another month replaces the month level rather than stacking on it
+        app.drill_into(DrillFilter::Month("2024-04".to_string()));
+        assert_eq!(app.drill_down.len(), 2);
+        assert_eq!(app.drilled_expenses()[0].description, "Bakery");
+
+        app.drill_up_to(1);
+        assert_eq!(
+            app.drill_down,
+            vec![DrillFilter::Category("Food".to_string())]
+        );
+        app.drill_up_to(0);
+        assert_eq!(app.drilled_expenses().len(), 3);
+    }
+
+    #[test]
+    fn test_period_limits_analytics_but_not_the_list() {
+        let mut app = create_test_app();
+        for (name, date) in [
+            ("January rent", "2024-01-01"),
+            ("February rent", "2024-02-01"),
+        ] {
+            fill_expense_form(&mut app, name, "800", "Housing and Utilities");
+            app.expense_date = date.to_string();
+            app.add_expense_to_db();
+        }
+        app.wait_for_worker();
+
+        app.period_form.from = "2024-02-01".to_string();
+        app.period_form.to = "2024-01-01".to_string();
+        app.apply_custom_period();
+        assert_eq!(app.period, Period::AllTime);
+        assert!(app.warning_message.is_some());
+
+        app.period_form.to = "2024-02-29".to_string();
+        app.apply_custom_period();
+        let drilled = app.drilled_expenses();
+        assert_eq!(drilled.len(), 1);
+        assert_eq!(drilled[0].description, "February rent");
+        assert_eq!(app.period.previous().label(), "2024-01-03 to 2024-01-31");
+
+        app.period_form.rolling_days = "zero".to_string();
+        app.apply_rolling_period(chrono::NaiveDate::from_ymd_opt(2024, 1, 10).unwrap());
+        assert!(app.warning_message.is_some());
+        app.period_form.rolling_days = "10".to_string();
+        app.apply_rolling_period(chrono::NaiveDate::from_ymd_opt(2024, 1, 10).unwrap());
+        assert_eq!(app.drilled_expenses()[0].description, "January rent");
+        assert_eq!(app.expenses.len(), 2);
+
+        // Rolling periods compare against the stretch just before them
+        app.period_form.rolling_days = "31".to_string();
+        app.apply_rolling_period(chrono::NaiveDate::from_ymd_opt(2024, 2, 29).unwrap());
+        let (baseline, rows) = app.comparison_rows().unwrap();
+        assert_eq!(baseline.label(), "31 days to 2024-01-29");
+        assert_eq!((rows[0].current, rows[0].previous), (800.0, 800.0));
+        app.period = Period::AllTime;
+        assert!(app.comparison_rows().is_none());
+
+        let today = chrono::NaiveDate::from_ymd_opt(2024, 2, 15).unwrap();
+        let forecast = app.month_forecast(today).unwrap();
+        assert_eq!(forecast.spent, 800.0);
+        let projected = app.projected_months(today);
+        assert_eq!(projected.len(), 4);
+        assert_eq!(projected[0].month, "2024-02");
+        assert_eq!(projected[3].month, "2024-05");
+        app.drill_into(DrillFilter::Month("2024-01".to_string()));
+        assert!(app.month_forecast(today).is_none());
+        assert!(app.projected_months(today).is_empty());
+    }
+
+    #[test]
+    fn test_calendar_steps_between_months_and_lists_a_day() {
+        let mut app = create_test_app();
+        fill_expense_form(&mut app, "Lunch", "12.5", "Food");
+        app.add_expense_to_db();
+        app.wait_for_worker();
+
+        let today = NaiveDate::from_ymd_opt(2023, 2, 14).unwrap();
+        assert_eq!(app.calendar(today).first_day.to_string(), "2023-02-01");
+        app.step_calendar(false, today);
+        let january = app.calendar(today);
+        assert_eq!(january.first_day.to_string(), "2023-01-01");
+        assert_eq!(january.days[0].spent, 12.5);
+
+        app.calendar_day = NaiveDate::from_ymd_opt(2023, 1, 1);
+        assert_eq!(app.calendar_day_expenses().len(), 1);
+        app.step_calendar(false, today);
+        assert_eq!(app.calendar_month, NaiveDate::from_ymd_opt(2022, 12, 1));
+        assert!(app.calendar_day_expenses().is_empty());
+    }
+
+    #[test]
+    fn test_apply_receipt_suggestion_prefills_form() {
+        let mut app = create_test_app();
+        app.receipt_suggestion = Some(receipts::suggest_from_text(
+            "Corner Cafe\n2023-05-01\nTotal 12.5",
+        ));
+        // "12.5" has no cents so only the date and merchant should be found
+        app.apply_receipt_suggestion();
+
+        assert_eq!(app.expense_name, "Corner Cafe");
+        assert_eq!(app.expense_date, "2023-05-01");
+        assert!(app.expense_amount.is_empty());
+        assert!(app.receipt_suggestion.is_none());
+    }
+
+    #[test]
+    fn test_server_mode_refuses_receipts() {
+        let mut app = create_test_app();
+        app.server_mode = true;
+        app.scan_receipt_path = "receipt.pdf".to_string();
+        app.scan_receipt();
+        assert_eq!(app.warning_message.as_deref(), Some(LOCAL_ONLY_MESSAGE));
+
+        app.warning_message = None;
+        app.open_receipts(1);
+        assert!(app.receipts_expense_id.is_none());
+        assert_eq!(app.warning_message.as_deref(), Some(LOCAL_ONLY_MESSAGE));
+    }
+
+    #[test]
+    fn test_statement_and_receipt_scan_run_in_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = create_test_app();
+        fill_expense_form(&mut app, "Lunch", "12.5", "Food");
+        app.add_expense_to_db();
+        app.wait_for_worker();
+
+        let path = dir.path().join("statement.pdf");
+        app.statement_path = path.to_str().unwrap().to_string();
+        app.save_statement(NaiveDate::from_ymd_opt(2023, 1, 31).unwrap());
+        assert!(app.worker.is_busy());
+        app.wait_for_worker();
+        assert!(path.exists());
+        assert!(app
+            .warning_message
+            .as_deref()
+            .unwrap()
+            .starts_with("Saved the"));
+
+        app.scan_receipt_path = dir.path().join("missing.pdf").display().to_string();
+        app.scan_receipt();
+        app.wait_for_worker();
+        assert!(app.receipt_suggestion.is_none());
+        assert!(app
+            .warning_message
+            .as_deref()
+            .unwrap()
+            .starts_with("Failed to read receipt"));
+    }
+
+    #[test]
+    fn test_remap_payees_saves_through_the_store() {
+        let mut app = create_test_app();
+        fill_expense_form(&mut app, "UBER *TRIP", "9", "Transportation");
+        app.add_expense_to_db();
+        app.wait_for_worker();
+        assert_eq!(app.expenses[0].payee_id, None);
+
+        app.payees = vec![Payee {
+            id: 7,
+            name: "Uber".to_string(),
+            aliases: vec![PayeeAlias {
+                id: 1,
+                payee_id: 7,
+                pattern: "uber".to_string(),
+            }],
+        }];
+        app.remap_payees();
+        app.wait_for_worker();
+        reload(&mut app);
+        assert_eq!(app.expenses[0].payee_id, Some(7));
+    }
+
+    #[test]
+    fn test_local_features_save_through_the_worker() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SqliteStore::open(dir.path().join("app.db").to_str().unwrap()).unwrap();
+        let conn = store.connection();
+        let user = User {
+            id: 0,
+            username: "saver".to_string(),
+            password_hash: String::new(),
+        };
+        models::add_user(conn, &user, "secret1!").unwrap();
+        let schedule = RecurringSchedule {
+            id: 0,
+            description: "Gym".to_string(),
+            amount: 30.0,
+            category: "Health".to_string(),
+            payment_method: "Card".to_string(),
+            payee_id: None,
+            cadence: Cadence::Monthly,
+            next_date: "2024-01-15".to_string(),
+            anchor_date: "2024-01-15".to_string(),
+        };
+        models::add_recurring_schedule(conn, &schedule).unwrap();
+        let mut app = MyApp::with_store(&egui::Context::default(), Box::new(store));
+        app.process_login("saver", "secret1!");
+        app.wait_for_worker();
+        assert!(app.is_logged_in);
+
+        app.payee_form.new_name = "Corner Shop".to_string();
+        app.add_payee_from_form();
+        // Nothing changes until the worker answers
+        assert!(app.payees.is_empty());
+        app.wait_for_worker();
+        assert_eq!(app.payees.len(), 1);
+        assert!(app.payee_form.new_name.is_empty());
+        assert_eq!(app.payee_form.alias_payee_id, Some(app.payees[0].id));
+
+        let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        app.goal_form.name = "Holiday".to_string();
+        app.goal_form.target_amount = "500".to_string();
+        app.goal_form.target_date = "2099-01-01".to_string();
+        app.add_goal_from_form(today);
+        app.wait_for_worker();
+        assert_eq!(app.goals.len(), 1);
+        assert!(app.goal_form.name.is_empty());
+        app.delete_goal(app.goals[0].id);
+        app.wait_for_worker();
+        assert!(app.goals.is_empty());
+
+        // Saving an asset also records the day's net worth
+        app.asset_form.name = "Bank".to_string();
+        app.asset_form.value = "1200".to_string();
+        app.save_asset_from_form();
+        app.wait_for_worker();
+        assert_eq!(app.assets.len(), 1);
+        assert_eq!(app.net_worth_snapshots.len(), 1);
+        assert_eq!(app.net_worth_snapshots[0].assets, 1200.0);
+        app.asset_form.name = "bank".to_string();
+        app.asset_form.value = "900".to_string();
+        app.save_asset_from_form();
+        app.wait_for_worker();
+        assert_eq!(app.assets.len(), 1);
+        assert_eq!(app.assets[0].value, 900.0);
+
+        // Due charges are saved together with the schedule moving on
+        assert_eq!(app.due_schedule_count(today), 2);
+        app.record_due_schedules(today);
+        app.wait_for_worker();
+        assert_eq!(app.expenses.len(), 2);
+        assert_eq!(app.schedules[0].next_date, "2024-03-15");
+        assert_eq!(app.due_schedule_count(today), 0);
+        assert_eq!(
+            app.warning_message,
+            Some("Recorded 2 scheduled charges".to_string())
+        );
+        assert_eq!(reload(&mut app), 2);
+    }
+
+    #[test]
+    fn test_local_features_explain_a_store_without_them() {
+        let mut app = create_test_app();
+        app.payee_form.new_name = "Corner Shop".to_string();
+        app.add_payee_from_form();
+        app.wait_for_worker();
+        assert!(app.payees.is_empty());
+        assert_eq!(
+            app.warning_message,
+            Some("Failed to add payee: payees are only kept in the local database".to_string())
+        );
+        assert_eq!(app.payee_form.new_name, "Corner Shop");
+    }
+
+    #[test]
+    fn test_rule_form_from_expense() {
+        let mut app = create_test_app();
+        app.expenses = vec![Expense {
+            id: 7,
+            date: "2023-01-01".to_string(),
+            amount: 45.0,
+            category: "Transportation".to_string(),
+            description: "SHELL OIL 5734".to_string(),
+            payment_method: "Card".to_string(),
+            tags: "car".to_string(),
+            payee_id: None,
+            time: None,
+        }];
+
+        app.rule_form_from_expense(7);
+        assert_eq!(app.rule_form.description_pattern, "SHELL OIL 5734");
+        assert_eq!(app.rule_form.category, "Transportation");
+        assert_eq!(app.rule_form.payment_method, "Card");
+        assert_eq!(app.rule_form.tags, "car");
+    }
+
+    #[test]
+    fn test_suggest_category_respects_manual_pick() {
+        let mut app = create_test_app();
+        app.expenses = vec![Expense {
+            id: 1,
+            date: "2023-01-01".to_string(),
+            amount: 4.5,
+            category: "Food".to_string(),
+            description: "Starbucks Coffee".to_string(),
+            payment_method: "Card".to_string(),
+            tags: String::new(),
+            payee_id: None,
+            time: None,
+        }];
+        app.classifier = CategoryClassifier::train(&app.expenses);
+
+        app.expense_name = "Starbucks".to_string();
+        app.suggest_category();
+        assert_eq!(app.category, "Food");
+        assert!(app.category_suggested);
+
+        app.category = "Entertainment and Leisure".to_string();
+        app.category_suggested = false;
+        app.suggest_category();
+        assert_eq!(app.category, "Entertainment and Leisure");
+    }
 }
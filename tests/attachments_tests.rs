@@ -0,0 +1,80 @@
+use expense_tracker::attachments;
+use expense_tracker::models::{self, Attachment};
+use std::path::Path;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mime_type_for_supported_files() {
+        assert_eq!(
+            attachments::mime_type_for(Path::new("receipt.JPG")),
+            Some("image/jpeg")
+        );
+        assert_eq!(
+            attachments::mime_type_for(Path::new("invoice.pdf")),
+            Some("application/pdf")
+        );
+        assert_eq!(attachments::mime_type_for(Path::new("notes.txt")), None);
+    }
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        let first = attachments::content_hash(b"receipt");
+        let second = attachments::content_hash(b"receipt");
+        assert_eq!(first, second);
+        assert_eq!(first.len(), 64);
+        assert_ne!(first, attachments::content_hash(b"other receipt"));
+    }
+
+    #[test]
+    fn test_thumbnail_fits_within_bounds() {
+        // Encode a large solid image and check the thumbnail is scaled down
+        let image = image::RgbaImage::from_pixel(800, 400, image::Rgba([200, 10, 10, 255]));
+        let mut png_bytes = Vec::new();
+        image::DynamicImage::ImageRgba8(image)
+            .write_to(
+                &mut std::io::Cursor::new(&mut png_bytes),
+                image::ImageOutputFormat::Png,
+            )
+            .unwrap();
+
+        let thumbnail = attachments::create_thumbnail(&png_bytes).unwrap();
+        let (pixels, size) = attachments::decode_thumbnail(&thumbnail).unwrap();
+        assert_eq!(size, [160, 80]);
+        assert_eq!(pixels.len(), 160 * 80 * 4);
+    }
+
+    #[test]
+    fn test_stored_path_uses_content_hash() {
+        let attachment = Attachment {
+            id: 1,
+            expense_id: 1,
+            file_name: "scan.pdf".to_string(),
+            mime_type: "application/pdf".to_string(),
+            content_hash: "abc123".to_string(),
+            thumbnail: None,
+        };
+        assert_eq!(
+            attachments::stored_path(Path::new("attachments"), &attachment),
+            Path::new("attachments").join("abc123.pdf")
+        );
+        assert!(!attachments::is_image(&attachment));
+    }
+
+    #[test]
+    fn test_attachments_dir_follows_database_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("expenses.db");
+        assert_eq!(
+            attachments::attachments_dir(&db_path),
+            dir.path().join("attachments")
+        );
+
+        assert_eq!(
+            attachments::attachments_dir(Path::new(models::DEFAULT_DATABASE_PATH)),
+            Path::new("attachments")
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use chrono::NaiveDate;
+use expense_tracker::calendar;
+use expense_tracker::models::{Cadence, Expense, RecurringSchedule};
+
+fn create_expense(id: i32, date: &str, amount: f32) -> Expense {
+    Expense {
+        id,
+        date: date.to_string(),
+        amount,
+        category: "Food".to_string(),
+        description: "Groceries".to_string(),
+        payment_method: "Card".to_string(),
+        tags: String::new(),
+        payee_id: None,
+        time: None,
+    }
+}
+
+fn create_schedule(cadence: Cadence, next_date: &str, amount: f32) -> RecurringSchedule {
+    RecurringSchedule {
+        id: 0,
+        description: "Gym".to_string(),
+        amount,
+        category: "Health and Personal Care".to_string(),
+        payment_method: "Card".to_string(),
+        payee_id: None,
+        cadence,
+        next_date: next_date.to_string(),
+        anchor_date: next_date.to_string(),
+    }
+}
+
+fn date(text: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_days_total_spending_and_credits() {
+        let expenses = vec![
+            create_expense(1, "2024-03-01", 20.0),
+            create_expense(2, "2024-03-01", 5.5),
+            create_expense(3, "2024-03-15", -150.0),
+            create_expense(4, "2024-03-31", 80.0),
+            create_expense(5, "2024-04-01", 999.0),
+        ];
+        let month = calendar::month_calendar(&expenses, &[], date("2024-03-20"));
+
+        assert_eq!(month.first_day, date("2024-03-01"));
+        // 1 March 2024 was a Friday
+        assert_eq!(month.leading_blanks, 4);
+        assert_eq!(month.days.len(), 31);
+        assert_eq!(month.days[0].spent, 25.5);
+        assert_eq!(month.days[0].expense_ids, vec![1, 2]);
+        assert_eq!(month.days[14].credits, 150.0);
+        assert_eq!(month.days[14].spent, 0.0);
+        assert_eq!(month.busiest_day(), 80.0);
+        assert_eq!(month.total_spent(), 105.5);
+        assert_eq!(month.total_credits(), 150.0);
+
+        assert_eq!(calendar::intensity(40.0, month.busiest_day()), 0.5);
+        assert_eq!(calendar::intensity(40.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_scheduled_charges_land_on_their_due_days() {
+        let schedules = vec![
+            create_schedule(Cadence::Weekly, "2024-02-26", 12.0),
+            create_schedule(Cadence::Monthly, "2024-04-10", 9.99),
+        ];
+        let month = calendar::month_calendar(&[], &schedules, date("2024-03-01"));
+
+        let due: Vec<u32> = month
+            .days
+            .iter()
+            .filter(|day| day.scheduled > 0.0)
+            .map(|day| day.date.format("%d").to_string().parse().unwrap())
+            .collect();
+        assert_eq!(due, vec![4, 11, 18, 25]);
+        assert_eq!(month.total_scheduled(), 48.0);
+    }
+}
@@ -0,0 +1,168 @@
+use eframe::egui;
+use eframe::egui::{Pos2, Rect, Vec2};
+use expense_tracker::charts;
+use expense_tracker::models::Expense;
+use expense_tracker::ui;
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+fn totals(entries: &[(&str, f32)]) -> HashMap<String, f32> {
+    entries
+        .iter()
+        .map(|(label, amount)| (label.to_string(), *amount))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pie_slices_are_largest_first_and_cover_the_circle() {
+        let data = totals(&[("Food", 25.0), ("Housing", 75.0), ("Refund", 0.0)]);
+        let slices = charts::pie_slices(&data);
+
+        let labels: Vec<&str> = slices.iter().map(|slice| slice.label.as_str()).collect();
+        assert_eq!(labels, vec!["Housing", "Food"]);
+        assert_eq!(slices[0].share, 0.75);
+        assert_eq!(slices[0].start_angle, 0.0);
+        assert!((slices[1].end_angle - TAU).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_slice_at_follows_the_pointer_clockwise_from_the_top() {
+        let slices = charts::pie_slices(&totals(&[("Housing", 75.0), ("Food", 25.0)]));
+
+        // Right of centre is a quarter turn in, inside Housing; left is past 75%, inside Food
+        assert_eq!(
+            charts::slice_at(&slices, Vec2::new(50.0, 0.0), 100.0),
+            Some(0)
+        );
+        assert_eq!(
+            charts::slice_at(&slices, Vec2::new(-50.0, -1.0), 100.0),
+            Some(1)
+        );
+        assert_eq!(
+            charts::slice_at(&slices, Vec2::new(150.0, 0.0), 100.0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_series_columns_and_tooltips() {
+        let series = charts::sorted_series(&totals(&[("2024-02", 800.0), ("2024-01", 12.5)]));
+        assert_eq!(series[0], ("2024-01".to_string(), 12.5));
+
+        let plot = Rect::from_min_size(Pos2::new(100.0, 0.0), Vec2::new(200.0, 100.0));
+        assert_eq!(charts::column_at(2, plot, 150.0), Some(0));
+        assert_eq!(charts::column_at(2, plot, 250.0), Some(1));
+        assert_eq!(charts::column_at(2, plot, 50.0), None);
+
+        assert_eq!(
+            charts::tooltip_text("2024-01", 12.5, None),
+            "2024-01: 12.50"
+        );
+        assert_eq!(
+            charts::tooltip_text("Food", 25.0, Some(0.25)),
+            "Food: 25.00 (25.0%)"
+        );
+    }
+
+    #[test]
+    fn test_charts_draw_in_a_headless_frame_while_hovered() {
+        let ctx = egui::Context::default();
+        let data = totals(&[("2024-01", 12.5), ("2024-02", 800.0)]);
+        let input = egui::RawInput {
+            events: vec![egui::Event::PointerMoved(Pos2::new(120.0, 200.0))],
+            ..Default::default()
+        };
+        let output = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                charts::pie_chart(ui, "Categories", &data);
+                charts::bar_chart(ui, "Months", &data, &[]);
+                charts::line_chart(ui, "Years", &data);
+                let net_worth = totals(&[("2024-01", -500.0), ("2024-02", 250.0)]);
+                charts::line_chart(ui, "Net Worth", &net_worth);
+                charts::bar_chart(ui, "Nothing", &HashMap::new(), &[]);
+                let projection = [
+                    charts::Projection {
+                        label: "2024-02".to_string(),
+                        expected: 900.0,
+                        low: 800.0,
+                        high: 1000.0,
+                    },
+                    charts::Projection {
+                        label: "2024-03".to_string(),
+                        expected: 850.0,
+                        low: 600.0,
+                        high: 1100.0,
+                    },
+                ];
+                charts::bar_chart(ui, "Projected", &data, &projection);
+                let groups = vec![("Food".to_string(), [80.0, 120.0])];
+                charts::grouped_bar_chart(ui, "Compared", ["Before", "After"], &groups);
+                let balances = vec![
+                    ("2024-02".to_string(), [900.0, 950.0]),
+                    ("2024-03".to_string(), [500.0, 0.0]),
+                ];
+                charts::paired_line_chart(ui, "Balances", ["Avalanche", "Snowball"], &balances);
+            });
+        });
+        assert!(!output.shapes.is_empty());
+    }
+
+    #[test]
+    fn test_clicking_a_pie_slice_returns_its_label() {
+        let ctx = egui::Context::default();
+        let data = totals(&[("Housing", 75.0), ("Food", 25.0)]);
+        // Just right of the pie's centre, inside the larger slice
+        let pointer = Pos2::new(240.0, 210.0);
+        let press = |pressed| egui::Event::PointerButton {
+            pos: pointer,
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::NONE,
+        };
+        let frames = [
+            vec![egui::Event::PointerMoved(pointer)],
+            vec![press(true)],
+            vec![press(false)],
+        ];
+
+        let mut clicked = Vec::new();
+        for events in frames {
+            let input = egui::RawInput {
+                events,
+                ..Default::default()
+            };
+            let _ = ctx.run(input, |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    clicked.push(charts::pie_chart(ui, "Categories", &data));
+                });
+            });
+        }
+        assert_eq!(clicked, vec![None, None, Some("Housing".to_string())]);
+    }
+
+    #[test]
+    fn test_monthly_trends_skip_malformed_dates() {
+        let expense = |date: &str, amount: f32| Expense {
+            id: 0,
+            date: date.to_string(),
+            amount,
+            category: "Food".to_string(),
+            description: "Lunch".to_string(),
+            payment_method: "Card".to_string(),
+            tags: String::new(),
+            payee_id: None,
+            time: None,
+        };
+        let trends = ui::calculate_monthly_trends(&[
+            expense("2024-01-05", 10.0),
+            expense("2024", 5.0),
+            expense("", 5.0),
+            expense("2024-01-20", 2.5),
+        ]);
+        assert_eq!(trends, totals(&[("2024-01", 12.5)]));
+    }
+}
@@ -0,0 +1,75 @@
+use expense_tracker::classifier::{self, CategoryClassifier};
+use expense_tracker::models::Expense;
+
+// Rows are split into "train" and held-out "test" sets so accuracy is measured on unseen data
+fn load_fixture(split: &str) -> Vec<Expense> {
+    include_str!("fixtures/categorized_expenses.csv")
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            if fields.len() != 4 || fields[0] != split {
+                return None;
+            }
+            Some(Expense {
+                id: 0,
+                date: "2023-01-01".to_string(),
+                amount: 0.0,
+                category: fields[3].to_string(),
+                description: fields[1].to_string(),
+                payment_method: fields[2].to_string(),
+                tags: String::new(),
+                payee_id: None,
+                time: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_drops_numbers_and_adds_payment_method() {
+        assert_eq!(
+            classifier::tokenize("SHELL OIL 57442", "Card"),
+            vec!["shell", "oil", "payment:card"]
+        );
+    }
+
+    #[test]
+    fn test_untrained_classifier_has_no_prediction() {
+        let classifier = CategoryClassifier::default();
+        assert!(classifier.predict("Anything", "Cash").is_none());
+    }
+
+    #[test]
+    fn test_held_out_accuracy() {
+        let training = load_fixture("train");
+        let held_out = load_fixture("test");
+        assert!(!training.is_empty() && !held_out.is_empty());
+
+        let classifier = CategoryClassifier::train(&training);
+        let correct = held_out
+            .iter()
+            .filter(|expense| {
+                classifier
+                    .predict(&expense.description, &expense.payment_method)
+                    .map(|(category, _)| category == expense.category)
+                    .unwrap_or(false)
+            })
+            .count();
+
+        let accuracy = correct as f32 / held_out.len() as f32;
+        assert!(accuracy >= 0.8, "held-out accuracy was {:.2}", accuracy);
+    }
+
+    #[test]
+    fn test_prediction_probability_is_normalized() {
+        let classifier = CategoryClassifier::train(&load_fixture("train"));
+        let (category, probability) = classifier.predict("STARBUCKS STORE 1", "Card").unwrap();
+        assert_eq!(category, "Food");
+        assert!(probability > classifier::MIN_SUGGESTION_CONFIDENCE && probability <= 1.0);
+    }
+}
@@ -0,0 +1,203 @@
+use clap::Parser;
+use expense_tracker::cli::{self, Cli, Command};
+use expense_tracker::import_export;
+use expense_tracker::models;
+use std::path::Path;
+
+fn run(db: &Path, args: &[&str]) -> String {
+    let db_args = ["--db", db.to_str().unwrap()];
+    let cli = Cli::try_parse_from(
+        std::iter::once("expense_tracker")
+            .chain(args.iter().copied())
+            .chain(db_args),
+    )
+    .expect("arguments should parse");
+    let mut output = Vec::new();
+    cli::run(cli.command.unwrap(), &cli.db, cli.json, &mut output).expect("command should succeed");
+    String::from_utf8(output).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_subcommand_opens_gui() {
+        let cli = Cli::try_parse_from(["expense_tracker"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.db, models::DEFAULT_DATABASE_PATH);
+    }
+
+    #[test]
+    fn test_global_flags_after_subcommand() {
+        let cli =
+            Cli::try_parse_from(["expense_tracker", "list", "--json", "--db", "other.db"]).unwrap();
+        assert!(cli.json);
+        assert_eq!(cli.db, "other.db");
+        assert!(matches!(cli.command, Some(Command::List { .. })));
+    }
+
+    #[test]
+    fn test_invalid_amounts_are_rejected() {
+        for amount in ["NaN", "inf", "-inf", "0", "-0", "ten"] {
+            let result = Cli::try_parse_from([
+                "expense_tracker",
+                "add",
+                "--amount",
+                amount,
+                "--description",
+                "Lunch",
+            ]);
+            assert!(result.is_err(), "{} should be rejected", amount);
+        }
+    }
+
+    #[test]
+    fn test_negative_amounts_are_refunds() {
+        let cli = Cli::try_parse_from([
+            "expense_tracker",
+            "add",
+            "--amount",
+            "-12.50",
+            "--description",
+            "Returned shoes",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Add { amount, .. }) if amount == -12.5
+        ));
+    }
+
+    #[test]
+    fn test_date_range_must_be_in_order() {
+        let cli = Cli::try_parse_from([
+            "expense_tracker",
+            "report",
+            "--from",
+            "2024-03-01",
+            "--to",
+            "2024-02-01",
+        ])
+        .unwrap();
+        let error = cli.validate().unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("--from 2024-03-01 is after --to 2024-02-01"));
+
+        let cli = Cli::try_parse_from([
+            "expense_tracker",
+            "statement",
+            "out.pdf",
+            "--from",
+            "2024-02-01",
+            "--to",
+            "2024-02-29",
+        ])
+        .unwrap();
+        assert!(cli.validate().is_ok());
+        assert!(Cli::try_parse_from(["expense_tracker", "list", "--from", "2024-13-01"]).is_err());
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let csv = "date,amount,category,description,payment_method\n2023-01-02,12.50,Food,Lunch,Cash\n2023-01-03,40,,SHELL OIL,Card\n";
+        let expenses = import_export::read_expenses_csv(csv.as_bytes()).unwrap();
+        assert_eq!(expenses.len(), 2);
+        assert_eq!(expenses[1].category, "");
+        assert_eq!(expenses[1].tags, "");
+
+        let mut written = Vec::new();
+        import_export::write_expenses_csv(&mut written, &expenses).unwrap();
+        let reread = import_export::read_expenses_csv(written.as_slice()).unwrap();
+        assert_eq!(reread[0].description, "Lunch");
+        assert_eq!(reread[0].amount, 12.5);
+    }
+
+    #[test]
+    fn test_invalid_import_date_is_rejected() {
+        let csv = "date,amount,description\n01/02/2023,5.00,Coffee\n";
+        assert!(import_export::read_expenses_csv(csv.as_bytes()).is_err());
+    }
+
+    // Runs every subcommand against a throwaway database
+    #[test]
+    fn test_commands_against_temporary_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("cli.db");
+
+        let added = run(
+            &db_path,
+            &[
+                "add",
+                "--amount",
+                "12.5",
+                "--description",
+                "Lunch",
+                "--date",
+                "2023-01-02",
+                "--category",
+                "Food",
+                "--json",
+            ],
+        );
+        let added: serde_json::Value = serde_json::from_str(&added).unwrap();
+        assert_eq!(added["category"], "Food");
+        let id = added["id"].as_i64().unwrap();
+
+        let csv_path = dir.path().join("import.csv");
+        std::fs::write(
+            &csv_path,
+            "date,amount,category,description,payment_method\n2023-02-01,30,,Lunch special,Card\n",
+        )
+        .unwrap();
+        let imported = run(&db_path, &["import", csv_path.to_str().unwrap(), "--json"]);
+        let imported: serde_json::Value = serde_json::from_str(&imported).unwrap();
+        assert_eq!(imported["imported"], 1);
+        // The learned classifier categorizes the uncategorized row from history
+        assert_eq!(imported["expenses"][0]["category"], "Food");
+
+        let listed: serde_json::Value =
+            serde_json::from_str(&run(&db_path, &["list", "--from", "2023-02-01", "--json"]))
+                .unwrap();
+        assert_eq!(listed.as_array().unwrap().len(), 1);
+
+        let report: serde_json::Value =
+            serde_json::from_str(&run(&db_path, &["report", "--json"])).unwrap();
+        assert_eq!(report["count"], 2);
+        assert_eq!(report["categories"]["Food"], 42.5);
+
+        let exported = run(&db_path, &["export"]);
+        assert!(exported.starts_with("date,amount,category,description,payment_method,tags"));
+        assert_eq!(exported.lines().count(), 3);
+
+        let pdf_path = dir.path().join("statement.pdf");
+        let statement: serde_json::Value = serde_json::from_str(&run(
+            &db_path,
+            &[
+                "statement",
+                pdf_path.to_str().unwrap(),
+                "--month",
+                "2023-02",
+                "--json",
+            ],
+        ))
+        .unwrap();
+        assert_eq!(statement["period"], "February 2023");
+        assert_eq!(statement["count"], 1);
+        assert!(std::fs::read(&pdf_path).unwrap().starts_with(b"%PDF"));
+
+        run(&db_path, &["delete", &id.to_string()]);
+        let listed: serde_json::Value =
+            serde_json::from_str(&run(&db_path, &["list", "--json"])).unwrap();
+        assert_eq!(listed.as_array().unwrap().len(), 1);
+
+        assert!(run(
+            &db_path,
+            &["user", "create", "cli_user", "--password", "abc12!"]
+        )
+        .contains("cli_user"));
+        let conn = models::open_database(db_path.to_str().unwrap()).unwrap();
+        assert!(models::get_user_id(&conn, "cli_user").unwrap().is_some());
+    }
+}
@@ -0,0 +1,10 @@
+use eframe::egui;
+use expense_tracker::storage::MemoryStore;
+use expense_tracker::ui::MyApp;
+
+// Nothing touches the database file: expenses and users live in a MemoryStore
+pub fn create_test_app() -> MyApp {
+    let mut app = MyApp::with_store(&egui::Context::default(), Box::new(MemoryStore::default()));
+    app.wait_for_worker();
+    app
+}
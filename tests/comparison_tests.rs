@@ -0,0 +1,77 @@
+use chrono::NaiveDate;
+use expense_tracker::comparison::{self, Baseline, ComparisonRow, GroupBy};
+use expense_tracker::models::Expense;
+use expense_tracker::periods::Period;
+
+fn expense(date: &str, amount: f32, category: &str, payment_method: &str) -> Expense {
+    Expense {
+        id: 0,
+        date: date.to_string(),
+        amount,
+        category: category.to_string(),
+        description: String::new(),
+        payment_method: payment_method.to_string(),
+        tags: String::new(),
+        payee_id: None,
+        time: None,
+    }
+}
+
+fn march_2024() -> Period {
+    Period::this_month(NaiveDate::from_ymd_opt(2024, 3, 15).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows_are_sorted_by_largest_change() {
+        let expenses = vec![
+            expense("2024-03-02", 120.0, "Food", "Card"),
+            expense("2024-02-02", 100.0, "Food", "Card"),
+            expense("2024-03-05", 50.0, "Travel", "Cash"),
+            expense("2024-02-20", 300.0, "Shopping", "Card"),
+            expense("2023-03-10", 999.0, "Food", "Card"),
+        ];
+        let current = march_2024();
+        let previous = Baseline::PreviousPeriod.period(&current);
+
+        let rows = comparison::compare_periods(&expenses, &current, &previous, GroupBy::Category);
+        let keys: Vec<&str> = rows.iter().map(|row| row.key.as_str()).collect();
+        assert_eq!(keys, vec!["Shopping", "Travel", "Food"]);
+        assert_eq!(rows[0].change(), -300.0);
+        assert_eq!(rows[0].percent_label(), "-100.0%");
+        assert_eq!(rows[1].percent_change(), None);
+        assert_eq!(rows[1].percent_label(), "new");
+        assert_eq!(rows[2].percent_label(), "+20.0%");
+
+        let by_method =
+            comparison::compare_periods(&expenses, &current, &previous, GroupBy::PaymentMethod);
+        assert_eq!(
+            by_method[0],
+            ComparisonRow {
+                key: "Card".to_string(),
+                current: 120.0,
+                previous: 400.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_same_period_last_year_baseline() {
+        let expenses = vec![
+            expense("2024-03-02", 120.0, "Food", "Card"),
+            expense("2023-03-10", 80.0, "Food", "Card"),
+            expense("2024-02-02", 100.0, "Food", "Card"),
+        ];
+        let current = march_2024();
+        let baseline = Baseline::SamePeriodLastYear.period(&current);
+        assert_eq!(baseline.label(), "March 2023");
+
+        let rows = comparison::compare_periods(&expenses, &current, &baseline, GroupBy::Category);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].previous, 80.0);
+        assert_eq!(rows[0].percent_label(), "+50.0%");
+    }
+}
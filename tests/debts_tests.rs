@@ -0,0 +1,129 @@
+use chrono::NaiveDate;
+use expense_tracker::debts::{self, DebtState, Strategy};
+use expense_tracker::models::{self, Debt, Expense};
+
+fn create_debt(name: &str, balance: f32, apr: f32, minimum_payment: f32) -> DebtState {
+    DebtState {
+        name: name.to_string(),
+        balance,
+        apr,
+        minimum_payment,
+    }
+}
+
+fn create_payment(date: &str, amount: f32, category: &str, tags: &str) -> Expense {
+    Expense {
+        id: 0,
+        date: date.to_string(),
+        amount,
+        category: category.to_string(),
+        description: "Card payment".to_string(),
+        payment_method: "Card".to_string(),
+        tags: tags.to_string(),
+        payee_id: None,
+        time: None,
+    }
+}
+
+fn date(text: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logged_payments_reduce_the_balance() {
+        let debt = Debt {
+            id: 1,
+            user_id: 1,
+            name: "Visa".to_string(),
+            balance: 1000.0,
+            apr: 19.9,
+            minimum_payment: 40.0,
+            tag: "visa".to_string(),
+            start_date: "2024-01-01".to_string(),
+        };
+        let expenses = vec![
+            create_payment("2024-01-20", 150.0, "Debt Payments", "Visa"),
+            create_payment("2024-02-20", 100.0, "Debt Payments", "visa, card"),
+            create_payment("2023-12-20", 100.0, "Debt Payments", "visa"),
+            create_payment("2024-02-21", 60.0, "Debt Payments", "car loan"),
+            create_payment("2024-02-22", 25.0, "Shopping", "visa"),
+        ];
+
+        let state = debts::current_state(&debt, &expenses);
+        assert_eq!(state.balance, 750.0);
+        assert_eq!(state.name, "Visa");
+    }
+
+    #[test]
+    fn test_avalanche_saves_interest_and_snowball_clears_small_debts_first() {
+        let states = vec![
+            create_debt("Card", 1000.0, 24.0, 50.0),
+            create_debt("Loan", 500.0, 6.0, 25.0),
+        ];
+        let today = date("2024-01-15");
+        let avalanche = debts::simulate(&states, 200.0, Strategy::Avalanche, today);
+        let snowball = debts::simulate(&states, 200.0, Strategy::Snowball, today);
+
+        assert!(avalanche.finished && snowball.finished);
+        assert_eq!(avalanche.months[0].month, "2024-02");
+        assert_eq!(avalanche.months[0].payment, 200.0);
+        assert!(avalanche.total_interest < snowball.total_interest);
+        assert!(snowball.payoff_months[1] < avalanche.payoff_months[1]);
+        assert!(avalanche.payoff_months[0] < snowball.payoff_months[0]);
+        assert!((avalanche.total_paid - 1500.0 - avalanche.total_interest).abs() < 0.1);
+        assert_eq!(avalanche.months.last().unwrap().remaining(), 0.0);
+    }
+
+    #[test]
+    fn test_minimums_only_and_plans_that_never_finish() {
+        // A budget below the minimums pays the minimums
+        let states = vec![create_debt("Family loan", 300.0, 0.0, 100.0)];
+        let plan = debts::simulate(&states, 0.0, Strategy::Snowball, date("2024-01-10"));
+        assert_eq!(plan.months.len(), 3);
+        assert_eq!(plan.total_interest, 0.0);
+        assert_eq!(plan.payoff_months, vec![Some("2024-04".to_string())]);
+        assert_eq!(plan.debt_free_month(), Some("2024-04"));
+
+        // The minimum doesn't even cover the interest
+        let states = vec![create_debt("Payday loan", 1000.0, 120.0, 5.0)];
+        let plan = debts::simulate(&states, 0.0, Strategy::Avalanche, date("2024-01-10"));
+        assert!(!plan.finished);
+        assert_eq!(plan.debt_free_month(), None);
+        assert_eq!(plan.payoff_months, vec![None]);
+    }
+
+    #[test]
+    fn test_debts_are_stored_per_user() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = models::open_database(dir.path().join("debts.db").to_str().unwrap()).unwrap();
+        models::create_expense_table(&conn).unwrap();
+
+        let debt = Debt {
+            id: 0,
+            user_id: 3,
+            name: "Car loan".to_string(),
+            balance: 8000.0,
+            apr: 4.5,
+            minimum_payment: 250.0,
+            tag: "car loan".to_string(),
+            start_date: "2024-01-01".to_string(),
+        };
+        let debt_id = models::add_debt(&conn, &debt).unwrap();
+        let stored = models::get_debts(&conn, 3).unwrap();
+        assert_eq!(
+            stored,
+            vec![Debt {
+                id: debt_id,
+                ..debt
+            }]
+        );
+        assert!(models::get_debts(&conn, 4).unwrap().is_empty());
+
+        models::delete_debt(&conn, debt_id).unwrap();
+        assert!(models::get_debts(&conn, 3).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+use chrono::NaiveDate;
+use expense_tracker::forecast;
+use expense_tracker::models::Expense;
+
+fn expense(date: &str, amount: f32, category: &str, description: &str) -> Expense {
+    Expense {
+        id: 0,
+        date: date.to_string(),
+        amount,
+        category: category.to_string(),
+        description: description.to_string(),
+        payment_method: "Card".to_string(),
+        tags: String::new(),
+        payee_id: None,
+        time: None,
+    }
+}
+
+fn date(text: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recurring_items_and_pace_blend_with_history() {
+        let expenses = vec![
+            expense("2024-01-01", 1000.0, "Housing and Utilities", "Rent"),
+            expense("2024-02-01", 1000.0, "Housing and Utilities", "Rent"),
+            expense("2024-01-12", 300.0, "Food", "Market 1"),
+            expense("2024-02-12", 300.0, "Food", "Market 2"),
+            expense("2024-03-04", 100.0, "Food", "Market 3"),
+        ];
+        let forecast = forecast::forecast_month(&expenses, date("2024-03-10"));
+        assert_eq!(forecast.month, "2024-03");
+
+        // Rent hasn't been paid this month yet, so it is still to come at its last amount
+        let housing = &forecast.categories[0];
+        assert_eq!(housing.category, "Housing and Utilities");
+        assert_eq!(housing.upcoming_recurring, 1000.0);
+        assert_eq!(
+            (housing.low, housing.projected, housing.high),
+            (1000.0, 1000.0, 1000.0)
+        );
+
+        // 10 of 31 days weigh this month's pace (310) against the 300 average month
+        let food = &forecast.categories[1];
+        let expected = 100.0 + (10.0 / 31.0 * 310.0 + 21.0 / 31.0 * 300.0 - 100.0);
+        assert!((food.projected - expected).abs() < 0.01);
+        assert_eq!(food.spent, 100.0);
+        assert!((forecast.projected - (1000.0 + expected)).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_seasonality_raises_months_that_usually_run_high() {
+        let mut expenses = Vec::new();
+        for month in 1..=12 {
+            let amount = if month == 3 { 200.0 } else { 100.0 };
+            let day = format!("2023-{:02}-15", month);
+            expenses.push(expense(&day, amount, "Food", &format!("Shop {}", month)));
+        }
+        expenses.push(expense("2024-01-15", 100.0, "Food", "Shop 13"));
+        expenses.push(expense("2024-02-15", 100.0, "Food", "Shop 14"));
+
+        // March ran at 200 against a 1300 / 12 average, and the last 12 months average the same
+        let forecast = forecast::forecast_month(&expenses, date("2024-03-01"));
+        let food = &forecast.categories[0];
+        assert!((food.projected - 30.0 / 31.0 * 200.0).abs() < 0.01);
+        assert!(food.low < food.projected && food.projected < food.high);
+    }
+
+    #[test]
+    fn test_following_months_project_the_average_month_and_recurring_items() {
+        let expenses = vec![
+            expense("2024-01-01", 1000.0, "Housing and Utilities", "Rent"),
+            expense("2024-02-01", 1000.0, "Housing and Utilities", "Rent"),
+            expense("2024-01-12", 200.0, "Food", "Market 1"),
+            expense("2024-02-12", 400.0, "Food", "Market 2"),
+            expense("2024-03-04", 100.0, "Food", "Market 3"),
+        ];
+        let today = date("2024-03-10");
+        let months = forecast::project_months(&expenses, today, 2);
+        let labels: Vec<&str> = months.iter().map(|month| month.month.as_str()).collect();
+        assert_eq!(labels, vec!["2024-03", "2024-04", "2024-05"]);
+
+        // The current month is the month-end projection
+        let current = forecast::forecast_month(&expenses, today);
+        assert_eq!(months[0].expected, current.projected);
+        assert_eq!((months[0].low, months[0].high), (current.low, current.high));
+
+        // Later months are rent plus the 300 average food month, give or take its 100 deviation
+        for month in &months[1..] {
+            assert!((month.expected - 1300.0).abs() < 0.01);
+            assert!((month.low - 1200.0).abs() < 0.01);
+            assert!((month.high - 1400.0).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_without_earlier_months_the_projection_carries_forward() {
+        let expenses = vec![expense("2024-12-05", 50.0, "Travel", "Train")];
+        let months = forecast::project_months(&expenses, date("2024-12-10"), 1);
+        assert_eq!(months[1].month, "2025-01");
+        assert_eq!(months[1].expected, months[0].expected);
+    }
+
+    #[test]
+    fn test_without_history_the_pace_is_extrapolated() {
+        let expenses = vec![expense("2024-04-05", 50.0, "Travel", "Train")];
+        let forecast = forecast::forecast_month(&expenses, date("2024-04-10"));
+        let travel = &forecast.categories[0];
+        assert_eq!(travel.projected, 150.0);
+        // A quarter of the projection, shrunk by the 20 of 30 days still to go
+        assert!((travel.high - 175.0).abs() < 0.01);
+        assert!((travel.low - 125.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,130 @@
+use chrono::NaiveDate;
+use expense_tracker::goals;
+use expense_tracker::models::{self, Expense, Goal};
+
+fn create_goal(tag: &str) -> Goal {
+    Goal {
+        id: 0,
+        user_id: 1,
+        name: "Laptop".to_string(),
+        target_amount: 1200.0,
+        target_date: "2024-12-31".to_string(),
+        category: "Savings and Investments".to_string(),
+        tag: tag.to_string(),
+        start_date: "2024-01-01".to_string(),
+    }
+}
+
+fn create_expense(date: &str, amount: f32, category: &str, tags: &str) -> Expense {
+    Expense {
+        id: 0,
+        date: date.to_string(),
+        amount,
+        category: category.to_string(),
+        description: "Transfer".to_string(),
+        payment_method: "Card".to_string(),
+        tags: tags.to_string(),
+        payee_id: None,
+        time: None,
+    }
+}
+
+fn date(text: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contributions_follow_category_tag_and_start_date() {
+        let expenses = vec![
+            create_expense("2024-02-01", 100.0, "Savings and Investments", "Laptop"),
+            create_expense(
+                "2024-03-01",
+                50.0,
+                "Savings and Investments",
+                "holiday, laptop",
+            ),
+            create_expense("2024-03-05", 400.0, "Savings and Investments", "holiday"),
+            create_expense("2023-12-01", 70.0, "Savings and Investments", "laptop"),
+            create_expense("2024-03-09", 30.0, "Shopping", "laptop"),
+        ];
+
+        let tagged = goals::goal_progress(&create_goal("laptop"), &expenses, date("2024-04-01"));
+        assert_eq!(tagged.saved, 150.0);
+        assert_eq!(tagged.remaining, 1050.0);
+        assert_eq!(tagged.fraction, 0.125);
+
+        let whole_category = goals::goal_progress(&create_goal(""), &expenses, date("2024-04-01"));
+        assert_eq!(whole_category.saved, 550.0);
+    }
+
+    #[test]
+    fn test_required_contribution_and_pace() {
+        let goal = create_goal("");
+        let today = date("2024-07-01");
+        let on_pace = vec![create_expense(
+            "2024-06-01",
+            600.0,
+            "Savings and Investments",
+            "",
+        )];
+        let progress = goals::goal_progress(&goal, &on_pace, today);
+        // Half the target with half the time left
+        assert!((progress.required_monthly - 99.8).abs() < 0.1);
+        assert!((progress.monthly_pace - 100.4).abs() < 0.1);
+        assert!(progress.on_track);
+        let projected = progress.projected_date.unwrap();
+        assert!(projected > date("2024-12-20") && projected <= date("2024-12-31"));
+
+        let behind = vec![create_expense(
+            "2024-06-01",
+            300.0,
+            "Savings and Investments",
+            "",
+        )];
+        let progress = goals::goal_progress(&goal, &behind, today);
+        assert!((progress.required_monthly - 149.7).abs() < 0.1);
+        assert!(!progress.on_track);
+
+        // Past the target date the whole remainder is due
+        let progress = goals::goal_progress(&goal, &behind, date("2025-02-01"));
+        assert_eq!(progress.required_monthly, 900.0);
+        assert!(!progress.on_track);
+
+        let done = vec![create_expense(
+            "2024-06-01",
+            1500.0,
+            "Savings and Investments",
+            "",
+        )];
+        let progress = goals::goal_progress(&goal, &done, today);
+        assert!(progress.is_reached());
+        assert_eq!(progress.fraction, 1.0);
+        assert_eq!(progress.projected_date, None);
+        assert!(progress.on_track);
+    }
+
+    #[test]
+    fn test_goals_are_stored_per_user() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = models::open_database(dir.path().join("goals.db").to_str().unwrap()).unwrap();
+        models::create_expense_table(&conn).unwrap();
+
+        let goal_id = models::add_goal(&conn, &create_goal("laptop")).unwrap();
+        let mut other = create_goal("");
+        other.user_id = 2;
+        models::add_goal(&conn, &other).unwrap();
+
+        let goals = models::get_goals(&conn, 1).unwrap();
+        assert_eq!(goals.len(), 1);
+        assert_eq!(goals[0].tag, "laptop");
+        assert_eq!(goals[0].target_amount, 1200.0);
+
+        models::delete_goal(&conn, goal_id).unwrap();
+        assert!(models::get_goals(&conn, 1).unwrap().is_empty());
+        assert_eq!(models::get_goals(&conn, 2).unwrap().len(), 1);
+    }
+}
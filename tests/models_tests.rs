@@ -1,4 +1,18 @@
-use expense_tracker::models::Expense; // Import the Expense struct from the models module
+use expense_tracker::models::{self, Expense}; // Import the Expense struct from the models module
+
+fn create_expense(date: &str, amount: f32) -> Expense {
+    Expense {
+        id: 0,
+        date: date.to_string(),
+        amount,
+        category: "Food".to_string(),
+        description: "Imported".to_string(),
+        payment_method: "Card".to_string(),
+        tags: String::new(),
+        payee_id: None,
+        time: None,
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -14,6 +28,9 @@ mod tests {
             category: "Groceries".to_string(),
             description: "Weekly groceries".to_string(),
             payment_method: "Credit Card".to_string(),
+            tags: String::new(),
+            payee_id: None,
+            time: None,
         };
 
         // Assert that the expense has the correct properties
@@ -31,6 +48,9 @@ mod tests {
             category: "Groceries".to_string(),
             description: "".to_string(),
             payment_method: "".to_string(),
+            tags: String::new(),
+            payee_id: None,
+            time: None,
         };
         let expense2 = Expense {
             id: 0,
@@ -39,6 +59,9 @@ mod tests {
             category: "Transportation".to_string(),
             description: "".to_string(),
             payment_method: "".to_string(),
+            tags: String::new(),
+            payee_id: None,
+            time: None,
         };
         let expense3 = Expense {
             id: 0,
@@ -47,6 +70,9 @@ mod tests {
             category: "Entertainment".to_string(),
             description: "".to_string(),
             payment_method: "".to_string(),
+            tags: String::new(),
+            payee_id: None,
+            time: None,
         };
 
         let total = expense1.amount + expense2.amount + expense3.amount;
@@ -54,6 +80,40 @@ mod tests {
         // Assert that the total amount is calculated correctly
         assert_eq!(total, 100.0);
     }
+
+    #[test]
+    fn test_batch_insert_is_all_or_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = models::open_database(dir.path().join("batch.db").to_str().unwrap()).unwrap();
+        models::create_expense_table(&conn).unwrap();
+
+        let ids = models::add_expenses(
+            &conn,
+            &[
+                create_expense("2024-01-01", 5.0),
+                create_expense("2024-01-02", 7.5),
+            ],
+        )
+        .unwrap();
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+
+        // SQLite stores NaN as NULL, which the amount column refuses
+        let result = models::add_expenses(
+            &conn,
+            &[
+                create_expense("2024-02-01", 3.0),
+                create_expense("2024-02-02", f32::NAN),
+            ],
+        );
+        assert!(result.is_err());
+        let dates: Vec<String> = models::get_expenses(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|expense| expense.date)
+            .collect();
+        assert_eq!(dates, vec!["2024-01-01", "2024-01-02"]);
+    }
 }
 
 // Path: expense_tracker/tests/models_tests.rs
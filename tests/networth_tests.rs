@@ -0,0 +1,97 @@
+use chrono::NaiveDate;
+use expense_tracker::debts::DebtState;
+use expense_tracker::models::{self, Asset, NetWorthSnapshot};
+use expense_tracker::networth;
+
+fn create_asset(name: &str, value: f32) -> Asset {
+    Asset {
+        id: 0,
+        user_id: 1,
+        name: name.to_string(),
+        value,
+    }
+}
+
+fn create_snapshot(date: &str, assets: f32, liabilities: f32) -> NetWorthSnapshot {
+    NetWorthSnapshot {
+        date: date.to_string(),
+        assets,
+        liabilities,
+    }
+}
+
+fn date(text: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_net_worth_is_assets_less_debt_balances() {
+        let assets = vec![
+            create_asset("Checking", 2500.0),
+            create_asset("Car", 8000.0),
+        ];
+        let debts = vec![DebtState {
+            name: "Car loan".to_string(),
+            balance: 6000.0,
+            apr: 4.5,
+            minimum_payment: 250.0,
+        }];
+
+        let snapshot = networth::current_snapshot(&assets, &debts, date("2024-03-15"));
+        assert_eq!(snapshot, create_snapshot("2024-03-15", 10500.0, 6000.0));
+        assert_eq!(snapshot.net_worth(), 4500.0);
+    }
+
+    #[test]
+    fn test_monthly_history_uses_the_last_snapshot_of_each_month() {
+        let snapshots = vec![
+            create_snapshot("2024-01-03", 1000.0, 1500.0),
+            create_snapshot("2024-01-28", 1200.0, 1400.0),
+            create_snapshot("2024-02-10", 1300.0, 1000.0),
+        ];
+
+        let history = networth::monthly_history(&snapshots);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history["2024-01"], -200.0);
+        assert_eq!(history["2024-02"], 300.0);
+
+        assert!(!networth::is_snapshot_due(&snapshots, date("2024-02-20")));
+        assert!(networth::is_snapshot_due(&snapshots, date("2024-03-01")));
+    }
+
+    #[test]
+    fn test_assets_and_snapshots_are_stored() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = models::open_database(dir.path().join("networth.db").to_str().unwrap()).unwrap();
+        models::create_expense_table(&conn).unwrap();
+
+        let asset_id = models::add_asset(&conn, &create_asset("Savings account", 900.0)).unwrap();
+        models::set_asset_value(&conn, asset_id, 1100.0).unwrap();
+        let assets = models::get_assets(&conn, 1).unwrap();
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].value, 1100.0);
+        assert!(models::get_assets(&conn, 2).unwrap().is_empty());
+        models::delete_asset(&conn, asset_id).unwrap();
+        assert!(models::get_assets(&conn, 1).unwrap().is_empty());
+
+        // The same day is overwritten, earlier days are kept
+        models::save_net_worth_snapshot(&conn, 1, &create_snapshot("2024-01-31", 900.0, 0.0))
+            .unwrap();
+        models::save_net_worth_snapshot(&conn, 1, &create_snapshot("2024-02-29", 900.0, 0.0))
+            .unwrap();
+        models::save_net_worth_snapshot(&conn, 1, &create_snapshot("2024-02-29", 1100.0, 0.0))
+            .unwrap();
+        let snapshots = models::get_net_worth_snapshots(&conn, 1).unwrap();
+        assert_eq!(
+            snapshots,
+            vec![
+                create_snapshot("2024-01-31", 900.0, 0.0),
+                create_snapshot("2024-02-29", 1100.0, 0.0),
+            ]
+        );
+    }
+}
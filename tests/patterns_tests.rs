@@ -0,0 +1,93 @@
+use chrono::Weekday;
+use expense_tracker::models::Expense;
+use expense_tracker::patterns;
+
+fn create_expense(date: &str, time: Option<&str>, amount: f32) -> Expense {
+    Expense {
+        id: 0,
+        date: date.to_string(),
+        amount,
+        category: "Food".to_string(),
+        description: "Takeaway".to_string(),
+        payment_method: "Card".to_string(),
+        tags: String::new(),
+        payee_id: None,
+        time: time.map(str::to_string),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hour_heatmap_places_timed_spending_by_weekday() {
+        // 2024-03-01 is a Friday and 2024-03-02 a Saturday
+        let expenses = vec![
+            create_expense("2024-03-01", Some("23:15"), 30.0),
+            create_expense("2024-03-08", Some("23:50"), 20.0),
+            create_expense("2024-03-02", Some("09:05"), 12.0),
+            create_expense("2024-03-02", None, 100.0),
+            create_expense("2024-03-02", Some("09:30"), -12.0),
+        ];
+
+        let heatmap = patterns::hour_heatmap(&expenses);
+        assert_eq!(heatmap.columns.len(), 24);
+        assert_eq!(heatmap.columns[23], "23");
+        assert_eq!(heatmap.cells.len(), 7);
+        assert_eq!(heatmap.cells[4][23], 50.0);
+        assert_eq!(heatmap.cells[5][9], 12.0);
+        let total: f32 = heatmap.cells.iter().flatten().sum();
+        assert_eq!(total, 62.0);
+    }
+
+    #[test]
+    fn test_week_heatmap_includes_quiet_weeks() {
+        let expenses = vec![
+            create_expense("2023-12-31", None, 10.0),
+            create_expense("2024-01-01", None, 5.0),
+            create_expense("2024-01-17", None, 7.0),
+        ];
+
+        let heatmap = patterns::week_heatmap(&expenses);
+        assert_eq!(
+            heatmap.columns,
+            vec!["2023-W52", "2024-W01", "2024-W02", "2024-W03"]
+        );
+        assert_eq!(heatmap.cells[6][0], 10.0);
+        assert_eq!(heatmap.cells[0][1], 5.0);
+        assert!(heatmap.cells.iter().all(|row| row[2] == 0.0));
+        assert_eq!(heatmap.cells[2][3], 7.0);
+
+        assert!(patterns::week_heatmap(&[]).columns.is_empty());
+    }
+
+    #[test]
+    fn test_summary_averages_weekdays_and_finds_weekend_and_late_night_spending() {
+        // Two full weeks, Monday 2024-03-04 to Sunday 2024-03-17
+        let expenses = vec![
+            create_expense("2024-03-04", Some("12:00"), 10.0),
+            create_expense("2024-03-09", Some("01:30"), 40.0),
+            create_expense("2024-03-16", Some("22:45"), 20.0),
+            create_expense("2024-03-17", None, 30.0),
+            create_expense("2024-03-10", None, -50.0),
+        ];
+
+        let summary = patterns::summarize(&expenses);
+        let saturday = &summary.weekday_averages[5];
+        assert_eq!(saturday.weekday, Weekday::Sat);
+        assert_eq!(saturday.days, 2);
+        assert_eq!(saturday.total, 60.0);
+        assert_eq!(saturday.average, 30.0);
+        assert_eq!(summary.weekday_averages[0].average, 5.0);
+        assert_eq!(summary.weekday_averages[2].average, 0.0);
+
+        assert_eq!(summary.weekend_share, 0.9);
+        assert_eq!(summary.late_night_share, Some(60.0 / 70.0));
+        assert_eq!(summary.timed_expenses, 3);
+        assert_eq!(summary.expenses, 4);
+
+        let untimed = vec![create_expense("2024-03-04", None, 10.0)];
+        assert_eq!(patterns::summarize(&untimed).late_night_share, None);
+    }
+}
@@ -0,0 +1,73 @@
+use expense_tracker::models::{Expense, Payee, PayeeAlias};
+use expense_tracker::payees;
+
+fn create_payee(id: i32, name: &str, aliases: &[&str]) -> Payee {
+    Payee {
+        id,
+        name: name.to_string(),
+        aliases: aliases
+            .iter()
+            .enumerate()
+            .map(|(index, pattern)| PayeeAlias {
+                id: id * 100 + index as i32,
+                payee_id: id,
+                pattern: pattern.to_string(),
+            })
+            .collect(),
+    }
+}
+
+fn create_expense(description: &str, amount: f32, payee_id: Option<i32>) -> Expense {
+    Expense {
+        id: 0,
+        date: "2023-01-01".to_string(),
+        amount,
+        category: "Shopping".to_string(),
+        description: description.to_string(),
+        payment_method: "Card".to_string(),
+        tags: String::new(),
+        payee_id,
+        time: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aliases_map_bank_descriptions_to_one_payee() {
+        let payees = vec![
+            create_payee(1, "Amazon", &["AMZN MKTP", "amazon.com"]),
+            create_payee(2, "Target", &[]),
+        ];
+        assert_eq!(payees::match_payee(&payees, "AMZN MKTP US*2K3"), Some(1));
+        assert_eq!(payees::match_payee(&payees, "Amazon.com order"), Some(1));
+        assert_eq!(payees::match_payee(&payees, "TARGET 00012"), Some(2));
+        assert_eq!(payees::match_payee(&payees, "Corner shop"), None);
+    }
+
+    #[test]
+    fn test_longest_alias_wins() {
+        let payees = vec![
+            create_payee(1, "Uber", &["UBER"]),
+            create_payee(2, "Uber Eats", &["UBER EATS"]),
+        ];
+        assert_eq!(payees::match_payee(&payees, "UBER EATS PENDING"), Some(2));
+        assert_eq!(payees::match_payee(&payees, "UBER TRIP"), Some(1));
+    }
+
+    #[test]
+    fn test_payee_totals_group_by_canonical_name() {
+        let payees = vec![create_payee(1, "Amazon", &["AMZN"])];
+        let expenses = vec![
+            create_expense("AMZN MKTP US*2K3", 20.0, Some(1)),
+            create_expense("Amazon.com", 15.0, Some(1)),
+            create_expense("Corner shop", 5.0, None),
+        ];
+
+        let totals = payees::calculate_payee_totals(&expenses, &payees);
+        assert_eq!(totals.get("Amazon"), Some(&35.0));
+        assert_eq!(totals.get("Corner shop"), Some(&5.0));
+    }
+}
@@ -0,0 +1,88 @@
+use chrono::NaiveDate;
+use expense_tracker::periods::Period;
+
+fn date(text: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_cover_whole_calendar_units() {
+        let today = date("2024-03-15");
+
+        assert_eq!(
+            Period::this_month(today).range(),
+            Some((date("2024-03-01"), date("2024-03-31")))
+        );
+        assert_eq!(
+            Period::last_month(today).range(),
+            Some((date("2024-02-01"), date("2024-02-29")))
+        );
+        assert_eq!(
+            Period::this_quarter(today).range(),
+            Some((date("2024-01-01"), date("2024-03-31")))
+        );
+        assert_eq!(
+            Period::year_to_date(today).range(),
+            Some((date("2024-01-01"), today))
+        );
+        assert_eq!(
+            Period::rolling(30, today).range(),
+            Some((date("2024-02-15"), today))
+        );
+        assert_eq!(Period::AllTime.range(), None);
+    }
+
+    #[test]
+    fn test_previous_and_next_keep_the_period_length() {
+        let january = Period::this_month(date("2024-01-20"));
+        assert_eq!(january.previous().label(), "December 2023");
+        assert_eq!(january.previous().next(), january);
+
+        let first_quarter = Period::this_quarter(date("2024-02-01"));
+        assert_eq!(first_quarter.previous().label(), "Q4 2023");
+        assert_eq!(first_quarter.next().label(), "Q2 2024");
+
+        // Leap day falls back to the 28th
+        let to_date = Period::year_to_date(date("2024-02-29"));
+        assert_eq!(
+            to_date.previous().range(),
+            Some((date("2023-01-01"), date("2023-02-28")))
+        );
+
+        let custom = Period::Custom {
+            from: date("2024-01-01"),
+            to: date("2024-01-10"),
+        };
+        assert_eq!(
+            custom.next().range(),
+            Some((date("2024-01-11"), date("2024-01-20")))
+        );
+        assert_eq!(
+            Period::rolling(7, date("2024-03-15")).previous().range(),
+            Some((date("2024-03-02"), date("2024-03-08")))
+        );
+        assert_eq!(Period::AllTime.next(), Period::AllTime);
+
+        assert_eq!(first_quarter.year_earlier().label(), "Q1 2023");
+        assert_eq!(
+            Period::rolling(7, date("2024-02-29"))
+                .year_earlier()
+                .range(),
+            Some((date("2023-02-22"), date("2023-02-28")))
+        );
+    }
+
+    #[test]
+    fn test_contains_is_inclusive_and_rejects_bad_dates() {
+        let march = Period::this_month(date("2024-03-15"));
+        assert!(march.contains("2024-03-01"));
+        assert!(march.contains("2024-03-31"));
+        assert!(!march.contains("2024-04-01"));
+        assert!(!march.contains("not a date"));
+        assert!(Period::AllTime.contains("not a date"));
+    }
+}
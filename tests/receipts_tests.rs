@@ -0,0 +1,71 @@
+use expense_tracker::receipts;
+use std::path::Path;
+
+const SAMPLE_RECEIPT: &str = "
+    CORNER GROCERY MARKET
+    123 Main St, Springfield
+    Date: 2023-11-07 14:32
+    Apples            3.49
+    Milk              2.99
+    Subtotal          6.48
+    Tax               0.52
+    TOTAL            $7.00
+    Thank you for shopping!
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest_from_text_finds_all_fields() {
+        let suggestion = receipts::suggest_from_text(SAMPLE_RECEIPT);
+
+        let merchant = suggestion.merchant.unwrap();
+        assert_eq!(merchant.value, "CORNER GROCERY MARKET");
+
+        let date = suggestion.date.unwrap();
+        assert_eq!(date.value, "2023-11-07");
+        assert!(date.confidence >= 0.9);
+
+        let total = suggestion.total.unwrap();
+        assert_eq!(total.value, 7.0);
+        assert!(total.confidence >= 0.9);
+    }
+
+    #[test]
+    fn test_total_ignores_subtotal_and_falls_back_to_largest_amount() {
+        let suggestion = receipts::suggest_from_text("Cafe\nSubtotal 12.00\nLatte 4.50");
+        let total = suggestion.total.unwrap();
+        assert_eq!(total.value, 12.0);
+        assert!(total.confidence < 0.5);
+    }
+
+    #[test]
+    fn test_month_name_and_us_dates_are_normalized() {
+        let named = receipts::suggest_from_text("Store\nMar 5, 2024")
+            .date
+            .unwrap();
+        assert_eq!(named.value, "2024-03-05");
+
+        let slashed = receipts::suggest_from_text("Store\n12/31/23").date.unwrap();
+        assert_eq!(slashed.value, "2023-12-31");
+    }
+
+    #[test]
+    fn test_unsupported_receipt_type_is_rejected() {
+        let parsers = receipts::default_parsers();
+        assert!(receipts::parse_receipt(&parsers, Path::new("photo.jpg")).is_err());
+        assert!(receipts::parse_receipt(&parsers, Path::new("notes.txt")).is_err());
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    #[test]
+    fn test_image_receipts_explain_missing_ocr() {
+        let parsers = receipts::default_parsers();
+        let error = receipts::parse_receipt(&parsers, Path::new("photo.png"))
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("--features ocr"));
+    }
+}
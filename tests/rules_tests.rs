@@ -0,0 +1,123 @@
+use expense_tracker::models::{CategoryRule, Expense};
+use expense_tracker::rules;
+
+fn create_expense(id: i32, description: &str, amount: f32, payment_method: &str) -> Expense {
+    Expense {
+        id,
+        date: "2023-01-01".to_string(),
+        amount,
+        category: "Miscellaneous".to_string(),
+        description: description.to_string(),
+        payment_method: payment_method.to_string(),
+        tags: String::new(),
+        payee_id: None,
+        time: None,
+    }
+}
+
+fn create_rule(id: i32, description_pattern: &str, category: &str) -> CategoryRule {
+    CategoryRule {
+        id,
+        user_id: 1,
+        position: id,
+        description_pattern: description_pattern.to_string(),
+        is_regex: false,
+        min_amount: None,
+        max_amount: None,
+        payment_method: String::new(),
+        category: category.to_string(),
+        tags: String::new(),
+        rename_to: String::new(),
+        description_regex: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains_match_is_case_insensitive() {
+        let rule = create_rule(1, "uber", "Transportation");
+        assert!(rules::rule_matches(
+            &rule,
+            &create_expense(1, "UBER *TRIP", 12.0, "Card")
+        ));
+        assert!(!rules::rule_matches(
+            &rule,
+            &create_expense(2, "Groceries", 12.0, "Card")
+        ));
+    }
+
+    #[test]
+    fn test_regex_amount_and_payment_conditions() {
+        let mut rule = create_rule(1, r"^AMZN\s+MKTP", "Shopping");
+        rule.is_regex = true;
+        rule.description_regex = rules::compile_pattern(&rule);
+        rule.min_amount = Some(10.0);
+        rule.max_amount = Some(100.0);
+        rule.payment_method = "Card".to_string();
+
+        assert!(rules::rule_matches(
+            &rule,
+            &create_expense(1, "AMZN MKTP US*2K3", 25.0, "Card")
+        ));
+        assert!(!rules::rule_matches(
+            &rule,
+            &create_expense(2, "AMZN MKTP US*2K3", 250.0, "Card")
+        ));
+        assert!(!rules::rule_matches(
+            &rule,
+            &create_expense(3, "AMZN MKTP US*2K3", 25.0, "Cash")
+        ));
+
+        rule.description_pattern = "(unclosed".to_string();
+        rule.description_regex = rules::compile_pattern(&rule);
+        assert!(rule.description_regex.is_none());
+        assert!(!rules::rule_matches(
+            &rule,
+            &create_expense(4, "(unclosed", 25.0, "Card")
+        ));
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let mut coffee = create_rule(1, "starbucks", "Food");
+        coffee.tags = "coffee".to_string();
+        coffee.rename_to = "Starbucks".to_string();
+        let rules = vec![coffee, create_rule(2, "star", "Entertainment and Leisure")];
+
+        let mut expense = create_expense(1, "STARBUCKS #1234 SEATTLE", 5.5, "Card");
+        expense.tags = "work".to_string();
+        assert_eq!(rules::apply_rules(&rules, &mut expense), Some(1));
+        assert_eq!(expense.category, "Food");
+        assert_eq!(expense.tags, "work, coffee");
+        assert_eq!(expense.description, "Starbucks");
+    }
+
+    #[test]
+    fn test_dry_run_only_lists_changed_rows() {
+        let rules = vec![create_rule(1, "rent", "Housing and Utilities")];
+        let mut already_categorized = create_expense(1, "Rent March", 900.0, "Card");
+        already_categorized.category = "Housing and Utilities".to_string();
+        let expenses = vec![
+            already_categorized,
+            create_expense(2, "Rent April", 900.0, "Card"),
+            create_expense(3, "Lunch", 12.0, "Cash"),
+        ];
+
+        let changes = rules::dry_run(&rules, &expenses);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].expense_id, 2);
+        assert_eq!(changes[0].old_category, "Miscellaneous");
+        assert_eq!(changes[0].new_category, "Housing and Utilities");
+        // The dry run must not touch the expenses themselves
+        assert_eq!(expenses[1].category, "Miscellaneous");
+    }
+
+    #[test]
+    fn test_merge_tags_removes_duplicates_and_blanks() {
+        assert_eq!(rules::merge_tags("a, b", "b,c, ,a"), "a, b, c");
+        assert_eq!(rules::merge_tags("", ""), "");
+    }
+}
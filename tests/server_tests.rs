@@ -0,0 +1,276 @@
+use expense_tracker::models::{self, User};
+use expense_tracker::server::ApiServer;
+use expense_tracker::storage::{ExpenseStore, HttpStore, SqliteStore};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::time::Duration;
+
+// Starts a server for the database on a free port and returns its base URL
+fn start_server(db_path: &Path) -> String {
+    start_server_with(ApiServer::bind("127.0.0.1:0", open_store(db_path)).unwrap())
+}
+
+fn open_store(db_path: &Path) -> SqliteStore {
+    SqliteStore::open(db_path.to_str().unwrap()).unwrap()
+}
+
+fn start_server_with(api_server: ApiServer) -> String {
+    let address = api_server.local_addr().unwrap();
+    std::thread::spawn(move || api_server.run());
+    format!("http://{}", address)
+}
+
+// Returns the status code and JSON body, including for error responses
+fn send(request: ureq::Request, body: Option<Value>) -> (u16, Value) {
+    let result = match body {
+        Some(body) => request.send_json(body),
+        None => request.call(),
+    };
+    let response = match result {
+        Ok(response) => response,
+        Err(ureq::Error::Status(_, response)) => response,
+        Err(e) => panic!("request failed: {}", e),
+    };
+    (response.status(), response.into_json().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_without_token_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let base_url = start_server(&dir.path().join("server.db"));
+        let (status, body) = send(ureq::get(&format!("{}/api/expenses", base_url)), None);
+        assert_eq!(status, 401);
+        assert_eq!(body["error"], "missing token");
+
+        let request = ureq::get(&format!("{}/api/expenses", base_url))
+            .set("Authorization", "Bearer not-a-session");
+        assert_eq!(send(request, None).0, 401);
+    }
+
+    #[test]
+    fn test_cross_origin_requests_are_allowed() {
+        let dir = tempfile::tempdir().unwrap();
+        let base_url = start_server(&dir.path().join("server.db"));
+        let preflight = ureq::request("OPTIONS", &format!("{}/api/expenses", base_url))
+            .set("Origin", "http://localhost:8081")
+            .set("Access-Control-Request-Method", "GET")
+            .call()
+            .unwrap();
+        assert_eq!(preflight.status(), 204);
+        assert_eq!(preflight.header("Access-Control-Allow-Origin"), Some("*"));
+        assert!(preflight
+            .header("Access-Control-Allow-Headers")
+            .unwrap()
+            .contains("Authorization"));
+
+        // Rejected requests carry the header too, so the page can read the error
+        let result = ureq::get(&format!("{}/api/expenses", base_url)).call();
+        let Err(ureq::Error::Status(401, response)) = result else {
+            panic!("expected the request to be rejected");
+        };
+        assert_eq!(response.header("Access-Control-Allow-Origin"), Some("*"));
+    }
+
+    #[test]
+    fn test_expense_endpoints_against_temporary_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("server.db");
+        let conn = models::open_database(db_path.to_str().unwrap()).unwrap();
+        models::create_expense_table(&conn).unwrap();
+        let user = User {
+            id: 0,
+            username: "phone".to_string(),
+            password_hash: String::new(),
+        };
+        models::add_user(&conn, &user, "secret1!").unwrap();
+
+        let base_url = start_server(&db_path);
+        let url = |path: &str| format!("{}{}", base_url, path);
+
+        let (status, _) = send(
+            ureq::post(&url("/api/login")),
+            Some(json!({ "username": "phone", "password": "wrong" })),
+        );
+        assert_eq!(status, 401);
+        let (status, login) = send(
+            ureq::post(&url("/api/login")),
+            Some(json!({ "username": "phone", "password": "secret1!" })),
+        );
+        assert_eq!(status, 200);
+        let auth = format!("Bearer {}", login["token"].as_str().unwrap());
+
+        let (status, lunch) = send(
+            ureq::post(&url("/api/expenses")).set("Authorization", &auth),
+            Some(
+                json!({ "date": "2024-01-05", "amount": 12.5, "description": "Lunch", "category": "Food" }),
+            ),
+        );
+        assert_eq!(status, 201);
+        assert_eq!(lunch["payment_method"], "Card");
+        let lunch_id = lunch["id"].as_i64().unwrap();
+        send(
+            ureq::post(&url("/api/expenses")).set("Authorization", &auth),
+            Some(
+                json!({ "date": "2024-02-01", "amount": 800, "description": "Rent", "category": "Housing" }),
+            ),
+        );
+
+        let (_, listed) = send(
+            ureq::get(&url("/api/expenses?from=2024-01-01&to=2024-01-31"))
+                .set("Authorization", &auth),
+            None,
+        );
+        assert_eq!(listed.as_array().unwrap().len(), 1);
+
+        let lunch_url = url(&format!("/api/expenses/{}", lunch_id));
+        let (status, updated) = send(
+            ureq::put(&lunch_url).set("Authorization", &auth),
+            Some(json!({ "amount": 15, "description": "Team lunch", "tags": "work" })),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(updated["date"], "2024-01-05");
+        assert_eq!(updated["category"], "Food");
+        assert_eq!(updated["tags"], "work");
+
+        // Fields left out of an update keep their values
+        let cafe_id = models::add_payee(&conn, "Cafe").unwrap();
+        let (_, updated) = send(
+            ureq::put(&lunch_url).set("Authorization", &auth),
+            Some(json!({ "payment_method": "Cash", "payee_id": cafe_id })),
+        );
+        assert_eq!(updated["description"], "Team lunch");
+        assert_eq!(updated["amount"], 15.0);
+        assert_eq!(updated["tags"], "work");
+        assert_eq!(updated["payment_method"], "Cash");
+        assert_eq!(updated["payee_id"], cafe_id);
+        let (_, updated) = send(
+            ureq::put(&lunch_url).set("Authorization", &auth),
+            Some(json!({ "amount": -2.5 })),
+        );
+        assert_eq!(updated["payee_id"], cafe_id);
+        assert_eq!(updated["payment_method"], "Cash");
+        let (_, updated) = send(
+            ureq::put(&lunch_url).set("Authorization", &auth),
+            Some(json!({ "amount": 15, "payee_id": null })),
+        );
+        assert_eq!(updated["payee_id"], Value::Null);
+
+        // 1e39 overflows an f32 to infinity
+        for body in [
+            json!({ "amount": 1e39 }),
+            json!({ "amount": 0 }),
+            json!({ "payee_id": 9999 }),
+        ] {
+            let (status, _) = send(
+                ureq::put(&lunch_url).set("Authorization", &auth),
+                Some(body),
+            );
+            assert_eq!(status, 400);
+        }
+        let (status, body) = send(
+            ureq::post(&url("/api/expenses")).set("Authorization", &auth),
+            Some(json!({ "amount": 1e39, "description": "Overflow" })),
+        );
+        assert_eq!(status, 400);
+        assert_eq!(body["error"], "amount must be a non-zero number");
+        let (status, coffee) = send(
+            ureq::post(&url("/api/expenses")).set("Authorization", &auth),
+            Some(
+                json!({ "date": "2024-01-06", "amount": 3, "description": "Coffee", "payee_id": cafe_id }),
+            ),
+        );
+        assert_eq!(status, 201);
+        assert_eq!(coffee["payee_id"], cafe_id);
+        send(
+            ureq::delete(&url(&format!("/api/expenses/{}", coffee["id"])))
+                .set("Authorization", &auth),
+            None,
+        );
+
+        let (_, totals) = send(
+            ureq::get(&url("/api/totals/categories")).set("Authorization", &auth),
+            None,
+        );
+        assert_eq!(totals, json!({ "Food": 15.0, "Housing": 800.0 }));
+        let (_, trends) = send(
+            ureq::get(&url("/api/trends/monthly")).set("Authorization", &auth),
+            None,
+        );
+        assert_eq!(trends, json!({ "2024-01": 15.0, "2024-02": 800.0 }));
+
+        let (status, _) = send(
+            ureq::delete(&url(&format!("/api/expenses/{}", lunch_id))).set("Authorization", &auth),
+            None,
+        );
+        assert_eq!(status, 200);
+        let (status, _) = send(
+            ureq::get(&url(&format!("/api/expenses/{}", lunch_id))).set("Authorization", &auth),
+            None,
+        );
+        assert_eq!(status, 404);
+
+        // The GUI's remote store goes through the same endpoints
+        let mut store = HttpStore::new(&base_url);
+        assert!(store.authenticate("phone", "wrong").unwrap().is_none());
+        assert!(store.get_expenses().unwrap().is_empty());
+        let tablet = User {
+            id: 0,
+            username: "tablet".to_string(),
+            password_hash: String::new(),
+        };
+        store.add_user(&tablet, "secret2!").unwrap();
+        assert!(store.add_user(&tablet, "secret2!").is_err());
+        assert!(store.authenticate("tablet", "secret2!").unwrap().is_some());
+        let user = store.authenticate("phone", "secret1!").unwrap().unwrap();
+        assert_eq!(user.username, "phone");
+        let mut expense = store.get_expenses().unwrap().remove(0);
+        expense.id = 0;
+        expense.description = "Parking".to_string();
+        let parking_id = store.add_expense(&expense).unwrap();
+        expense.id = parking_id;
+        expense.amount = 3.0;
+        store.update_expense(&expense).unwrap();
+        let amounts: Vec<f32> = store
+            .get_expenses()
+            .unwrap()
+            .iter()
+            .map(|expense| expense.amount)
+            .collect();
+        assert_eq!(amounts, vec![800.0, 3.0]);
+        store.delete_expense(parking_id).unwrap();
+        assert!(store.delete_expense(parking_id).is_err());
+        store.logout();
+
+        send(
+            ureq::post(&url("/api/logout")).set("Authorization", &auth),
+            None,
+        );
+        let (status, _) = send(
+            ureq::get(&url("/api/expenses")).set("Authorization", &auth),
+            None,
+        );
+        assert_eq!(status, 401);
+
+        // Idle tokens expire
+        let short_lived = start_server_with(
+            ApiServer::bind("127.0.0.1:0", open_store(&db_path))
+                .unwrap()
+                .with_session_timeout(Duration::from_millis(200)),
+        );
+        let (_, login) = send(
+            ureq::post(&format!("{}/api/login", short_lived)),
+            Some(json!({ "username": "phone", "password": "secret1!" })),
+        );
+        let auth = format!("Bearer {}", login["token"].as_str().unwrap());
+        let expenses = || ureq::get(&format!("{}/api/expenses", short_lived));
+        assert_eq!(send(expenses().set("Authorization", &auth), None).0, 200);
+        std::thread::sleep(Duration::from_millis(400));
+        let (status, body) = send(expenses().set("Authorization", &auth), None);
+        assert_eq!(status, 401);
+        assert_eq!(body["error"], "invalid or expired token");
+    }
+}
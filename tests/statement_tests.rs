@@ -0,0 +1,202 @@
+use chrono::NaiveDate;
+use expense_tracker::debts::DEBT_PAYMENTS_CATEGORY;
+use expense_tracker::models::{Debt, Expense, Goal};
+use expense_tracker::periods::Period;
+use expense_tracker::statement::{self, Statement};
+
+fn create_expense(id: i32, date: &str, amount: f32, category: &str, description: &str) -> Expense {
+    Expense {
+        id,
+        date: date.to_string(),
+        amount,
+        category: category.to_string(),
+        description: description.to_string(),
+        payment_method: "Card".to_string(),
+        tags: String::new(),
+        payee_id: None,
+        time: None,
+    }
+}
+
+fn date(text: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+}
+
+fn create_goal() -> Goal {
+    Goal {
+        id: 1,
+        user_id: 1,
+        name: "Holiday".to_string(),
+        target_amount: 1200.0,
+        target_date: "2024-12-31".to_string(),
+        category: "Savings".to_string(),
+        tag: String::new(),
+        start_date: "2024-01-01".to_string(),
+    }
+}
+
+fn create_debt() -> Debt {
+    Debt {
+        id: 1,
+        user_id: 1,
+        name: "Credit card".to_string(),
+        balance: 1000.0,
+        apr: 19.9,
+        minimum_payment: 50.0,
+        tag: "card".to_string(),
+        start_date: "2024-01-01".to_string(),
+    }
+}
+
+fn march() -> Period {
+    Period::Month {
+        year: 2024,
+        month: 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statement_covers_the_period_against_the_one_before() {
+        let expenses = vec![
+            create_expense(1, "2024-03-20", 60.0, "Food", "Supermarket"),
+            create_expense(2, "2024-03-02", 900.0, "Housing", "Rent"),
+            create_expense(3, "2024-03-15", -25.0, "Shopping", "Refund"),
+            create_expense(4, "2024-02-10", 40.0, "Food", "Supermarket"),
+            create_expense(5, "2024-04-01", 10.0, "Food", "Bakery"),
+        ];
+
+        let statement = Statement::new(&expenses, &march(), &[], &[], date("2024-04-02"));
+        let ids: Vec<i32> = statement.transactions.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![2, 3, 1]);
+        assert_eq!(statement.spent, 960.0);
+        assert_eq!(statement.income, 25.0);
+        assert_eq!(statement.net(), 935.0);
+
+        assert_eq!(statement.baseline, Some(march().previous()));
+        assert_eq!(statement.categories[0].key, "Housing");
+        let food = &statement.categories[1];
+        assert_eq!((food.current, food.previous), (60.0, 40.0));
+        assert_eq!(food.percent_label(), "+50.0%");
+
+        let all_time = Statement::new(&expenses, &Period::AllTime, &[], &[], date("2024-04-02"));
+        assert_eq!(all_time.baseline, None);
+        assert_eq!(all_time.transactions.len(), 5);
+        assert!(all_time.categories.iter().all(|row| row.previous == 0.0));
+    }
+
+    #[test]
+    fn test_budget_status_holds_goals_and_debts_against_the_period() {
+        let mut payment = create_expense(3, "2024-03-10", 40.0, DEBT_PAYMENTS_CATEGORY, "Card");
+        payment.tags = "card".to_string();
+        let expenses = vec![
+            create_expense(1, "2024-02-05", 200.0, "Savings", "Transfer"),
+            create_expense(2, "2024-03-05", 100.0, "Savings", "Transfer"),
+            payment,
+            // After the period, so not part of its status
+            create_expense(4, "2024-04-01", 900.0, "Savings", "Bonus"),
+        ];
+        let goals = [create_goal()];
+        let debts = [create_debt()];
+
+        let statement = Statement::new(&expenses, &march(), &goals, &debts, date("2024-04-02"));
+        assert_eq!(statement.budget.len(), 2);
+        let goal = &statement.budget[0];
+        assert_eq!(goal.name, "Goal: Holiday");
+        assert_eq!(goal.actual, 100.0);
+        // 900 left over the nine months to the end of December, scaled to March's 31 days
+        assert!((goal.planned.unwrap() - 101.4).abs() < 0.1);
+        assert_eq!(goal.status, "On track, 25% saved");
+        let debt = &statement.budget[1];
+        assert_eq!(debt.name, "Debt: Credit card");
+        assert_eq!(debt.actual, 40.0);
+        assert!((debt.planned.unwrap() - 50.9).abs() < 0.1);
+        assert_eq!(debt.status, "Below minimum, 960.00 owed");
+
+        let all_time = Statement::new(
+            &expenses,
+            &Period::AllTime,
+            &goals,
+            &debts,
+            date("2024-04-02"),
+        );
+        assert_eq!(all_time.budget[0].actual, 1200.0);
+        assert_eq!(all_time.budget[0].planned, None);
+        assert_eq!(all_time.budget[0].status, "Reached");
+        assert_eq!(all_time.budget[1].status, "960.00 owed");
+
+        let text =
+            pdf_extract::extract_text_from_mem(&statement::statement_pdf(&statement).unwrap())
+                .unwrap();
+        assert!(text.contains("Budget Status"));
+        assert!(text.contains("Goal: Holiday"));
+        assert!(text.contains("Below minimum, 960.00 owed"));
+    }
+
+    #[test]
+    fn test_all_time_statement_skips_malformed_dates() {
+        let expenses = vec![
+            create_expense(1, "2024-03-02", 900.0, "Housing", "Rent"),
+            create_expense(2, "2024", 12.5, "Food", "Imported"),
+            create_expense(3, "", 3.0, "Food", "Blank"),
+        ];
+
+        let statement = Statement::new(&expenses, &Period::AllTime, &[], &[], date("2024-04-02"));
+        let text =
+            pdf_extract::extract_text_from_mem(&statement::statement_pdf(&statement).unwrap())
+                .unwrap();
+        assert!(text.contains("Monthly Spending"));
+        assert!(text.contains("Imported"));
+    }
+
+    #[test]
+    fn test_pdf_has_the_summary_charts_and_every_transaction() {
+        let mut expenses = vec![
+            create_expense(1, "2024-03-02", 900.0, "Housing", "Rent"),
+            create_expense(2, "2024-03-03", 12.5, "Food", "Café au lait"),
+        ];
+        // Enough rows to run the transaction table onto more pages
+        for id in 3..120 {
+            let day = format!("2024-03-{:02}", id % 28 + 1);
+            expenses.push(create_expense(
+                id,
+                &day,
+                4.0,
+                "Food",
+                &format!("Snack {}", id),
+            ));
+        }
+
+        let statement = Statement::new(&expenses, &march(), &[], &[], date("2024-04-02"));
+        let pdf = statement::statement_pdf(&statement).unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+
+        let text = pdf_extract::extract_text_from_mem(&pdf).unwrap();
+        assert!(text.contains("Expense Statement"));
+        assert!(text.contains("March 2024"));
+        assert!(text.contains("Category Breakdown"));
+        assert!(text.contains("Monthly Spending"));
+        assert!(text.contains("Café au lait"));
+        assert!(text.contains("Snack 119"));
+        assert!(text.contains("1380.50"));
+        assert!(text.contains("Page 1 of 4"));
+        assert!(text.contains("Page 4 of 4"));
+    }
+
+    #[test]
+    fn test_statement_is_saved_to_the_chosen_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("march.pdf");
+        let statement = Statement::new(&[], &march(), &[], &[], date("2024-04-02"));
+
+        statement::write_statement(&statement, &path).unwrap();
+        let text = pdf_extract::extract_text(&path).unwrap();
+        assert!(text.contains("No expenses in this period."));
+
+        let missing = dir.path().join("no_such_dir").join("march.pdf");
+        assert!(statement::write_statement(&statement, &missing).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use chrono::NaiveDate;
+use expense_tracker::models::{self, Cadence, Expense, Payee};
+use expense_tracker::subscriptions;
+
+fn create_expense(id: i32, date: &str, amount: f32, description: &str) -> Expense {
+    Expense {
+        id,
+        date: date.to_string(),
+        amount,
+        category: "Entertainment".to_string(),
+        description: description.to_string(),
+        payment_method: "Card".to_string(),
+        tags: String::new(),
+        payee_id: None,
+        time: None,
+    }
+}
+
+fn date(text: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monthly_charges_with_changing_references_are_one_subscription() {
+        let expenses = vec![
+            create_expense(1, "2024-01-05", 9.99, "SPOTIFY P1A2B3"),
+            create_expense(2, "2024-02-05", 9.99, "SPOTIFY P4C5D6"),
+            // Skipped March, then a price rise
+            create_expense(3, "2024-04-06", 10.99, "Spotify P7E8F9"),
+            create_expense(4, "2024-05-05", 10.99, "SPOTIFY P0G1H2"),
+            // A one-off from the same service is far off the usual amount
+            create_expense(5, "2024-04-20", 49.0, "SPOTIFY P3J4K5"),
+            create_expense(6, "2024-03-14", 30.0, "Cinema"),
+        ];
+        let detected = subscriptions::detect_subscriptions(&expenses, &[], date("2024-05-20"));
+
+        assert_eq!(detected.len(), 1);
+        let spotify = &detected[0];
+        assert_eq!(spotify.name, "SPOTIFY P0G1H2");
+        assert_eq!(spotify.cadence, Cadence::Monthly);
+        assert_eq!(spotify.charges, 4);
+        assert_eq!(spotify.amount, 10.99);
+        assert_eq!(spotify.next_date, date("2024-06-05"));
+        assert!((spotify.annual_cost() - 131.88).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_payee_cadence_and_lapsed_subscriptions() {
+        let payees = vec![Payee {
+            id: 4,
+            name: "Gym".to_string(),
+            aliases: Vec::new(),
+        }];
+        let expenses: Vec<Expense> = ["2024-03-01", "2024-03-08", "2024-03-15", "2024-03-22"]
+            .iter()
+            .enumerate()
+            .map(|(index, day)| {
+                let mut expense = create_expense(index as i32, day, 12.0, "GYM DROP-IN");
+                expense.payee_id = Some(4);
+                expense
+            })
+            .collect();
+
+        let detected = subscriptions::detect_subscriptions(&expenses, &payees, date("2024-03-25"));
+        assert_eq!(detected[0].name, "Gym");
+        assert_eq!(detected[0].cadence, Cadence::Weekly);
+        assert_eq!(detected[0].next_date, date("2024-03-29"));
+
+        // Two weekly charges missed means the membership has ended
+        let detected = subscriptions::detect_subscriptions(&expenses, &payees, date("2024-04-08"));
+        assert!(detected.is_empty());
+
+        // Irregular gaps are not a subscription
+        let irregular: Vec<Expense> = ["2024-01-01", "2024-01-09", "2024-03-20", "2024-03-24"]
+            .iter()
+            .map(|day| create_expense(0, day, 12.0, "Taxi"))
+            .collect();
+        assert!(
+            subscriptions::detect_subscriptions(&irregular, &[], date("2024-03-25")).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_schedules_catch_up_on_missed_dates() {
+        let expenses = vec![
+            create_expense(1, "2023-11-30", 5.0, "Cloud Storage"),
+            create_expense(2, "2023-12-31", 5.0, "Cloud Storage"),
+            create_expense(3, "2024-01-31", 5.0, "Cloud Storage"),
+        ];
+        let detected = subscriptions::detect_subscriptions(&expenses, &[], date("2024-02-01"));
+        let mut schedule = detected[0].to_schedule();
+        assert_eq!(schedule.next_date, "2024-02-29");
+        assert_eq!(schedule.anchor_date, "2024-01-31");
+        assert!(!detected[0].is_scheduled(&[]));
+        assert!(detected[0].is_scheduled(std::slice::from_ref(&schedule)));
+
+        // Charges stay on the last day of the month instead of drifting to the 29th
+        let (due, next) = subscriptions::due_dates(&schedule, date("2024-04-01")).unwrap();
+        assert_eq!(due, vec![date("2024-02-29"), date("2024-03-31")]);
+        assert_eq!(next, date("2024-04-30"));
+
+        schedule.next_date = next.to_string();
+        let (due, next) = subscriptions::due_dates(&schedule, date("2024-07-31")).unwrap();
+        assert_eq!(
+            due,
+            vec![
+                date("2024-04-30"),
+                date("2024-05-31"),
+                date("2024-06-30"),
+                date("2024-07-31")
+            ]
+        );
+        assert_eq!(next, date("2024-08-31"));
+    }
+
+    #[test]
+    fn test_schedules_are_stored() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn =
+            models::open_database(dir.path().join("schedules.db").to_str().unwrap()).unwrap();
+        models::create_expense_table(&conn).unwrap();
+
+        let expenses = vec![
+            create_expense(1, "2024-01-05", 9.99, "Streaming"),
+            create_expense(2, "2024-02-05", 9.99, "Streaming"),
+            create_expense(3, "2024-03-05", 9.99, "Streaming"),
+        ];
+        let detected = subscriptions::detect_subscriptions(&expenses, &[], date("2024-03-10"));
+        let schedule_id =
+            models::add_recurring_schedule(&conn, &detected[0].to_schedule()).unwrap();
+
+        let schedules = models::get_recurring_schedules(&conn).unwrap();
+        assert_eq!(schedules.len(), 1);
+        assert_eq!(schedules[0].cadence, Cadence::Monthly);
+        assert_eq!(schedules[0].next_date, "2024-04-05");
+        assert_eq!(schedules[0].anchor_date, "2024-03-05");
+
+        let charges = subscriptions::due_charges(&schedules, date("2024-05-10"));
+        assert_eq!(charges.len(), 1);
+        assert_eq!(charges[0].schedule_id, schedule_id);
+        assert_eq!(charges[0].next_date, "2024-06-05");
+        let dates: Vec<&str> = charges[0]
+            .expenses
+            .iter()
+            .map(|expense| expense.date.as_str())
+            .collect();
+        assert_eq!(dates, vec!["2024-04-05", "2024-05-05"]);
+
+        // A charge that fails to save leaves the schedule where it was
+        let mut failing = charges.clone();
+        failing[0].expenses[1].amount = f32::NAN;
+        assert!(models::record_due_charges(&conn, &failing).is_err());
+        assert!(models::get_expenses(&conn).unwrap().is_empty());
+        assert_eq!(
+            models::get_recurring_schedules(&conn).unwrap()[0].next_date,
+            "2024-04-05"
+        );
+
+        let ids = models::record_due_charges(&conn, &charges).unwrap();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(models::get_expenses(&conn).unwrap().len(), 2);
+        let schedules = models::get_recurring_schedules(&conn).unwrap();
+        assert_eq!(schedules[0].next_date, "2024-06-05");
+        assert!(subscriptions::due_charges(&schedules, date("2024-05-10")).is_empty());
+
+        models::delete_recurring_schedule(&conn, schedule_id).unwrap();
+        assert!(models::get_recurring_schedules(&conn).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use expense_tracker::tui::{self, Screen, TuiState};
+use std::collections::HashMap;
+
+fn press(state: &mut TuiState, code: KeyCode) {
+    state.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+}
+
+fn type_text(state: &mut TuiState, text: &str) {
+    for c in text.chars() {
+        press(state, KeyCode::Char(c));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::create_test_app;
+
+    #[test]
+    fn test_text_bar_chart_scales_to_largest_total() {
+        let totals = HashMap::from([("Food".to_string(), 100.0), ("Rent".to_string(), 300.0)]);
+        let lines = tui::text_bar_chart(&totals, 44);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("Rent"));
+        assert!(lines[0].contains("300.00"));
+        assert!(lines[0].contains("75.0%"));
+        let bar_length = |line: &str| line.chars().filter(|&c| c == '█').count();
+        assert_eq!(bar_length(&lines[0]), 18);
+        assert_eq!(bar_length(&lines[1]), 6);
+    }
+
+    #[test]
+    fn test_login_form_typing_and_signup_toggle() {
+        let mut state = TuiState::new(create_test_app());
+        assert_eq!(state.screen, Screen::Login);
+        type_text(&mut state, "alice");
+        press(&mut state, KeyCode::Tab);
+        type_text(&mut state, "pw1!");
+        press(&mut state, KeyCode::Backspace);
+        assert_eq!(state.app.username, "alice");
+        assert_eq!(state.app.password, "pw1");
+
+        press(&mut state, KeyCode::F(2));
+        assert_eq!(state.screen, Screen::Signup);
+        type_text(&mut state, "bob");
+        assert_eq!(state.app.new_username, "bob");
+    }
+
+    #[test]
+    fn test_add_form_fields_and_option_cycling() {
+        let mut app = create_test_app();
+        app.is_logged_in = true;
+        let mut state = TuiState::new(app);
+        assert_eq!(state.screen, Screen::Expenses);
+
+        press(&mut state, KeyCode::F(2));
+        assert_eq!(state.screen, Screen::AddExpense);
+        assert_eq!(state.app.expense_date.len(), 10);
+        type_text(&mut state, "Coffee");
+        press(&mut state, KeyCode::Tab);
+        type_text(&mut state, "3.50");
+        press(&mut state, KeyCode::Tab);
+        press(&mut state, KeyCode::Tab);
+        press(&mut state, KeyCode::Right);
+        assert_eq!(state.app.expense_name, "Coffee");
+        assert_eq!(state.app.expense_amount, "3.50");
+        assert_eq!(state.app.category, "Housing and Utilities");
+
+        // Cycling back past the first category returns to rule-based categorization
+        press(&mut state, KeyCode::Left);
+        assert_eq!(state.app.category, "");
+
+        press(&mut state, KeyCode::Tab);
+        press(&mut state, KeyCode::Right);
+        assert_eq!(state.app.payment_method, "Cash");
+        press(&mut state, KeyCode::Right);
+        assert_eq!(state.app.payment_method, "Card");
+    }
+
+    #[test]
+    fn test_escape_quits() {
+        let mut state = TuiState::new(create_test_app());
+        press(&mut state, KeyCode::Esc);
+        assert!(state.quit);
+    }
+}